@@ -0,0 +1,251 @@
+mod common;
+use {
+    crate::common::calculate_escrow_state_account_len,
+    crate::common::error::{test_error_to_program_error, TestError},
+    crate::common::fixture::{Fixture, Listing, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::spoof::spoofed_account,
+    crate::common::state::{Currency, State},
+    solana_program::pubkey::Pubkey,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{admin::ADMIN_LEN, error::EscrowError},
+    solana_sdk::{account::Account, signature::Signer},
+};
+
+const LISTING_FOR_FOUR: Listing = Listing {
+    max_renters: 4,
+    ..LISTING
+};
+
+enum Funding {
+    RentExempt,
+    // One lamport short of rent exemption
+    Underfunded,
+}
+
+// A zeroed account owned by the program, as the fixture would have created it, but with the given
+// size and balance. Written directly so that the runtime's own checks on `create_account` do not
+// stop the program from ever seeing it.
+fn state_account(len: usize, funding: Funding, test_state: &State) -> Account {
+    let mut account = spoofed_account(vec![0; len], &test_state.program_id, &test_state.bench.rent);
+    if let Funding::Underfunded = funding {
+        account.lamports -= 1;
+    }
+    account
+}
+
+// A rejected instruction must leave every account it was given as it was, so that no lamports
+// or tokens end up in an account nobody can use. Fee payers are left out.
+async fn assert_unchanged(
+    before: &[(Pubkey, Option<Account>)],
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    for (address, account) in before.iter() {
+        let after = test_state.bench.get_account(address).await;
+        if after != *account {
+            return Err(TestError::TestError(format!(
+                "{} changed after a rejected instruction",
+                address
+            )));
+        }
+    }
+    Ok(())
+}
+
+// An accepted instruction leaves the state account's rent-exempt balance in the account, and
+// costs the payer nothing beyond the transaction fee.
+async fn assert_lamports_kept(
+    state_account: &Account,
+    state_account_pubkey: &Pubkey,
+    payer_lamports: u64,
+    payer: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    test_state
+        .bench
+        .assert_lamports(state_account_pubkey, state_account.lamports)
+        .await;
+    let fee = test_state.bench.get_fee(&[], payer).await?;
+    test_state
+        .bench
+        .assert_lamports(payer, payer_lamports - fee)
+        .await;
+    Ok(())
+}
+
+async fn initialize_admin_state_with(
+    len: usize,
+    funding: Funding,
+) -> Result<Result<(), TestError>, TestError> {
+    let mut test_state = Fixture::new(LISTING_FOR_FOUR)
+        .renter_sol_token_amount(0)
+        .build(Stage::Created)
+        .await?;
+    let admin_state_account_pubkey = test_state.admin_state_account_keypair.pubkey();
+    let account = state_account(len, funding, &test_state);
+    test_state
+        .bench
+        .set_account(&admin_state_account_pubkey, &account)
+        .await;
+
+    let admin_pubkey = test_state.admin_keypair.pubkey();
+    let admin_lamports = test_state.bench.get_lamports(&admin_pubkey).await;
+    let before = test_state
+        .bench
+        .snapshot_accounts(&[admin_state_account_pubkey])
+        .await;
+    let result = initialize_admin_state(LISTING_FOR_FOUR.fee, &mut test_state).await;
+    if result.is_err() {
+        assert_unchanged(&before, &mut test_state).await?;
+    } else {
+        assert_lamports_kept(
+            &account,
+            &admin_state_account_pubkey,
+            admin_lamports,
+            &admin_pubkey,
+            &mut test_state,
+        )
+        .await?;
+    }
+    Ok(result)
+}
+
+async fn lend_with(
+    len: usize,
+    funding: Funding,
+) -> Result<(State, Result<(), TestError>), TestError> {
+    let mut test_state = Fixture::new(LISTING_FOR_FOUR)
+        .renter_sol_token_amount(0)
+        .build(Stage::AdminReady)
+        .await?;
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+    let account = state_account(len, funding, &test_state);
+    test_state
+        .bench
+        .set_account(&escrow_state_account_pubkey, &account)
+        .await;
+
+    let lender_pubkey = test_state.lender_keypair.pubkey();
+    let lender_lamports = test_state.bench.get_lamports(&lender_pubkey).await;
+    let before = test_state
+        .bench
+        .snapshot_accounts(&[
+            escrow_state_account_pubkey,
+            test_state.lender_temp_nft_account_keypair.pubkey(),
+            test_state.lender_token_account_pubkey(Currency::Sol),
+            test_state.pda_token_account_pubkey(Currency::Sol),
+            test_state.admin_state_account_keypair.pubkey(),
+        ])
+        .await;
+    let result = LISTING_FOR_FOUR.lend(Currency::Sol, &mut test_state).await;
+    if result.is_err() {
+        assert_unchanged(&before, &mut test_state).await?;
+    } else {
+        assert_lamports_kept(
+            &account,
+            &escrow_state_account_pubkey,
+            lender_lamports,
+            &lender_pubkey,
+            &mut test_state,
+        )
+        .await?;
+    }
+    Ok((test_state, result))
+}
+
+#[tokio::test]
+async fn test_account_size_admin_state() -> Result<(), TestError> {
+    tracing::info!("Initialize admin state in a rent-exempt account of ADMIN_LEN bytes");
+    let result = initialize_admin_state_with(ADMIN_LEN, Funding::RentExempt).await?;
+    result?;
+
+    tracing::info!("Initialize admin state in an account one byte too small");
+    let result = initialize_admin_state_with(ADMIN_LEN - 1, Funding::RentExempt).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    tracing::info!("Initialize admin state in an account one byte too large");
+    let result = initialize_admin_state_with(ADMIN_LEN + 1, Funding::RentExempt).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    tracing::info!("Initialize admin state in an account that is not rent-exempt");
+    let result = initialize_admin_state_with(ADMIN_LEN, Funding::Underfunded).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::NotRentExempt.into()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_account_size_escrow_state() -> Result<(), TestError> {
+    let escrow_state_account_len =
+        calculate_escrow_state_account_len(LISTING_FOR_FOUR.max_renters)?;
+
+    tracing::info!("Lend with a rent-exempt escrow state account sized for max_renters");
+    let (mut test_state, result) = lend_with(escrow_state_account_len, Funding::RentExempt).await?;
+    result?;
+    stop_lend(&mut test_state).await?;
+
+    tracing::info!("Lend with an escrow state account one byte too small");
+    let (_, result) = lend_with(escrow_state_account_len - 1, Funding::RentExempt).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    tracing::info!("Lend with an escrow state account one byte too large");
+    let (_, result) = lend_with(escrow_state_account_len + 1, Funding::RentExempt).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    tracing::info!("Lend with an escrow state account that is not rent-exempt");
+    let (_, result) = lend_with(escrow_state_account_len, Funding::Underfunded).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::NotRentExempt.into()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_account_size_escrow_state_max_renters_mismatch() -> Result<(), TestError> {
+    let escrow_state_account_len =
+        calculate_escrow_state_account_len(LISTING_FOR_FOUR.max_renters)?;
+    let fewer_renters_len = calculate_escrow_state_account_len(1)?;
+    let more_renters_len = calculate_escrow_state_account_len(LISTING_FOR_FOUR.max_renters * 4)?;
+    assert_ne!(fewer_renters_len, escrow_state_account_len);
+    assert_ne!(more_renters_len, escrow_state_account_len);
+
+    tracing::info!(
+        "Lend {} renters in an account sized for 1",
+        LISTING_FOR_FOUR.max_renters
+    );
+    let (_, result) = lend_with(fewer_renters_len, Funding::RentExempt).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    tracing::info!(
+        "Lend {} renters in an account sized for {}",
+        LISTING_FOR_FOUR.max_renters,
+        LISTING_FOR_FOUR.max_renters * 4
+    );
+    let (_, result) = lend_with(more_renters_len, Funding::RentExempt).await?;
+    assert_eq!(
+        test_error_to_program_error(result),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+mod common;
+use {
+    crate::common::async_rpc_renft::{
+        async_rpc_claim, async_rpc_initialize_admin_state, async_rpc_lend, async_rpc_rent,
+        async_rpc_rent_impl, async_rpc_set_payable_account, async_rpc_stop_rent,
+    },
+    crate::common::async_rpc_state::AsyncRpcState,
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::labels,
+    crate::common::rpc_client_utils::SendConfig,
+    crate::common::telemetry::{trace_file_path, trace_to_json_file_globally},
+    futures::stream::{self, StreamExt, TryStreamExt},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{admin, renting::Renting},
+    solana_sdk::signature::Signer,
+    solana_sdk::signer::keypair::Keypair,
+    std::sync::atomic::{AtomicUsize, Ordering},
+    tracing::Instrument,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(tarpaulin, ignore)]
+async fn test_async_integration_0() -> Result<(), TestError> {
+    // Renters run on every worker thread of the runtime, so the subscriber has to be global
+    trace_to_json_file_globally("test_async_integration_0")?;
+
+    let daily_rent_price: u64 = 2000;
+    let max_renters: u32 = 8000;
+    let max_rent_duration: u8 = 3;
+    // Each rental is an airdrop, a wrap and a rent, each confirmed before the next. Eight of them
+    // per CPU is several times what the rayon pool of `test_integration_0` keeps in flight, while
+    // not opening thousands of connections to the validator and its faucet at once, which then
+    // time out instead of measuring throughput.
+    let max_in_flight = 8 * std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+
+    let rent_amount: u16 = 1;
+    let rent_duration: u8 = 1;
+    let fee: u32 = 1000;
+    let compute_budget = ComputeBudget {
+        unit_price: Some(1000),
+        ..ComputeBudget::default()
+    };
+
+    let renter_sol_token_amount = daily_rent_price
+        .checked_mul((rent_duration as u64) * (rent_amount as u64))
+        .unwrap_or_default();
+    let test_state =
+        AsyncRpcState::initialize(max_renters, renter_sol_token_amount, SendConfig::default())
+            .await?;
+
+    tracing::info!("Initialize admin state");
+    async_rpc_initialize_admin_state(fee, &test_state).await?;
+
+    tracing::info!("Set payable account");
+    async_rpc_set_payable_account(
+        test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state,
+    )
+    .await?;
+
+    let mut token_accounts: [u8; admin::TOKEN_ACCOUNTS_LEN] = [0; admin::TOKEN_ACCOUNTS_LEN];
+    let admin_state_account_fee = test_state
+        .rpc_bench
+        .get_admin_state_account(
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut token_accounts,
+        )
+        .await?;
+    assert_eq!(admin_state_account_fee, fee);
+
+    tracing::info!("Start lending");
+    async_rpc_lend(
+        daily_rent_price,
+        max_renters,
+        max_rent_duration,
+        &test_state,
+    )
+    .await?;
+
+    let number_renters = AtomicUsize::new(0);
+
+    let rent_by_index = |i: usize| {
+        let test_state = &test_state;
+        let number_renters = &number_renters;
+        let compute_budget = &compute_budget;
+        async move {
+            tracing::info!("Start renting {}", i);
+            let renter_keypair = Keypair::new();
+            let renter_temp_sol_token_account_keypair = Keypair::new();
+            labels::register(&renter_keypair.pubkey(), &format!("renter_{}", i));
+            labels::register(
+                &renter_temp_sol_token_account_keypair.pubkey(),
+                &format!("renter_temp_sol_token_account_{}", i),
+            );
+            test_state
+                .rpc_bench
+                .airdrop(vec![renter_keypair.pubkey()])
+                .await?;
+
+            test_state
+                .rpc_bench
+                .wrap_sol(
+                    &renter_keypair,
+                    &renter_temp_sol_token_account_keypair,
+                    renter_sol_token_amount,
+                )
+                .await?;
+
+            async_rpc_rent_impl(
+                rent_amount,
+                rent_duration,
+                &renter_keypair,
+                &renter_temp_sol_token_account_keypair.pubkey(),
+                compute_budget,
+                test_state,
+            )
+            .await?;
+            number_renters.fetch_add(1, Ordering::SeqCst);
+            Ok::<(), TestError>(())
+        }
+        .instrument(tracing::info_span!("renter", index = i))
+    };
+    stream::iter(0_usize..(max_renters as usize))
+        .map(rent_by_index)
+        .buffer_unordered(max_in_flight)
+        .try_collect::<Vec<()>>()
+        .await
+        .map_err(|e| {
+            println!(
+                "Number of Renters: {}",
+                number_renters.load(Ordering::SeqCst)
+            );
+            println!(
+                "Transactions traced to {}",
+                trace_file_path("test_async_integration_0").display()
+            );
+            e
+        })?;
+    assert_eq!(number_renters.load(Ordering::SeqCst), max_renters as usize);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg_attr(tarpaulin, ignore)]
+async fn test_async_stop_rent_and_claim() -> Result<(), TestError> {
+    let daily_rent_price: u64 = 2000;
+    let max_renters: u32 = 1;
+    let rent_amount: u16 = 1;
+    let rent_duration: u8 = 1;
+    let prepaid_rent = daily_rent_price * (rent_amount as u64) * (rent_duration as u64);
+    let test_state =
+        AsyncRpcState::initialize(max_renters, prepaid_rent, SendConfig::default()).await?;
+
+    tracing::info!("Initialize admin state");
+    async_rpc_initialize_admin_state(1000, &test_state).await?;
+    tracing::info!("Set payable account");
+    async_rpc_set_payable_account(
+        test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state,
+    )
+    .await?;
+    tracing::info!("Start lending");
+    async_rpc_lend(daily_rent_price, max_renters, 3, &test_state).await?;
+    tracing::info!("Start renting");
+    async_rpc_rent(rent_amount, rent_duration, &test_state).await?;
+    let renting = test_state.find_valid_renting().await?;
+    assert_eq!(renting.rent_duration, rent_duration);
+
+    tracing::info!("Claiming an unexpired rent");
+    assert!(is_instruction_error(
+        &async_rpc_claim(
+            &test_state.renter_keypair.pubkey(),
+            renting.rented_at,
+            &test_state
+        )
+        .await
+    ));
+    assert!(test_state.find_valid_renting().await? == renting);
+
+    tracing::info!("Stop renting");
+    async_rpc_stop_rent(renting.rented_at, &test_state).await?;
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+    let pda_sol_token_account = test_state
+        .rpc_bench
+        .get_token_account(&test_state.pda_sol_token_account_keypair.pubkey())
+        .await?;
+    assert_eq!(pda_sol_token_account.amount, 0);
+
+    Ok(())
+}
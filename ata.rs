@@ -0,0 +1,125 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::TestError,
+    crate::common::fee::get_fee_split_with_currency,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::state::Currency,
+    solana_program_test::tokio,
+    solana_sdk::signature::Signer,
+    spl_associated_token_account::get_associated_token_address,
+};
+const CURRENCIES: [Currency; 2] = [Currency::Sol, Currency::Usdc];
+
+fn fixture(currency: Currency) -> Result<Fixture, TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    Ok(Fixture::new(LISTING)
+        .associated_token_accounts()
+        .currency(currency)
+        .payable_currencies(&CURRENCIES)
+        .renter_sol_token_amount(prepaid_rent)
+        .renter_usdc_token_amount(prepaid_rent))
+}
+
+#[tokio::test]
+async fn test_ata_fixture() -> Result<(), TestError> {
+    let mut test_state = fixture(Currency::Sol)?.build(Stage::AdminReady).await?;
+
+    for currency in CURRENCIES.iter() {
+        let token_pubkey = test_state.token_pubkey(*currency);
+        for owner in [
+            clone_keypair(&test_state.admin_keypair),
+            clone_keypair(&test_state.lender_keypair),
+            clone_keypair(&test_state.renter_keypair),
+        ]
+        .iter()
+        {
+            let address = get_associated_token_address(&owner.pubkey(), &token_pubkey);
+            let token_account = test_state.bench.get_token_account(&address).await?;
+            assert_eq!(token_account.mint, token_pubkey);
+            assert_eq!(token_account.owner, owner.pubkey());
+        }
+        assert_eq!(
+            test_state.lender_token_account_pubkey(*currency),
+            get_associated_token_address(&test_state.lender_keypair.pubkey(), &token_pubkey)
+        );
+    }
+
+    let lender_main_nft_account = test_state
+        .bench
+        .get_token_account(&test_state.lender_main_nft_account_pubkey())
+        .await?;
+    assert_eq!(
+        lender_main_nft_account.mint,
+        test_state.nft_keypair.pubkey()
+    );
+    assert_eq!(lender_main_nft_account.amount, 0);
+
+    // The keypair accounts are never created in this mode
+    assert!(test_state
+        .bench
+        .get_account(&test_state.lender_sol_token_account_keypair.pubkey())
+        .await
+        .is_none());
+
+    tracing::info!("Creating an existing associated token account");
+    let lender_sol_token_account_pubkey = test_state.lender_token_account_pubkey(Currency::Sol);
+    let lender_sol_token_account = test_state
+        .bench
+        .get_account(&lender_sol_token_account_pubkey)
+        .await;
+    assert!(lender_sol_token_account.is_some());
+    assert!(test_state
+        .bench
+        .create_associated_token_account(
+            test_state.sol_token_pubkey,
+            &clone_keypair(&test_state.lender_keypair),
+        )
+        .await
+        .is_err());
+    assert_eq!(
+        test_state
+            .bench
+            .get_account(&lender_sol_token_account_pubkey)
+            .await,
+        lender_sol_token_account
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ata_lifecycle() -> Result<(), TestError> {
+    for currency in CURRENCIES.iter() {
+        let mut test_state = fixture(*currency)?.build(Stage::Rented).await?;
+        let rented_at = test_state.find_valid_renting().await?.rented_at;
+        test_state
+            .bench
+            .set_unix_timestamp(LISTING.expires_at(rented_at))
+            .await?;
+
+        tracing::info!("Claim rent in {:?}", currency);
+        claim_with_currency(
+            &test_state.renter_keypair.pubkey(),
+            rented_at,
+            *currency,
+            &mut test_state,
+        )
+        .await?;
+        assert_eq!(
+            get_fee_split_with_currency(*currency, &mut test_state).await?,
+            LISTING.fee_split()?
+        );
+
+        tracing::info!("Stop lending in {:?}", currency);
+        stop_lend_with_currency(*currency, &mut test_state).await?;
+        let lender_main_nft_account = test_state
+            .bench
+            .get_token_account(&test_state.lender_main_nft_account_pubkey())
+            .await?;
+        assert_eq!(lender_main_nft_account.amount, LISTING.max_renters as u64);
+    }
+
+    Ok(())
+}
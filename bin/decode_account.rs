@@ -0,0 +1,205 @@
+// Decodes an escrow or admin state account of solana_renft_collateral_free, for support to
+// inspect stuck rentals.
+//
+//   decode_account --base64 <data>
+//   decode_account --file <path>
+//   decode_account --pubkey <address> [--url <rpc url>]
+//
+// The payable account of an admin account is listed for SOL and for every `--mint <address>`.
+//
+// A binary cannot use the dev-dependencies of the test harness, so it does not share
+// `common::decode` and is registered on its own in the package manifest:
+//
+//   [[bin]]
+//   name = "decode_account"
+//   path = "tests/bin/decode_account.rs"
+//
+// with base64, chrono, arrayref, spl-token and solana-client as dependencies.
+use {
+    arrayref::{array_ref, array_refs},
+    chrono::{TimeZone, Utc},
+    solana_client::rpc_client::RpcClient,
+    solana_program::{clock::SECONDS_PER_DAY, pubkey::Pubkey},
+    solana_renft_collateral_free::{
+        admin::{self, ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
+        map::Bucket,
+        renting::Renting,
+        state::Escrow,
+        util::calculate_rentings_size,
+    },
+    std::{error::Error, fs, process, str::FromStr},
+};
+
+const DEFAULT_RPC_URL: &str = "http://localhost:8899";
+
+enum Source {
+    Base64(String),
+    File(String),
+    Pubkey(Pubkey),
+}
+
+struct Args {
+    source: Source,
+    url: String,
+    mints: Vec<Pubkey>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: decode_account (--base64 <data> | --file <path> | --pubkey <address> [--url <rpc url>]) [--mint <address>]..."
+    );
+    process::exit(2);
+}
+
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut source = None;
+    let mut url = DEFAULT_RPC_URL.to_string();
+    let mut mints = vec![spl_token::native_mint::id()];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--base64" => source = Some(Source::Base64(value)),
+            "--file" => source = Some(Source::File(value)),
+            "--pubkey" => source = Some(Source::Pubkey(Pubkey::from_str(&value)?)),
+            "--url" => url = value,
+            "--mint" => mints.push(Pubkey::from_str(&value)?),
+            _ => usage(),
+        }
+    }
+    Ok(Args {
+        source: source.unwrap_or_else(|| usage()),
+        url,
+        mints,
+    })
+}
+
+fn read_data(args: &Args) -> Result<Vec<u8>, Box<dyn Error>> {
+    match &args.source {
+        Source::Base64(data) => Ok(base64::decode(data.trim())?),
+        Source::File(path) => Ok(fs::read(path)?),
+        Source::Pubkey(address) => {
+            let account = RpcClient::new(args.url.clone()).get_account(address)?;
+            println!("address: {}", address);
+            println!("owner: {}", account.owner);
+            println!("lamports: {}", account.lamports);
+            Ok(account.data)
+        }
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_else(|| format!("{} (out of range)", timestamp))
+}
+
+fn print_admin(data: &[u8], mints: &[Pubkey]) {
+    let src = array_ref![data, 0, ADMIN_LEN];
+    let (token_accounts, fee, is_initialized) = array_refs![src, TOKEN_ACCOUNTS_LEN, 4, 1];
+    println!("admin state account ({} bytes)", data.len());
+    println!("  is_initialized: {}", is_initialized[0] != 0);
+    println!("  fee: {} bps", u32::from_le_bytes(*fee));
+    for mint in mints.iter() {
+        match admin::get(mint, token_accounts) {
+            Ok(account) => println!("  payable account for {}: {}", mint, account),
+            Err(_) => println!("  payable account for {}: none", mint),
+        }
+    }
+}
+
+fn print_renting(renting: &Renting) {
+    // The data may come from anywhere, so the end of the rental is not trusted to fit an i64
+    let ends_at = (renting.rent_duration as i64)
+        .checked_mul(SECONDS_PER_DAY as i64)
+        .and_then(|duration| renting.rented_at.checked_add(duration));
+    println!("  renting");
+    println!("    renter: {}", renting.renter_address);
+    println!(
+        "    rented_at: {} ({})",
+        format_timestamp(renting.rented_at),
+        renting.rented_at
+    );
+    println!("    duration: {} days", renting.rent_duration);
+    match ends_at {
+        Some(ends_at) => println!("    ends_at: {}", format_timestamp(ends_at)),
+        None => println!("    ends_at: out of range"),
+    }
+    println!("    amount: {}", renting.rent_amount);
+}
+
+// Same checks as `common::decode`: the data only reaches `Escrow::unpack` once its length is the
+// header followed by whole buckets, and an initialized escrow must be sized for its max_renters.
+fn print_escrow(data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let data_len = data.len();
+    if data_len < Escrow::LEN || (data_len - Escrow::LEN) % Bucket::BUCKET_BUFFER_LEN != 0 {
+        return Err(format!(
+            "{} bytes is neither an admin nor an escrow state account",
+            data_len
+        )
+        .into());
+    }
+    let mut data = data.to_vec();
+    let mut escrow_info = Escrow::new();
+    let escrow_info = Escrow::unpack(&mut data, &mut escrow_info)?;
+    if escrow_info.is_initialized {
+        let rentings_size = calculate_rentings_size(escrow_info.max_renters)? as usize;
+        let expected_len = rentings_size
+            .checked_mul(Bucket::BUCKET_BUFFER_LEN)
+            .and_then(|len| len.checked_add(Escrow::LEN));
+        if expected_len != Some(data_len) {
+            return Err(format!(
+                "{} bytes is not the length of an escrow state account for {} renters",
+                data_len, escrow_info.max_renters
+            )
+            .into());
+        }
+    }
+
+    println!("escrow state account ({} bytes)", data_len);
+    println!("  is_initialized: {}", escrow_info.is_initialized);
+    println!("  lender: {}", escrow_info.lender_pubkey);
+    println!(
+        "  temp_nft_account: {}",
+        escrow_info.temp_nft_account_pubkey
+    );
+    println!(
+        "  lender_token_account: {}",
+        escrow_info.lender_token_account_pubkey
+    );
+    println!(
+        "  pda_token_account: {}",
+        escrow_info.pda_token_account_pubkey
+    );
+    println!("  daily_rent_price: {}", escrow_info.daily_rent_price);
+    println!("  max_renters: {}", escrow_info.max_renters);
+    println!(
+        "  max_rent_duration: {} days",
+        escrow_info.max_rent_duration
+    );
+    println!("  current_renters: {}", escrow_info.current_renters);
+    println!(
+        "  rentings: length {}, capacity {}, size {}",
+        escrow_info.rentings.length, escrow_info.rentings.capacity, escrow_info.rentings.size
+    );
+    for i in 0..escrow_info.rentings.size {
+        let renting = escrow_info.rentings.get_renting(i as usize)?;
+        if renting.is_valid() {
+            print_renting(&renting);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args()?;
+    let data = read_data(&args)?;
+    // Admin accounts have a fixed size, escrow accounts grow with max_renters
+    if data.len() == ADMIN_LEN {
+        print_admin(&data, &args.mints);
+        Ok(())
+    } else {
+        print_escrow(&data)
+    }
+}
@@ -0,0 +1,180 @@
+use {
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::coverage,
+    crate::common::error::TestError,
+    crate::common::rpc_client_utils::SendConfig,
+    crate::common::telemetry::{record_outcome, transaction_span},
+    crate::common::{unpack_admin_state, wrap_sol_instructions},
+    futures::future::try_join_all,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_program::{
+        instruction::Instruction, native_token::LAMPORTS_PER_SOL, program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_renft_collateral_free::admin::TOKEN_ACCOUNTS_LEN,
+    solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction},
+    std::time::Instant,
+    tracing::field,
+};
+
+// Counterpart of `RpcBench` on the nonblocking client, so that many transactions can be in
+// flight on one runtime instead of one OS thread each. It only has what the renters of a load test
+// need, the fixture itself is set up by `RpcState`.
+pub struct AsyncRpcBench {
+    pub rpc_client: RpcClient,
+    pub send_config: SendConfig,
+}
+
+impl AsyncRpcBench {
+    pub fn start_new(rpc_client: RpcClient, send_config: SendConfig) -> Self {
+        Self {
+            rpc_client,
+            send_config,
+        }
+    }
+
+    pub async fn process_transaction(
+        &self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+    ) -> Result<(), TestError> {
+        self.process_transaction_with_compute_budget(
+            instructions,
+            payer,
+            signers,
+            &ComputeBudget::default(),
+        )
+        .await
+    }
+
+    pub async fn process_transaction_with_compute_budget(
+        &self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+        compute_budget: &ComputeBudget,
+    ) -> Result<(), TestError> {
+        let span = transaction_span(instructions, payer);
+        let started = Instant::now();
+        let instructions = compute_budget.prepend_to(instructions);
+        let mut transaction = Transaction::new_with_payer(&instructions, payer);
+
+        let recent_blockhash = self
+            .rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        transaction.sign(&Vec::from(signers), recent_blockhash);
+        span.record("signature", &field::display(transaction.signatures[0]));
+
+        // The span is not entered, a guard must not be held across an await
+        let result = self
+            .rpc_client
+            .send_and_confirm_transaction_with_spinner_and_config(
+                &transaction,
+                self.send_config.commitment,
+                self.send_config.rpc_send_transaction_config(),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)));
+        let slot = self
+            .rpc_client
+            .get_signature_statuses(&transaction.signatures[..1])
+            .await
+            .ok()
+            .and_then(|statuses| statuses.value.into_iter().next().flatten())
+            .map(|status| status.slot);
+        record_outcome(&span, started, slot, &result);
+        coverage::record(&instructions, &result);
+
+        result
+    }
+
+    pub async fn airdrop(&self, addresses: Vec<Pubkey>) -> Result<(), TestError> {
+        try_join_all(addresses.iter().map(|address| async move {
+            let recent_hash = self
+                .rpc_client
+                .get_latest_blockhash()
+                .await
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+            let signature = self
+                .rpc_client
+                .request_airdrop_with_blockhash(address, LAMPORTS_PER_SOL * 5, &recent_hash)
+                .await
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+            self.rpc_client
+                .confirm_transaction_with_spinner(
+                    &signature,
+                    &recent_hash,
+                    self.send_config.commitment,
+                )
+                .await
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+        }))
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn wrap_sol(
+        &self,
+        owner: &Keypair,
+        wrapped_sol_account: &Keypair,
+        lamports: u64,
+    ) -> Result<(), TestError> {
+        let rent = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::get_packed_len())
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        let instructions = wrap_sol_instructions(
+            &owner.pubkey(),
+            &wrapped_sol_account.pubkey(),
+            rent + lamports,
+        )?;
+
+        self.process_transaction(
+            &instructions,
+            Some(&owner.pubkey()),
+            &[owner, wrapped_sol_account],
+        )
+        .await
+    }
+
+    pub async fn get_account(&self, address: &Pubkey) -> Option<Account> {
+        self.rpc_client.get_account(address).await.ok()
+    }
+
+    pub async fn get_admin_state_account(
+        &self,
+        address: &Pubkey,
+        token_accounts: &mut [u8; TOKEN_ACCOUNTS_LEN],
+    ) -> Result<u32, TestError> {
+        let admin_state_account = self
+            .get_account(address)
+            .await
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        Ok(unpack_admin_state(
+            &admin_state_account.data,
+            token_accounts,
+        ))
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_token_account(
+        &self,
+        address: &Pubkey,
+    ) -> Result<spl_token::state::Account, TestError> {
+        let account = self
+            .get_account(address)
+            .await
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        spl_token::state::Account::unpack(&account.data)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+    }
+}
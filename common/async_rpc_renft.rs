@@ -0,0 +1,190 @@
+use {
+    crate::common::async_rpc_state::AsyncRpcState,
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::error::TestError,
+    crate::common::renft::{
+        claim_instruction, initialize_admin_state_instruction, lend_instruction, rent_instruction,
+        set_payable_account_instruction, stop_rent_instruction,
+    },
+    solana_program::pubkey::Pubkey,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+};
+
+pub async fn async_rpc_lend(
+    daily_rent_price: u64,
+    max_renters: u32,
+    max_rent_duration: u8,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    let instruction = lend_instruction(
+        daily_rent_price,
+        max_renters,
+        max_rent_duration,
+        &test_state.lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &test_state.lender_sol_token_account_keypair.pubkey(),
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .rpc_bench
+        .process_transaction(
+            &[instruction],
+            Some(&test_state.lender_keypair.pubkey()),
+            &[&test_state.lender_keypair],
+        )
+        .await
+}
+
+#[allow(dead_code)]
+pub async fn async_rpc_rent(
+    rent_amount: u16,
+    rent_duration: u8,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    async_rpc_rent_impl(
+        rent_amount,
+        rent_duration,
+        &test_state.renter_keypair,
+        &test_state.renter_temp_sol_token_account_keypair.pubkey(),
+        &ComputeBudget::default(),
+        test_state,
+    )
+    .await
+}
+
+pub async fn async_rpc_rent_impl(
+    rent_amount: u16,
+    rent_duration: u8,
+    renter_keypair: &Keypair,
+    renter_temp_sol_token_account_pubkey: &Pubkey,
+    compute_budget: &ComputeBudget,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    let instruction = rent_instruction(
+        rent_amount,
+        rent_duration,
+        &renter_keypair.pubkey(),
+        renter_temp_sol_token_account_pubkey,
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .rpc_bench
+        .process_transaction_with_compute_budget(
+            &[instruction],
+            Some(&renter_keypair.pubkey()),
+            &[renter_keypair],
+            compute_budget,
+        )
+        .await
+}
+
+#[allow(dead_code)]
+pub async fn async_rpc_stop_rent(
+    rented_at: i64,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    let instruction = stop_rent_instruction(
+        rented_at,
+        &test_state.renter_keypair.pubkey(),
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.renter_sol_token_account_keypair.pubkey(),
+        &test_state.lender_sol_token_account_keypair.pubkey(),
+        &test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .rpc_bench
+        .process_transaction(
+            &[instruction],
+            Some(&test_state.renter_keypair.pubkey()),
+            &[&test_state.renter_keypair],
+        )
+        .await
+}
+
+#[allow(dead_code)]
+pub async fn async_rpc_claim(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    let instruction = claim_instruction(
+        renter_address,
+        rented_at,
+        &test_state.lender_keypair.pubkey(),
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.lender_sol_token_account_keypair.pubkey(),
+        &test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .rpc_bench
+        .process_transaction(
+            &[instruction],
+            Some(&test_state.lender_keypair.pubkey()),
+            &[&test_state.lender_keypair],
+        )
+        .await
+}
+
+pub async fn async_rpc_initialize_admin_state(
+    fee: u32,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    let instruction = initialize_admin_state_instruction(
+        fee,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .rpc_bench
+        .process_transaction(
+            &[instruction],
+            Some(&test_state.admin_keypair.pubkey()),
+            &[&test_state.admin_keypair],
+        )
+        .await
+}
+
+pub async fn async_rpc_set_payable_account(
+    admin_token_account: Pubkey,
+    test_state: &AsyncRpcState,
+) -> Result<(), TestError> {
+    let instruction = set_payable_account_instruction(
+        admin_token_account,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .rpc_bench
+        .process_transaction(
+            &[instruction],
+            Some(&test_state.admin_keypair.pubkey()),
+            &[&test_state.admin_keypair],
+        )
+        .await
+}
@@ -0,0 +1,124 @@
+use {
+    crate::common::{
+        async_rpc_bench::AsyncRpcBench, error::TestError, find_valid_renting,
+        progress::LineProgress, rpc_client_utils::SendConfig, rpc_state::RpcState,
+    },
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_program::pubkey::Pubkey,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+};
+
+// `RpcState` on `AsyncRpcBench`. The fixture is set up by `RpcState` itself, only the bench is
+// swapped afterwards, so both go through the same steps and register the same labels.
+pub struct AsyncRpcState {
+    pub rpc_bench: AsyncRpcBench,
+    pub program_id: Pubkey,
+    pub minter_keypair: Keypair,
+    pub admin_keypair: Keypair,
+    pub lender_keypair: Keypair,
+    pub renter_keypair: Keypair,
+    pub admin_state_account_keypair: Keypair,
+    pub escrow_state_account_keypair: Keypair,
+    pub x_token_keypair: Keypair,
+    pub payer_sol_token_account_keypair: Keypair,
+    pub admin_sol_token_account_keypair: Keypair,
+    pub lender_sol_token_account_keypair: Keypair,
+    pub renter_sol_token_account_keypair: Keypair,
+    pub renter_temp_sol_token_account_keypair: Keypair,
+    pub pda_sol_token_account_keypair: Keypair,
+    pub nft_keypair: Keypair,
+    pub lender_temp_nft_account_keypair: Keypair,
+    pub lender_main_nft_account_keypair: Keypair,
+    pub pda_pubkey: Pubkey,
+    pub pda_bump_seed: u8,
+    pub sol_token_pubkey: Pubkey,
+}
+
+impl AsyncRpcState {
+    pub async fn initialize(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        send_config: SendConfig,
+    ) -> Result<Self, TestError> {
+        // The blocking client must neither run nor be dropped on a runtime thread
+        tokio::task::spawn_blocking(move || {
+            RpcState::initialize_impl(
+                max_renters,
+                renter_temp_sol_token_amount,
+                send_config,
+                &LineProgress,
+            )
+            .map(Self::from_rpc_state)
+        })
+        .await
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?
+    }
+
+    fn from_rpc_state(rpc_state: RpcState) -> Self {
+        let RpcState {
+            rpc_bench,
+            program_id,
+            minter_keypair,
+            admin_keypair,
+            lender_keypair,
+            renter_keypair,
+            admin_state_account_keypair,
+            escrow_state_account_keypair,
+            x_token_keypair,
+            payer_sol_token_account_keypair,
+            admin_sol_token_account_keypair,
+            lender_sol_token_account_keypair,
+            renter_sol_token_account_keypair,
+            renter_temp_sol_token_account_keypair,
+            pda_sol_token_account_keypair,
+            nft_keypair,
+            lender_temp_nft_account_keypair,
+            lender_main_nft_account_keypair,
+            pda_pubkey,
+            pda_bump_seed,
+            sol_token_pubkey,
+            trace: _,
+        } = rpc_state;
+
+        let rpc_client = RpcClient::new_with_commitment(
+            rpc_bench.rpc_client.url(),
+            rpc_bench.send_config.commitment,
+        );
+
+        Self {
+            rpc_bench: AsyncRpcBench::start_new(rpc_client, rpc_bench.send_config),
+            program_id,
+            minter_keypair,
+            admin_keypair,
+            lender_keypair,
+            renter_keypair,
+            admin_state_account_keypair,
+            escrow_state_account_keypair,
+            x_token_keypair,
+            payer_sol_token_account_keypair,
+            admin_sol_token_account_keypair,
+            lender_sol_token_account_keypair,
+            renter_sol_token_account_keypair,
+            renter_temp_sol_token_account_keypair,
+            pda_sol_token_account_keypair,
+            nft_keypair,
+            lender_temp_nft_account_keypair,
+            lender_main_nft_account_keypair,
+            pda_pubkey,
+            pda_bump_seed,
+            sol_token_pubkey,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn find_valid_renting(&self) -> Result<Renting, TestError> {
+        let account = self
+            .rpc_bench
+            .get_account(&self.escrow_state_account_keypair.pubkey())
+            .await
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        find_valid_renting(&account.data)
+    }
+}
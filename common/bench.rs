@@ -1,22 +1,34 @@
 use {
     crate::common::calculate_escrow_state_account_len,
     crate::common::clone_keypair,
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::coverage,
     crate::common::error::TestError,
-    arrayref::{array_ref, array_refs},
+    crate::common::labels::Labeled,
+    crate::common::simulation::{writable_accounts, Simulation},
+    crate::common::telemetry::{record_outcome, transaction_span},
+    crate::common::token_2022::{
+        self, get_mint_len, get_token_account_len, unpack_token_account, MintExtension,
+    },
+    crate::common::{unpack_admin_state, wrap_sol_instructions},
     bincode::deserialize,
     solana_program::system_program,
     solana_program::{
         clock::Clock, instruction::Instruction, native_token::LAMPORTS_PER_SOL, program_pack::Pack,
         pubkey::Pubkey, rent::Rent, system_instruction, sysvar,
     },
-    solana_program_test::{ProgramTest, ProgramTestContext},
+    solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext},
     solana_renft_collateral_free::admin::{ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
     solana_sdk::{
-        account::Account, account::AccountSharedData, clock::UnixTimestamp,
+        account::Account, account::AccountSharedData, clock::UnixTimestamp, message::Message,
         program_option::COption, signature::Keypair, signer::Signer, transaction::Transaction,
     },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account,
+    },
     spl_token::state::Mint,
-    std::borrow::Borrow,
+    std::{borrow::Borrow, time::Instant},
+    tracing::field,
 };
 
 pub struct ProgramTestBench {
@@ -47,6 +59,59 @@ impl ProgramTestBench {
         payer: Option<&Pubkey>,
         signers: &[&Keypair],
     ) -> Result<(), TestError> {
+        self.process_transaction_with_compute_budget(
+            instructions,
+            payer,
+            signers,
+            &ComputeBudget::default(),
+        )
+        .await
+    }
+
+    pub async fn process_transaction_with_compute_budget(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+        compute_budget: &ComputeBudget,
+    ) -> Result<(), TestError> {
+        let span = transaction_span(instructions, payer);
+        let started = Instant::now();
+        let instructions = compute_budget.prepend_to(instructions);
+        let mut transaction = Transaction::new_with_payer(&instructions, payer);
+
+        let recent_blockhash = self
+            .context
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .unwrap();
+
+        transaction.sign(&Vec::from(signers), recent_blockhash);
+        span.record("signature", &field::display(transaction.signatures[0]));
+
+        let result = self
+            .context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)));
+        let slot = self.get_clock().await.slot;
+        record_outcome(&span, started, Some(slot), &result);
+        coverage::record(&instructions, &result);
+
+        result
+    }
+
+    // BanksClient reports the result, logs and compute units of a simulation but not the
+    // resulting accounts. A transaction that would land is replayed on the bank to read its
+    // writable accounts, which are then put back as they were, so nothing is committed.
+    pub async fn simulate_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+    ) -> Result<Simulation, TestError> {
         let mut transaction = Transaction::new_with_payer(instructions, payer);
 
         let recent_blockhash = self
@@ -58,13 +123,57 @@ impl ProgramTestBench {
 
         transaction.sign(&Vec::from(signers), recent_blockhash);
 
+        let addresses = writable_accounts(&transaction.message);
+        let snapshots = self.snapshot_accounts(&addresses).await;
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction.clone())
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        let result = simulation
+            .result
+            .ok_or(TestError::TestError(
+                "Simulation returned no result".to_string(),
+            ))?
+            .map_err(|e| TestError::UnexpectedError(Box::new(BanksClientError::from(e))));
+        let (logs, units_consumed) = simulation
+            .simulation_details
+            .map(|details| (details.logs, details.units_consumed))
+            .unwrap_or_default();
+        if result.is_err() {
+            return Ok(Simulation {
+                result,
+                logs,
+                units_consumed,
+                accounts: snapshots,
+            });
+        }
+
         self.context
             .banks_client
             .process_transaction(transaction)
             .await
             .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        let accounts = self.snapshot_accounts(&addresses).await;
+        for (address, account) in snapshots.iter() {
+            // An account that did not exist is put back as an empty one, which the bank drops
+            let account = account.clone().unwrap_or_default();
+            self.set_account(address, &account).await;
+        }
+        // The replayed transaction is in the status cache, so the real send has to be signed
+        // with another blockhash or it would be rejected as already processed
+        self.context
+            .get_new_latest_blockhash()
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
 
-        Ok(())
+        Ok(Simulation {
+            result,
+            logs,
+            units_consumed,
+            accounts,
+        })
     }
 
     pub async fn create_mint(
@@ -172,7 +281,7 @@ impl ProgramTestBench {
             if !(account_data.owner == system_program::id() && system_account_ok) {
                 return Err(TestError::TestError(format!(
                     "Error: Account already exists: {}",
-                    account
+                    Labeled(&account)
                 )));
             }
         }
@@ -183,6 +292,31 @@ impl ProgramTestBench {
         Ok(account)
     }
 
+    pub async fn create_associated_token_account(
+        &mut self,
+        token: Pubkey,
+        owner: &Keypair,
+    ) -> Result<Pubkey, TestError> {
+        let account = get_associated_token_address(&owner.pubkey(), &token);
+        if self.get_account(&account).await.is_some() {
+            return Err(TestError::TestError(format!(
+                "Error: Account already exists: {}",
+                Labeled(&account)
+            )));
+        }
+
+        let instructions = vec![create_associated_token_account(
+            &owner.pubkey(),
+            &owner.pubkey(),
+            &token,
+            &spl_token::id(),
+        )];
+        self.process_transaction(&instructions, Some(&owner.pubkey()), &[owner])
+            .await?;
+
+        Ok(account)
+    }
+
     pub async fn mint_tokens(
         &mut self,
         token_account: Pubkey,
@@ -213,6 +347,35 @@ impl ProgramTestBench {
         Ok(())
     }
 
+    pub async fn mint_tokens_with_address(
+        &mut self,
+        token_account: Pubkey,
+        token_mint: &Pubkey,
+        token_mint_authority: &Keypair,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<(), TestError> {
+        let signers = [token_mint_authority];
+        let instructions = vec![spl_token::instruction::mint_to_checked(
+            &spl_token::id(),
+            token_mint,
+            &token_account,
+            &token_mint_authority.pubkey(),
+            &[&token_mint_authority.pubkey()],
+            amount,
+            decimals,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?];
+
+        self.process_transaction(
+            &instructions,
+            Some(&token_mint_authority.pubkey()),
+            &signers,
+        )
+        .await?;
+        Ok(())
+    }
+
     pub async fn create_admin_state_account(
         &mut self,
         admin: &Keypair,
@@ -244,7 +407,7 @@ impl ProgramTestBench {
         program_id: Pubkey,
     ) -> Result<Keypair, TestError> {
         let escrow_state_account = Keypair::new();
-        let escrow_state_account_len = calculate_escrow_state_account_len(max_renters);
+        let escrow_state_account_len = calculate_escrow_state_account_len(max_renters)?;
 
         let create_escrow_state_acc_ix = system_instruction::create_account(
             &lender.pubkey(),
@@ -309,24 +472,13 @@ impl ProgramTestBench {
         wrapped_sol_account: &Keypair,
         lamports: u64,
     ) -> Result<(), TestError> {
-        let instructions = vec![
-            system_instruction::create_account(
-                &owner.pubkey(),
-                &wrapped_sol_account.pubkey(),
-                self.rent
-                    .minimum_balance(spl_token::state::Account::get_packed_len())
-                    + lamports,
-                spl_token::state::Account::get_packed_len() as u64,
-                &spl_token::id(),
-            ),
-            spl_token::instruction::initialize_account(
-                &spl_token::id(),
-                &wrapped_sol_account.pubkey(),
-                &spl_token::native_mint::id(),
-                &owner.pubkey(),
-            )
-            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
-        ];
+        let instructions = wrap_sol_instructions(
+            &owner.pubkey(),
+            &wrapped_sol_account.pubkey(),
+            self.rent
+                .minimum_balance(spl_token::state::Account::get_packed_len())
+                + lamports,
+        )?;
 
         let signers = [owner, wrapped_sol_account];
         self.process_transaction(
@@ -338,6 +490,98 @@ impl ProgramTestBench {
         Ok(())
     }
 
+    pub async fn top_up_wrapped_sol(
+        &mut self,
+        owner: &Keypair,
+        wrapped_sol_account: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), TestError> {
+        let instructions = vec![
+            system_instruction::transfer(&owner.pubkey(), wrapped_sol_account, lamports),
+            spl_token::instruction::sync_native(&spl_token::id(), wrapped_sol_account)
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+        ];
+
+        self.process_transaction(&instructions, Some(&owner.pubkey()), &[owner])
+            .await?;
+        Ok(())
+    }
+
+    // The bench payer pays for the transaction so that the lamports released to `destination`
+    // can be asserted exactly.
+    pub async fn close_token_account(
+        &mut self,
+        owner: &Keypair,
+        token_account: &Pubkey,
+        destination: &Pubkey,
+    ) -> Result<(), TestError> {
+        let payer = clone_keypair(&self.payer);
+        let instructions = vec![spl_token::instruction::close_account(
+            &spl_token::id(),
+            token_account,
+            destination,
+            &owner.pubkey(),
+            &[],
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?];
+
+        self.process_transaction(&instructions, Some(&payer.pubkey()), &[&payer, owner])
+            .await?;
+        Ok(())
+    }
+
+    // Closing a wrapped SOL account releases both its token amount and its rent-exempt
+    // reserve as native SOL.
+    pub async fn unwrap_sol(
+        &mut self,
+        owner: &Keypair,
+        wrapped_sol_account: &Pubkey,
+    ) -> Result<u64, TestError> {
+        let lamports = self.get_lamports(wrapped_sol_account).await;
+        self.close_token_account(owner, wrapped_sol_account, &owner.pubkey())
+            .await?;
+        Ok(lamports)
+    }
+
+    pub async fn get_lamports(&mut self, address: &Pubkey) -> u64 {
+        self.get_account(address)
+            .await
+            .map(|account| account.lamports)
+            .unwrap_or_default()
+    }
+
+    pub async fn assert_lamports(&mut self, address: &Pubkey, expected: u64) {
+        assert_eq!(
+            self.get_lamports(address).await,
+            expected,
+            "Lamport balance of {}",
+            address
+        );
+    }
+
+    // Fee the bank charges for a message with these instructions, without any ComputeBudget
+    // instruction prepended
+    pub async fn get_fee(
+        &mut self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> Result<u64, TestError> {
+        let recent_blockhash = self
+            .context
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        // The fee rate is looked up by blockhash, so the message needs a live one
+        let message = Message::new_with_blockhash(instructions, Some(payer), &recent_blockhash);
+        self.context
+            .banks_client
+            .get_fee_for_message(message)
+            .await
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?
+            .ok_or(TestError::TestError("Fee not available".to_string()))
+    }
+
     pub async fn get_clock(&mut self) -> Clock {
         self.get_bincode_account::<Clock>(&sysvar::clock::id())
             .await
@@ -363,6 +607,23 @@ impl ProgramTestBench {
             .unwrap()
     }
 
+    // Pairs with `labels::balance_deltas` to report what a transaction moved.
+    pub async fn snapshot_accounts(
+        &mut self,
+        addresses: &[Pubkey],
+    ) -> Vec<(Pubkey, Option<Account>)> {
+        let mut snapshots = vec![];
+        for address in addresses.iter() {
+            snapshots.push((*address, self.get_account(address).await));
+        }
+        snapshots
+    }
+
+    pub async fn set_account(&mut self, address: &Pubkey, account: &Account) {
+        self.context
+            .set_account(address, &AccountSharedData::from(account.clone()));
+    }
+
     pub async fn get_admin_state_account(
         &mut self,
         address: &Pubkey,
@@ -372,12 +633,10 @@ impl ProgramTestBench {
             .get_account(address)
             .await
             .ok_or(TestError::TestError("Account not found".to_string()))?;
-        let admin_state_account_data = admin_state_account.data;
-        let dst = array_ref![admin_state_account_data, 0, ADMIN_LEN];
-        let (token_accounts_dst, fee_dst, _) = array_refs![dst, TOKEN_ACCOUNTS_LEN, 4, 1];
-        token_accounts.clone_from_slice(token_accounts_dst);
-        let fee_bps = u32::from_le_bytes(*fee_dst);
-        Ok(fee_bps)
+        Ok(unpack_admin_state(
+            &admin_state_account.data,
+            token_accounts,
+        ))
     }
 
     pub async fn get_token_account(
@@ -392,6 +651,135 @@ impl ProgramTestBench {
             .map_err(|e| TestError::UnexpectedError(Box::new(e)))
     }
 
+    pub async fn create_mint_2022(
+        &mut self,
+        mint_authority: &Keypair,
+        enable_freeze: bool,
+        decimals: u8,
+        extensions: &[MintExtension],
+    ) -> Result<Keypair, TestError> {
+        let mint_keypair = Keypair::new();
+        let mint_rent = self.rent.minimum_balance(get_mint_len(extensions)?);
+
+        let freeze_authority_pubkey = mint_authority.pubkey();
+        let freeze_authority = if enable_freeze {
+            Some(&freeze_authority_pubkey)
+        } else {
+            None
+        };
+
+        let instructions = token_2022::create_mint_instructions(
+            &mint_authority.pubkey(),
+            &mint_keypair.pubkey(),
+            &mint_authority.pubkey(),
+            freeze_authority,
+            decimals,
+            extensions,
+            mint_rent,
+        )?;
+        let signers = [mint_authority, &mint_keypair];
+
+        self.process_transaction(&instructions, Some(&mint_authority.pubkey()), &signers)
+            .await?;
+        Ok(mint_keypair)
+    }
+
+    pub async fn create_token_account_2022(
+        &mut self,
+        token: Pubkey,
+        owner: &Keypair,
+        account: &Keypair,
+    ) -> Result<Pubkey, TestError> {
+        let mint = self
+            .get_account(&token)
+            .await
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        let account_len = get_token_account_len(&mint.data)?;
+
+        let instructions = token_2022::create_token_account_instructions(
+            &owner.pubkey(),
+            &account.pubkey(),
+            &token,
+            account_len,
+            self.rent.minimum_balance(account_len),
+        )?;
+        if self.get_account(&account.pubkey()).await.is_some() {
+            return Err(TestError::TestError(format!(
+                "Error: Account already exists: {}",
+                Labeled(&account.pubkey())
+            )));
+        }
+
+        self.process_transaction(&instructions, Some(&owner.pubkey()), &[owner, account])
+            .await?;
+        Ok(account.pubkey())
+    }
+
+    pub async fn mint_tokens_2022(
+        &mut self,
+        token_account: Pubkey,
+        token_mint: &Pubkey,
+        token_mint_authority: &Keypair,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<(), TestError> {
+        let instructions = vec![spl_token_2022::instruction::mint_to_checked(
+            &spl_token_2022::id(),
+            token_mint,
+            &token_account,
+            &token_mint_authority.pubkey(),
+            &[],
+            amount,
+            decimals,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?];
+
+        self.process_transaction(
+            &instructions,
+            Some(&token_mint_authority.pubkey()),
+            &[token_mint_authority],
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn transfer_tokens_2022(
+        &mut self,
+        authority: &Keypair,
+        source_token_account: &Pubkey,
+        token_mint: &Pubkey,
+        destination_token_account: &Pubkey,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<(), TestError> {
+        let instructions = vec![spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::id(),
+            source_token_account,
+            token_mint,
+            destination_token_account,
+            &authority.pubkey(),
+            &[],
+            amount,
+            decimals,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?];
+
+        self.process_transaction(&instructions, Some(&authority.pubkey()), &[authority])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_token_account_2022(
+        &mut self,
+        address: &Pubkey,
+    ) -> Result<spl_token_2022::state::Account, TestError> {
+        let account = self
+            .get_account(address)
+            .await
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        unpack_token_account(&account.data)
+    }
+
     pub async fn advance_clock_past_timestamp(
         &mut self,
         unix_timestamp: UnixTimestamp,
@@ -418,6 +806,54 @@ impl ProgramTestBench {
             .await
     }
 
+    pub async fn set_unix_timestamp(
+        &mut self,
+        unix_timestamp: UnixTimestamp,
+    ) -> Result<(), TestError> {
+        // Warp a single slot first so that the next transaction is signed with a fresh
+        // blockhash, otherwise retrying an identical transaction at the new time would be
+        // rejected as already processed
+        let clock = self.get_clock().await;
+        self.context
+            .warp_to_slot(clock.slot + 1)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        let mut clock = self.get_clock().await;
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+
+        if self.get_clock().await.unix_timestamp != unix_timestamp {
+            return Err(TestError::TestError(format!(
+                "Clock sysvar was not set to {}",
+                unix_timestamp
+            )));
+        }
+        Ok(())
+    }
+
+    // Warps a single slot and keeps the clock where it is. An identical transaction sent again
+    // under the same blockhash is rejected as already processed before it reaches the program, so
+    // tests call this before repeating one.
+    pub async fn next_slot(&mut self) -> Result<(), TestError> {
+        let clock = self.get_clock().await;
+        self.context
+            .warp_to_slot(clock.slot + 1)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        let mut warped_clock = self.get_clock().await;
+        warped_clock.unix_timestamp = clock.unix_timestamp;
+        self.context.set_sysvar(&warped_clock);
+        Ok(())
+    }
+
+    pub async fn advance_seconds(&mut self, seconds: i64) -> Result<(), TestError> {
+        let clock = self.get_clock().await;
+        let unix_timestamp = clock
+            .unix_timestamp
+            .checked_add(seconds)
+            .ok_or(TestError::TestError("Clock overflow".to_string()))?;
+        self.set_unix_timestamp(unix_timestamp).await
+    }
+
     #[allow(dead_code)]
     pub async fn advance_clock(&mut self) {
         let clock = self.get_clock().await;
@@ -0,0 +1,36 @@
+use {
+    solana_program::instruction::Instruction, solana_sdk::compute_budget::ComputeBudgetInstruction,
+};
+
+// Compute budget options for a transaction. Fields left as `None` keep the runtime defaults.
+// The ComputeBudget instructions are prepended, so they shift the index of every instruction
+// reported in an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    // Priority fee in micro-lamports per compute unit
+    pub unit_price: Option<u64>,
+    pub heap_size: Option<u32>,
+}
+
+impl ComputeBudget {
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = vec![];
+        if let Some(unit_limit) = self.unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(unit_limit));
+        }
+        if let Some(unit_price) = self.unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(unit_price));
+        }
+        if let Some(heap_size) = self.heap_size {
+            instructions.push(ComputeBudgetInstruction::request_heap_frame(heap_size));
+        }
+        instructions
+    }
+
+    pub fn prepend_to(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut budgeted = self.instructions();
+        budgeted.extend_from_slice(instructions);
+        budgeted
+    }
+}
@@ -0,0 +1,245 @@
+use {
+    crate::common::error::{instruction_error_message, TestError},
+    crate::common::telemetry::instruction_name,
+    lazy_static::lazy_static,
+    solana_program::instruction::Instruction,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        env,
+        fmt::Write as _,
+        fs::{self, File, OpenOptions},
+        io::Write,
+        path::PathBuf,
+        sync::Mutex,
+    },
+};
+
+const COVERAGE_DIR_VAR: &str = "RENFT_COVERAGE_DIR";
+const DEFAULT_COVERAGE_DIR: &str = "target/instruction-coverage";
+const COVERAGE_RUN_VAR: &str = "RENFT_COVERAGE_RUN";
+
+// Same order as `EscrowInstruction`, so that instructions never exercised still get a row
+pub const ESCROW_INSTRUCTIONS: [&str; 9] = [
+    "Lend",
+    "StopLend",
+    "EditLend",
+    "Rent",
+    "StopRent",
+    "Claim",
+    "InitializeAdminState",
+    "SetFee",
+    "SetPayableAccount",
+];
+
+// Outcome of an instruction that succeeded
+pub const OK: &str = "ok";
+// Outcome of a transaction that failed without an instruction error, e.g. on a signature
+pub const REJECTED: &str = "rejected";
+
+lazy_static! {
+    // One file per test binary, written to by all of its tests
+    static ref COVERAGE_FILE: Mutex<Option<File>> = Mutex::new(open_coverage_file());
+}
+
+fn coverage_dir() -> PathBuf {
+    env::var(COVERAGE_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_COVERAGE_DIR))
+}
+
+// `$RENFT_COVERAGE_RUN`, or else the process id of cargo, which is the parent of every test
+// binary of one `cargo test`.
+fn run_id() -> String {
+    env::var(COVERAGE_RUN_VAR).unwrap_or_else(|_| std::os::unix::process::parent_id().to_string())
+}
+
+// Each run records into a directory of its own. The first binary of a run removes those of the
+// runs before it, so that a report only ever counts one run.
+fn open_coverage_file() -> Option<File> {
+    let exe = env::current_exe().ok()?;
+    let stem = exe.file_stem()?.to_str()?;
+    // Without the hash cargo appends, e.g. "integration.tsv"
+    let name = stem.rsplit_once('-').map_or(stem, |(name, _)| name);
+    let dir = coverage_dir();
+    let run_id = run_id();
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            if entry.file_name() != run_id.as_str() {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+    let run_dir = dir.join(run_id);
+    fs::create_dir_all(&run_dir).ok()?;
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(run_dir.join(format!("{}.tsv", name)))
+        .ok()
+}
+
+// The directory of `$RENFT_COVERAGE_RUN` when it is set. The report is made by a cargo of its
+// own, so otherwise it is the directory of the latest run.
+fn last_run_dir() -> Option<PathBuf> {
+    let dir = coverage_dir();
+    if let Ok(run_id) = env::var(COVERAGE_RUN_VAR) {
+        return Some(dir.join(run_id));
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+}
+
+// "custom program error: 0x1" becomes "0x1", other runtime errors keep their message.
+pub fn outcome(message: &str) -> String {
+    message
+        .strip_prefix("custom program error: ")
+        .unwrap_or(message)
+        .to_string()
+}
+
+// Escrow instructions of a transaction and what happened to each: `ok` when the transaction
+// succeeded, the error code of the one that failed, nothing for those rolled back with it.
+pub fn outcomes(
+    instructions: &[Instruction],
+    result: &Result<(), TestError>,
+) -> Vec<(&'static str, String)> {
+    let is_escrow = |name: &&str| ESCROW_INSTRUCTIONS.contains(name);
+    if result.is_ok() {
+        return instructions
+            .iter()
+            .map(instruction_name)
+            .filter(is_escrow)
+            .map(|name| (name, OK.to_string()))
+            .collect();
+    }
+    match instruction_error_message(result) {
+        Some((index, message)) => instructions
+            .get(index as usize)
+            .map(instruction_name)
+            .filter(is_escrow)
+            .map(|name| vec![(name, outcome(&message))])
+            .unwrap_or_default(),
+        None => instructions
+            .iter()
+            .map(instruction_name)
+            .filter(is_escrow)
+            .map(|name| (name, REJECTED.to_string()))
+            .collect(),
+    }
+}
+
+// `instructions` must be the instructions of the transaction as sent, compute budget included,
+// for the failing index to point at the right one.
+pub fn record(instructions: &[Instruction], result: &Result<(), TestError>) {
+    let outcomes = outcomes(instructions, result);
+    if outcomes.is_empty() {
+        return;
+    }
+    let mut file = COVERAGE_FILE.lock().unwrap();
+    if let Some(file) = file.as_mut() {
+        for (name, outcome) in outcomes.iter() {
+            // Coverage is a report, losing a line must not fail the test
+            let _ = writeln!(file, "{}\t{}", name, outcome);
+        }
+    }
+}
+
+fn read_counts() -> Result<BTreeMap<(String, String), usize>, TestError> {
+    let mut counts = BTreeMap::new();
+    let entries = match last_run_dir().map(fs::read_dir) {
+        Some(Ok(entries)) => entries,
+        // Nothing was recorded yet
+        _ => return Ok(counts),
+    };
+    for entry in entries {
+        let path = entry
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?
+            .path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "tsv")
+        {
+            continue;
+        }
+        let lines =
+            fs::read_to_string(&path).map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        for line in lines.lines() {
+            let mut fields = line.splitn(2, '\t');
+            if let (Some(instruction), Some(outcome)) = (fields.next(), fields.next()) {
+                *counts
+                    .entry((instruction.to_string(), outcome.to_string()))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+    Ok(counts)
+}
+
+// Matrix of escrow instruction by outcome over every test binary of the last run, followed by the
+// instructions no test made fail.
+#[allow(dead_code)]
+pub fn report() -> Result<String, TestError> {
+    let counts = read_counts()?;
+
+    // Successes first, then every rejection seen on any instruction
+    let mut outcomes = vec![OK.to_string()];
+    let rejections: BTreeSet<&String> = counts
+        .keys()
+        .map(|(_, outcome)| outcome)
+        .filter(|outcome| *outcome != OK)
+        .collect();
+    outcomes.extend(rejections.into_iter().cloned());
+
+    let name_width = ESCROW_INSTRUCTIONS
+        .iter()
+        .map(|name| name.len())
+        .max()
+        .unwrap();
+    let widths: Vec<usize> = outcomes
+        .iter()
+        .map(|outcome| outcome.len().max(3))
+        .collect();
+
+    let mut report = String::new();
+    write!(report, "{:<width$}", "", width = name_width).unwrap();
+    for (outcome, width) in outcomes.iter().zip(widths.iter()) {
+        write!(report, " | {:>width$}", outcome, width = width).unwrap();
+    }
+    writeln!(report).unwrap();
+    for instruction in ESCROW_INSTRUCTIONS.iter() {
+        write!(report, "{:<width$}", instruction, width = name_width).unwrap();
+        for (outcome, width) in outcomes.iter().zip(widths.iter()) {
+            match counts.get(&(instruction.to_string(), outcome.clone())) {
+                Some(count) => write!(report, " | {:>width$}", count, width = width),
+                None => write!(report, " | {:>width$}", ".", width = width),
+            }
+            .unwrap();
+        }
+        writeln!(report).unwrap();
+    }
+
+    let unexercised: Vec<&str> = ESCROW_INSTRUCTIONS
+        .iter()
+        .copied()
+        .filter(|instruction| {
+            !counts
+                .keys()
+                .any(|(name, outcome)| name == instruction && outcome != OK)
+        })
+        .collect();
+    if !unexercised.is_empty() {
+        writeln!(report).unwrap();
+        writeln!(
+            report,
+            "No rejection exercised for: {}",
+            unexercised.join(", ")
+        )
+        .unwrap();
+    }
+    Ok(report)
+}
@@ -0,0 +1,184 @@
+use {
+    crate::common::{
+        calculate_escrow_state_account_len, error::TestError, fixture::SECONDS_IN_DAY,
+        unpack_admin_state,
+    },
+    solana_program::pubkey::Pubkey,
+    solana_renft_collateral_free::{
+        admin::{self, ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
+        map::Bucket,
+        renting::Renting,
+        state::Escrow,
+    },
+    std::fmt::Write,
+};
+
+// Renders the data of an escrow or admin state account with every field, for inspecting accounts
+// by hand. Admin accounts have a fixed size, escrow accounts grow with max_renters. The payable
+// account of an admin account is looked up for each of `mints`.
+pub fn decode_account(data: &[u8], mints: &[Pubkey]) -> Result<String, TestError> {
+    if data.len() == ADMIN_LEN {
+        Ok(decode_admin(data, mints))
+    } else {
+        decode_escrow(data)
+    }
+}
+
+fn decode_admin(data: &[u8], mints: &[Pubkey]) -> String {
+    let mut token_accounts = [0; TOKEN_ACCOUNTS_LEN];
+    let fee = unpack_admin_state(data, &mut token_accounts);
+    let mut decoded = String::new();
+    writeln!(decoded, "admin state account ({} bytes)", data.len()).unwrap();
+    writeln!(
+        decoded,
+        "  is_initialized: {}",
+        data[TOKEN_ACCOUNTS_LEN + 4] != 0
+    )
+    .unwrap();
+    writeln!(decoded, "  fee: {} bps", fee).unwrap();
+    for mint in mints.iter() {
+        match admin::get(mint, &token_accounts) {
+            Ok(account) => writeln!(decoded, "  payable account for {}: {}", mint, account),
+            Err(_) => writeln!(decoded, "  payable account for {}: none", mint),
+        }
+        .unwrap();
+    }
+    decoded
+}
+
+// The data may come from anywhere, so it only reaches `Escrow::unpack` once its length is one an
+// escrow state account can have: the header followed by whole buckets.
+fn decode_escrow(data: &[u8]) -> Result<String, TestError> {
+    let data_len = data.len();
+    if data_len < Escrow::LEN || (data_len - Escrow::LEN) % Bucket::BUCKET_BUFFER_LEN != 0 {
+        return Err(TestError::TestError(format!(
+            "{} bytes is neither an admin nor an escrow state account",
+            data_len
+        )));
+    }
+    let mut data = data.to_vec();
+    let mut escrow_info = Escrow::new();
+    let escrow_info = Escrow::unpack(&mut data, &mut escrow_info)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    // An initialized escrow is sized for its max_renters, anything else is not an escrow
+    if escrow_info.is_initialized
+        && calculate_escrow_state_account_len(escrow_info.max_renters)? != data_len
+    {
+        return Err(TestError::TestError(format!(
+            "{} bytes is not the length of an escrow state account for {} renters",
+            data_len, escrow_info.max_renters
+        )));
+    }
+
+    let mut decoded = String::new();
+    writeln!(decoded, "escrow state account ({} bytes)", data_len).unwrap();
+    writeln!(decoded, "  is_initialized: {}", escrow_info.is_initialized).unwrap();
+    writeln!(decoded, "  lender: {}", escrow_info.lender_pubkey).unwrap();
+    writeln!(
+        decoded,
+        "  temp_nft_account: {}",
+        escrow_info.temp_nft_account_pubkey
+    )
+    .unwrap();
+    writeln!(
+        decoded,
+        "  lender_token_account: {}",
+        escrow_info.lender_token_account_pubkey
+    )
+    .unwrap();
+    writeln!(
+        decoded,
+        "  pda_token_account: {}",
+        escrow_info.pda_token_account_pubkey
+    )
+    .unwrap();
+    writeln!(
+        decoded,
+        "  daily_rent_price: {}",
+        escrow_info.daily_rent_price
+    )
+    .unwrap();
+    writeln!(decoded, "  max_renters: {}", escrow_info.max_renters).unwrap();
+    writeln!(
+        decoded,
+        "  max_rent_duration: {} days",
+        escrow_info.max_rent_duration
+    )
+    .unwrap();
+    writeln!(
+        decoded,
+        "  current_renters: {}",
+        escrow_info.current_renters
+    )
+    .unwrap();
+    writeln!(
+        decoded,
+        "  rentings: length {}, capacity {}, size {}",
+        escrow_info.rentings.length, escrow_info.rentings.capacity, escrow_info.rentings.size
+    )
+    .unwrap();
+    for i in 0..escrow_info.rentings.size {
+        let renting = escrow_info
+            .rentings
+            .get_renting(i as usize)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        if renting.is_valid() {
+            decode_renting(&renting, &mut decoded);
+        }
+    }
+    Ok(decoded)
+}
+
+fn decode_renting(renting: &Renting, decoded: &mut String) {
+    // The data may come from anywhere, so the end of the rental is not trusted to fit an i64
+    let ends_at = (renting.rent_duration as i64)
+        .checked_mul(SECONDS_IN_DAY)
+        .and_then(|duration| renting.rented_at.checked_add(duration));
+    writeln!(decoded, "  renting").unwrap();
+    writeln!(decoded, "    renter: {}", renting.renter_address).unwrap();
+    writeln!(
+        decoded,
+        "    rented_at: {} ({})",
+        format_timestamp(renting.rented_at),
+        renting.rented_at
+    )
+    .unwrap();
+    writeln!(decoded, "    duration: {} days", renting.rent_duration).unwrap();
+    match ends_at {
+        Some(ends_at) => writeln!(decoded, "    ends_at: {}", format_timestamp(ends_at)),
+        None => writeln!(decoded, "    ends_at: out of range"),
+    }
+    .unwrap();
+    writeln!(decoded, "    amount: {}", renting.rent_amount).unwrap();
+}
+
+// UTC date of a unix timestamp in RFC 3339, from the civil calendar algorithm of
+// http://howardhinnant.github.io/date_algorithms.html, which holds for any i64.
+pub fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(SECONDS_IN_DAY);
+    let seconds = timestamp.rem_euclid(SECONDS_IN_DAY);
+    // Days are counted from 0000-03-01, so that leap days end the year
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
@@ -1,42 +1,110 @@
+use crate::common::labels::substitute;
 use solana_program::instruction::InstructionError;
 use solana_program::program_error::ProgramError;
 use std::error::Error;
+use std::fmt;
 
-#[derive(thiserror::Error, Debug)]
+// Displayed with labels in place of the pubkeys they name, both when printed and when a test
+// returns one. The source of an `UnexpectedError` is the source of the wrapped error, as the
+// instruction error helpers below expect.
 pub enum TestError {
-    #[error("{0}")]
     TestError(String),
-    #[error(transparent)]
-    UnexpectedError(#[from] Box<dyn std::error::Error>),
+    UnexpectedError(Box<dyn std::error::Error>),
 }
 unsafe impl Send for TestError {}
 
-pub fn test_error_to_program_error(r: Result<(), TestError>) -> ProgramError {
-    let mut s = r.unwrap_err().source().unwrap().to_string();
-    println!("{}", s);
-    if s == "Error processing Instruction 0: invalid account data for instruction" {
-        ProgramError::InvalidAccountData
-    } else {
-        s = s
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .to_string()
-            .trim_start_matches("0x")
-            .to_string();
-        let u = u32::from_str_radix(&s, 16).unwrap();
-        ProgramError::Custom(u)
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TestError::TestError(message) => write!(f, "{}", substitute(message)),
+            TestError::UnexpectedError(e) => write!(f, "{}", substitute(&e.to_string())),
+        }
     }
 }
 
+impl fmt::Debug for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TestError::TestError(message) => write!(f, "TestError({:?})", substitute(message)),
+            TestError::UnexpectedError(e) => {
+                write!(f, "UnexpectedError({})", substitute(&format!("{:?}", e)))
+            }
+        }
+    }
+}
+
+impl Error for TestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TestError::TestError(_) => None,
+            TestError::UnexpectedError(e) => e.source(),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for TestError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        TestError::UnexpectedError(e)
+    }
+}
+
+pub fn test_error_to_program_error(r: Result<(), TestError>) -> ProgramError {
+    test_error_to_indexed_program_error(r).1
+}
+
+// Returns the index of the failing instruction within its transaction alongside the error, for
+// transactions that are not made of a single instruction.
+pub fn test_error_to_indexed_program_error(r: Result<(), TestError>) -> (u8, ProgramError) {
+    let s = r.unwrap_err().source().unwrap().to_string();
+    println!("{}", substitute(&s));
+    let (index, message) = split_instruction_error(&s).expect("Not an instruction error");
+    let program_error = match message {
+        "invalid account data for instruction" => ProgramError::InvalidAccountData,
+        "missing required signature for instruction" => ProgramError::MissingRequiredSignature,
+        "insufficient funds for instruction" => ProgramError::InsufficientFunds,
+        "incorrect program id for instruction" => ProgramError::IncorrectProgramId,
+        _ => {
+            let code = message
+                .split_whitespace()
+                .last()
+                .unwrap()
+                .trim_start_matches("0x");
+            ProgramError::Custom(u32::from_str_radix(code, 16).unwrap())
+        }
+    };
+    (index, program_error)
+}
+
+// Splits "Error processing Instruction <index>: <message>", or `None` for any other error.
+fn split_instruction_error(s: &str) -> Option<(u8, &str)> {
+    let s = s.strip_prefix("Error processing Instruction ")?;
+    let (index, message) = s.split_at(s.find(": ")?);
+    Some((index.parse().ok()?, &message[2..]))
+}
+
 pub fn test_error_to_instruction_error(r: Result<(), TestError>) -> InstructionError {
+    test_error_to_indexed_instruction_error(r).1
+}
+
+pub fn test_error_to_indexed_instruction_error(r: Result<(), TestError>) -> (u8, InstructionError) {
     let s = r.unwrap_err().source().unwrap().to_string();
-    println!("{}", s);
-    if s
-        == "Error processing Instruction 0: instruction modified data of an account it does not own"
-    {
-        InstructionError::ExternalAccountDataModified
+    println!("{}", substitute(&s));
+    let (index, message) = split_instruction_error(&s).expect("Not an instruction error");
+    if message == "instruction modified data of an account it does not own" {
+        (index, InstructionError::ExternalAccountDataModified)
     } else {
         panic!("Invalid error");
     }
 }
+
+// Index of the failing instruction and the runtime's message for it, or `None` when the
+// transaction succeeded or failed before any instruction ran.
+pub fn instruction_error_message(r: &Result<(), TestError>) -> Option<(u8, String)> {
+    let s = r.as_ref().err()?.source()?.to_string();
+    let (index, message) = split_instruction_error(&s)?;
+    Some((index, message.to_string()))
+}
+
+pub fn is_instruction_error(r: &Result<(), TestError>) -> bool {
+    instruction_error_message(r).is_some()
+}
@@ -0,0 +1,120 @@
+use crate::common::{
+    error::TestError,
+    state::{Currency, State},
+};
+
+pub const MAX_FEE_BPS: u32 = 10000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub lender: u64,
+    pub admin: u64,
+    pub renter: u64,
+}
+
+impl FeeSplit {
+    pub fn total(&self) -> u64 {
+        self.lender + self.admin + self.renter
+    }
+}
+
+pub fn calculate_prepaid_rent(
+    daily_rent_price: u64,
+    rent_amount: u16,
+    rent_duration: u8,
+) -> Result<u64, TestError> {
+    daily_rent_price
+        .checked_mul(rent_amount as u64)
+        .and_then(|price| price.checked_mul(rent_duration as u64))
+        .ok_or(TestError::TestError("Prepaid rent overflow".to_string()))
+}
+
+// Split of the prepaid rent once a renting ends after `days_charged` days. The admin fee is
+// taken from the charged rent and rounded down, the lender receives the remainder of the
+// charged rent and the renter is refunded the days that were not used.
+pub fn calculate_partial_fee_split(
+    daily_rent_price: u64,
+    rent_amount: u16,
+    rent_duration: u8,
+    days_charged: u8,
+    fee_bps: u32,
+) -> Result<FeeSplit, TestError> {
+    if days_charged > rent_duration {
+        return Err(TestError::TestError(
+            "Charged days exceed the rent duration".to_string(),
+        ));
+    }
+    if fee_bps > MAX_FEE_BPS {
+        return Err(TestError::TestError("Fee exceeds 10000 bps".to_string()));
+    }
+
+    let prepaid = calculate_prepaid_rent(daily_rent_price, rent_amount, rent_duration)?;
+    let charged = calculate_prepaid_rent(daily_rent_price, rent_amount, days_charged)?;
+    let admin = ((charged as u128) * (fee_bps as u128) / (MAX_FEE_BPS as u128)) as u64;
+
+    Ok(FeeSplit {
+        lender: charged - admin,
+        admin,
+        renter: prepaid - charged,
+    })
+}
+
+pub fn calculate_fee_split(
+    daily_rent_price: u64,
+    rent_amount: u16,
+    rent_duration: u8,
+    fee_bps: u32,
+) -> Result<FeeSplit, TestError> {
+    calculate_partial_fee_split(
+        daily_rent_price,
+        rent_amount,
+        rent_duration,
+        rent_duration,
+        fee_bps,
+    )
+}
+
+pub async fn get_fee_split(test_state: &mut State) -> Result<FeeSplit, TestError> {
+    get_fee_split_with_currency(Currency::Sol, test_state).await
+}
+
+pub async fn get_fee_split_with_currency(
+    currency: Currency,
+    test_state: &mut State,
+) -> Result<FeeSplit, TestError> {
+    let lender = test_state
+        .bench
+        .get_token_account(&test_state.lender_token_account_pubkey(currency))
+        .await?
+        .amount;
+    let admin = test_state
+        .bench
+        .get_token_account(&test_state.admin_token_account_pubkey(currency))
+        .await?
+        .amount;
+    let renter = test_state
+        .bench
+        .get_token_account(&test_state.renter_token_account_pubkey(currency))
+        .await?
+        .amount;
+    Ok(FeeSplit {
+        lender,
+        admin,
+        renter,
+    })
+}
+
+pub async fn get_pda_balance(test_state: &mut State) -> Result<u64, TestError> {
+    get_pda_balance_with_currency(Currency::Sol, test_state).await
+}
+
+pub async fn get_pda_balance_with_currency(
+    currency: Currency,
+    test_state: &mut State,
+) -> Result<u64, TestError> {
+    Ok(test_state
+        .bench
+        .get_token_account(&test_state.pda_token_account_pubkey(currency))
+        .await?
+        .amount)
+}
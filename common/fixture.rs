@@ -0,0 +1,204 @@
+use {
+    crate::common::error::TestError,
+    crate::common::fee::{
+        calculate_fee_split, calculate_partial_fee_split, calculate_prepaid_rent, FeeSplit,
+    },
+    crate::common::renft::*,
+    crate::common::state::{Currency, GenesisAccounts, GenesisKeys, State, TokenAccountMode},
+    solana_program::pubkey::Pubkey,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::account::Account,
+};
+
+pub const SECONDS_IN_DAY: i64 = 86400;
+
+// Terms of the single listing most tests lend and rent.
+#[derive(Clone, Copy, Debug)]
+pub struct Listing {
+    pub daily_rent_price: u64,
+    pub max_renters: u32,
+    pub max_rent_duration: u8,
+    pub rent_amount: u16,
+    pub rent_duration: u8,
+    pub fee: u32,
+}
+
+pub const LISTING: Listing = Listing {
+    daily_rent_price: 2000,
+    max_renters: 1,
+    max_rent_duration: 3,
+    rent_amount: 1,
+    rent_duration: 3,
+    fee: 1000,
+};
+
+impl Listing {
+    pub fn prepaid_rent(&self) -> Result<u64, TestError> {
+        calculate_prepaid_rent(self.daily_rent_price, self.rent_amount, self.rent_duration)
+    }
+
+    pub fn fee_split(&self) -> Result<FeeSplit, TestError> {
+        calculate_fee_split(
+            self.daily_rent_price,
+            self.rent_amount,
+            self.rent_duration,
+            self.fee,
+        )
+    }
+
+    pub fn partial_fee_split(&self, days_charged: u8) -> Result<FeeSplit, TestError> {
+        calculate_partial_fee_split(
+            self.daily_rent_price,
+            self.rent_amount,
+            self.rent_duration,
+            days_charged,
+            self.fee,
+        )
+    }
+
+    pub fn expires_at(&self, rented_at: i64) -> i64 {
+        rented_at + SECONDS_IN_DAY * (self.rent_duration as i64)
+    }
+
+    pub async fn lend(&self, currency: Currency, test_state: &mut State) -> Result<(), TestError> {
+        lend_with_currency(
+            self.daily_rent_price,
+            self.max_renters,
+            self.max_rent_duration,
+            currency,
+            test_state,
+        )
+        .await
+    }
+
+    pub async fn rent(
+        &self,
+        currency: Currency,
+        test_state: &mut State,
+    ) -> Result<Renting, TestError> {
+        rent_with_currency(self.rent_amount, self.rent_duration, currency, test_state).await?;
+        test_state.find_valid_renting().await
+    }
+}
+
+// How far `Fixture::build` takes the fixture. Each stage includes the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Created,
+    // Admin state initialized with the listing's fee and the payable accounts set
+    AdminReady,
+    Lent,
+    Rented,
+}
+
+pub struct Fixture {
+    listing: Listing,
+    currency: Currency,
+    payable_currencies: Vec<Currency>,
+    renter_sol_token_amount: Option<u64>,
+    renter_usdc_token_amount: Option<u64>,
+    token_account_mode: TokenAccountMode,
+    accounts: GenesisAccounts,
+}
+
+impl Fixture {
+    // The renter is funded with the prepaid rent of `listing` in the listing's currency, and the
+    // admin's payable account is set for that currency only.
+    pub fn new(listing: Listing) -> Self {
+        Self {
+            listing,
+            currency: Currency::Sol,
+            payable_currencies: vec![Currency::Sol],
+            renter_sol_token_amount: None,
+            renter_usdc_token_amount: None,
+            token_account_mode: TokenAccountMode::Keypair,
+            accounts: Box::new(|_| vec![]),
+        }
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self.payable_currencies = vec![currency];
+        self
+    }
+
+    pub fn payable_currencies(mut self, currencies: &[Currency]) -> Self {
+        self.payable_currencies = currencies.to_vec();
+        self
+    }
+
+    pub fn renter_sol_token_amount(mut self, amount: u64) -> Self {
+        self.renter_sol_token_amount = Some(amount);
+        self
+    }
+
+    pub fn renter_usdc_token_amount(mut self, amount: u64) -> Self {
+        self.renter_usdc_token_amount = Some(amount);
+        self
+    }
+
+    pub fn associated_token_accounts(mut self) -> Self {
+        self.token_account_mode = TokenAccountMode::Associated;
+        self
+    }
+
+    // Accounts loaded into the bank before it starts, see `State::initialize_with_accounts`
+    pub fn accounts(mut self, accounts: Vec<(Pubkey, Account)>) -> Self {
+        self.accounts = Box::new(move |_| accounts);
+        self
+    }
+
+    // Like `accounts`, for accounts that refer to the fixture's own addresses
+    pub fn genesis_accounts<F>(mut self, accounts: F) -> Self
+    where
+        F: FnOnce(&GenesisKeys) -> Vec<(Pubkey, Account)> + 'static,
+    {
+        self.accounts = Box::new(accounts);
+        self
+    }
+
+    fn renter_token_amount(&self, currency: Currency) -> Result<u64, TestError> {
+        let amount = match currency {
+            Currency::Sol => self.renter_sol_token_amount,
+            Currency::Usdc => self.renter_usdc_token_amount,
+        };
+        match amount {
+            Some(amount) => Ok(amount),
+            None if currency == self.currency => self.listing.prepaid_rent(),
+            None => Ok(0),
+        }
+    }
+
+    pub async fn build(self, stage: Stage) -> Result<State, TestError> {
+        let mut test_state = State::initialize_impl(
+            self.listing.max_renters,
+            self.renter_token_amount(Currency::Sol)?,
+            self.renter_token_amount(Currency::Usdc)?,
+            self.token_account_mode,
+            self.accounts,
+        )
+        .await?;
+
+        if stage >= Stage::AdminReady {
+            tracing::info!("Initialize admin state");
+            initialize_admin_state(self.listing.fee, &mut test_state).await?;
+            for currency in self.payable_currencies.iter() {
+                tracing::info!("Set payable account for {:?}", currency);
+                set_payable_account(
+                    test_state.admin_token_account_pubkey(*currency),
+                    &mut test_state,
+                )
+                .await?;
+            }
+        }
+        if stage >= Stage::Lent {
+            tracing::info!("Start lending in {:?}", self.currency);
+            self.listing.lend(self.currency, &mut test_state).await?;
+        }
+        if stage >= Stage::Rented {
+            tracing::info!("Start renting in {:?}", self.currency);
+            self.listing.rent(self.currency, &mut test_state).await?;
+        }
+        Ok(test_state)
+    }
+}
@@ -0,0 +1,255 @@
+use {
+    crate::common::error::TestError,
+    crate::common::labels::label,
+    crate::common::state::{Currency, State},
+    arrayref::{array_ref, array_refs},
+    solana_program::pubkey::Pubkey,
+    solana_renft_collateral_free::{
+        admin::{self, ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
+        state::Escrow,
+    },
+    solana_sdk::signature::Signer,
+    std::{fmt::Write, fs, path::PathBuf},
+};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+// Decoded admin and escrow accounts after each step of a scenario, compared with
+// `tests/golden/<name>.txt`. Run with `UPDATE_GOLDEN=1` to write the file instead, after checking
+// that the diff is expected. Of the renting slots only the valid rentings are rendered, so a slot
+// left empty or tombstoned by a stop rent only shows in the length of the map.
+pub struct Golden {
+    name: String,
+    rendered: String,
+    // Keys without a label are numbered in the order they are first seen
+    unlabelled: Vec<Pubkey>,
+}
+
+impl Golden {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rendered: String::new(),
+            unlabelled: vec![],
+        }
+    }
+
+    fn key(&mut self, address: &Pubkey) -> String {
+        if *address == Pubkey::default() {
+            return "none".to_string();
+        }
+        if let Some(label) = label(address) {
+            return label;
+        }
+        let index = match self.unlabelled.iter().position(|key| key == address) {
+            Some(index) => index,
+            None => {
+                self.unlabelled.push(*address);
+                self.unlabelled.len() - 1
+            }
+        };
+        format!("unlabelled_{}", index)
+    }
+
+    pub async fn snapshot(&mut self, step: &str, test_state: &mut State) -> Result<(), TestError> {
+        writeln!(self.rendered, "== {} ==", step).unwrap();
+        let admin_state_account = test_state
+            .bench
+            .get_account(&test_state.admin_state_account_keypair.pubkey())
+            .await;
+        match admin_state_account {
+            Some(account) => {
+                let mints = [
+                    test_state.token_pubkey(Currency::Sol),
+                    test_state.token_pubkey(Currency::Usdc),
+                ];
+                self.render_admin(&account.data, &mints)
+            }
+            None => writeln!(self.rendered, "admin_state_account: does not exist").unwrap(),
+        }
+        let escrow_state_account = test_state
+            .bench
+            .get_account(&test_state.escrow_state_account_keypair.pubkey())
+            .await;
+        match escrow_state_account {
+            Some(account) => self.render_escrow(&account.data)?,
+            None => writeln!(self.rendered, "escrow_state_account: does not exist").unwrap(),
+        }
+        Ok(())
+    }
+
+    fn render_admin(&mut self, data: &[u8], mints: &[Pubkey]) {
+        if data.len() < ADMIN_LEN {
+            writeln!(self.rendered, "admin_state_account: {} bytes", data.len()).unwrap();
+            return;
+        }
+        if data.iter().all(|byte| *byte == 0) {
+            writeln!(self.rendered, "admin_state_account: uninitialized").unwrap();
+            return;
+        }
+        let src = array_ref![data, 0, ADMIN_LEN];
+        let (token_accounts, fee, is_initialized) = array_refs![src, TOKEN_ACCOUNTS_LEN, 4, 1];
+        writeln!(self.rendered, "admin_state_account:").unwrap();
+        writeln!(
+            self.rendered,
+            "  is_initialized: {}",
+            is_initialized[0] != 0
+        )
+        .unwrap();
+        writeln!(self.rendered, "  fee: {}", u32::from_le_bytes(*fee)).unwrap();
+        for mint in mints.iter() {
+            let payable = match admin::get(mint, token_accounts) {
+                Ok(account) => self.key(&account),
+                Err(_) => "none".to_string(),
+            };
+            let mint = self.key(mint);
+            writeln!(self.rendered, "  payable {}: {}", mint, payable).unwrap();
+        }
+    }
+
+    fn render_escrow(&mut self, data: &[u8]) -> Result<(), TestError> {
+        if data.iter().all(|byte| *byte == 0) {
+            writeln!(self.rendered, "escrow_state_account: uninitialized").unwrap();
+            return Ok(());
+        }
+        let mut data = data.to_vec();
+        let mut escrow_info = Escrow::new();
+        let escrow_info = Escrow::unpack(&mut data, &mut escrow_info)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        let mut rentings = vec![];
+        for i in 0..escrow_info.rentings.size {
+            let renting = escrow_info
+                .rentings
+                .get_renting(i as usize)
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+            if renting.is_valid() {
+                rentings.push(renting);
+            }
+        }
+        let mut keys = vec![];
+        for pubkey in [
+            escrow_info.lender_pubkey,
+            escrow_info.temp_nft_account_pubkey,
+            escrow_info.lender_token_account_pubkey,
+            escrow_info.pda_token_account_pubkey,
+        ]
+        .iter()
+        {
+            keys.push(self.key(pubkey));
+        }
+        // Bucket positions depend on the renter's address, so rentings are listed in key order
+        let mut rendered_rentings = vec![];
+        for renting in rentings.iter() {
+            rendered_rentings.push(format!(
+                "  renting: renter {}, rented_at {}, duration {}, amount {}",
+                self.key(&renting.renter_address),
+                renting.rented_at,
+                renting.rent_duration,
+                renting.rent_amount
+            ));
+        }
+        rendered_rentings.sort();
+
+        let rendered = &mut self.rendered;
+        writeln!(rendered, "escrow_state_account:").unwrap();
+        writeln!(rendered, "  is_initialized: {}", escrow_info.is_initialized).unwrap();
+        writeln!(rendered, "  lender: {}", keys[0]).unwrap();
+        writeln!(rendered, "  temp_nft_account: {}", keys[1]).unwrap();
+        writeln!(rendered, "  lender_token_account: {}", keys[2]).unwrap();
+        writeln!(rendered, "  pda_token_account: {}", keys[3]).unwrap();
+        writeln!(
+            rendered,
+            "  daily_rent_price: {}",
+            escrow_info.daily_rent_price
+        )
+        .unwrap();
+        writeln!(rendered, "  max_renters: {}", escrow_info.max_renters).unwrap();
+        writeln!(
+            rendered,
+            "  max_rent_duration: {}",
+            escrow_info.max_rent_duration
+        )
+        .unwrap();
+        writeln!(
+            rendered,
+            "  current_renters: {}",
+            escrow_info.current_renters
+        )
+        .unwrap();
+        writeln!(
+            rendered,
+            "  rentings: length {}, capacity {}",
+            escrow_info.rentings.length, escrow_info.rentings.capacity
+        )
+        .unwrap();
+        for renting in rendered_rentings.iter() {
+            writeln!(rendered, "{}", renting).unwrap();
+        }
+        Ok(())
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(GOLDEN_DIR).join(format!("{}.txt", self.name))
+    }
+
+    pub fn check(&self) -> Result<(), TestError> {
+        let path = self.path();
+        if std::env::var(UPDATE_GOLDEN_VAR).is_ok() {
+            fs::create_dir_all(GOLDEN_DIR).map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+            return fs::write(&path, &self.rendered)
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)));
+        }
+        let expected = fs::read_to_string(&path).map_err(|e| {
+            TestError::TestError(format!(
+                "Cannot read {}: {}. Run with {}=1 to create it.",
+                path.display(),
+                e,
+                UPDATE_GOLDEN_VAR
+            ))
+        })?;
+        if expected == self.rendered {
+            return Ok(());
+        }
+        Err(TestError::TestError(format!(
+            "{} differs from the golden file:\n{}Run with {}=1 to accept the changes.",
+            path.display(),
+            diff(&expected, &self.rendered),
+            UPDATE_GOLDEN_VAR
+        )))
+    }
+}
+
+// Line diff of the two texts, from their longest common subsequence of lines.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            writeln!(diff, "  {}", expected[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || common[i][j + 1] >= common[i + 1][j])
+        {
+            writeln!(diff, "+ {}", actual[j]).unwrap();
+            j += 1;
+        } else {
+            writeln!(diff, "- {}", expected[i]).unwrap();
+            i += 1;
+        }
+    }
+    diff
+}
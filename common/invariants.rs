@@ -0,0 +1,227 @@
+use {
+    crate::common::fee::calculate_prepaid_rent,
+    crate::common::labels::{account_dump, balance_deltas, Labeled},
+    crate::common::state::State,
+    crate::common::telemetry::instruction_names,
+    crate::common::token_2022::unpack_token_account,
+    solana_program::{instruction::Instruction, pubkey::Pubkey},
+    solana_renft_collateral_free::{renting::Renting, state::Escrow},
+    solana_sdk::{account::Account, signature::Signer},
+    std::collections::{BTreeMap, HashMap},
+};
+
+// Checked by `State` after every transaction sent by the `renft` helpers. Tests that corrupt the
+// fixture on purpose can turn the checks off with `enabled`.
+pub struct Invariants {
+    pub enabled: bool,
+    // Prepaid rent of each renting, keyed by renter and rented_at, at the daily price it was
+    // rented at. An `EditLend` after the rent must not change what the PDA owes.
+    prepaid: HashMap<(Pubkey, i64), u64>,
+}
+
+impl Default for Invariants {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prepaid: HashMap::new(),
+        }
+    }
+}
+
+fn token_balance(account: Option<&Account>) -> Option<(Pubkey, u64)> {
+    account
+        .filter(|account| account.owner == spl_token::id() || account.owner == spl_token_2022::id())
+        .and_then(|account| unpack_token_account(&account.data).ok())
+        .map(|token_account| (token_account.mint, token_account.amount))
+}
+
+// Escrow instructions only move tokens, so every mint must add up to the same amount over the
+// accounts they write to. Transactions that also wrap or close accounts are not checked.
+fn check_conservation(
+    before: &[(Pubkey, Option<Account>)],
+    after: &[(Pubkey, Option<Account>)],
+    violations: &mut Vec<String>,
+) {
+    let mut totals: BTreeMap<Pubkey, (u128, u128)> = BTreeMap::new();
+    for (_, account) in before.iter() {
+        if let Some((mint, amount)) = token_balance(account.as_ref()) {
+            totals.entry(mint).or_default().0 += amount as u128;
+        }
+    }
+    for (_, account) in after.iter() {
+        if let Some((mint, amount)) = token_balance(account.as_ref()) {
+            totals.entry(mint).or_default().1 += amount as u128;
+        }
+    }
+    for (mint, (before, after)) in totals.iter() {
+        if before != after {
+            violations.push(format!(
+                "{} tokens are not conserved: {} before, {} after",
+                Labeled(mint),
+                before,
+                after
+            ));
+        }
+    }
+}
+
+struct EscrowSummary {
+    current_renters: u32,
+    max_renters: u32,
+    daily_rent_price: u64,
+    temp_nft_account_pubkey: Pubkey,
+    pda_token_account_pubkey: Pubkey,
+    rentings: Vec<Renting>,
+}
+
+fn summarize_escrow(account: Option<Account>) -> Option<EscrowSummary> {
+    let mut data = account?.data;
+    if data.iter().all(|byte| *byte == 0) {
+        return None;
+    }
+    let mut escrow_info = Escrow::new();
+    let escrow_info = Escrow::unpack(&mut data, &mut escrow_info).ok()?;
+    if !escrow_info.is_initialized {
+        return None;
+    }
+    let mut rentings = vec![];
+    for i in 0..escrow_info.rentings.size {
+        let renting = escrow_info.rentings.get_renting(i as usize).ok()?;
+        if renting.is_valid() {
+            rentings.push(renting);
+        }
+    }
+    Some(EscrowSummary {
+        current_renters: escrow_info.current_renters,
+        max_renters: escrow_info.max_renters,
+        daily_rent_price: escrow_info.daily_rent_price,
+        temp_nft_account_pubkey: escrow_info.temp_nft_account_pubkey,
+        pda_token_account_pubkey: escrow_info.pda_token_account_pubkey,
+        rentings,
+    })
+}
+
+async fn check_escrow(test_state: &mut State, violations: &mut Vec<String>) {
+    let escrow_state_account = test_state
+        .bench
+        .get_account(&test_state.escrow_state_account_keypair.pubkey())
+        .await;
+    let escrow = match summarize_escrow(escrow_state_account) {
+        Some(escrow) => escrow,
+        // Not lent, nothing is owed
+        None => {
+            test_state.invariants.prepaid.clear();
+            return;
+        }
+    };
+
+    if escrow.current_renters as usize != escrow.rentings.len() {
+        violations.push(format!(
+            "current_renters is {} but there are {} valid rentings",
+            escrow.current_renters,
+            escrow.rentings.len()
+        ));
+    }
+    let rented: u64 = escrow
+        .rentings
+        .iter()
+        .map(|renting| renting.rent_amount as u64)
+        .sum();
+    if rented > escrow.max_renters as u64 {
+        violations.push(format!(
+            "{} rented in total, more than max_renters {}",
+            rented, escrow.max_renters
+        ));
+    }
+
+    let mut prepaid = HashMap::new();
+    for renting in escrow.rentings.iter() {
+        let key = (renting.renter_address, renting.rented_at);
+        let amount = match test_state.invariants.prepaid.get(&key) {
+            Some(amount) => *amount,
+            None => match calculate_prepaid_rent(
+                escrow.daily_rent_price,
+                renting.rent_amount,
+                renting.rent_duration,
+            ) {
+                Ok(amount) => amount,
+                Err(e) => {
+                    violations.push(format!("prepaid rent of a renting: {}", e));
+                    continue;
+                }
+            },
+        };
+        prepaid.insert(key, amount);
+    }
+    let outstanding: u64 = prepaid.values().sum();
+    test_state.invariants.prepaid = prepaid;
+
+    let pda_token_account = test_state
+        .bench
+        .get_account(&escrow.pda_token_account_pubkey)
+        .await;
+    if let Some((_, amount)) = token_balance(pda_token_account.as_ref()) {
+        if amount != outstanding {
+            violations.push(format!(
+                "{} holds {} but {} of prepaid rent is outstanding",
+                Labeled(&escrow.pda_token_account_pubkey),
+                amount,
+                outstanding
+            ));
+        }
+    }
+
+    let temp_nft_account = test_state
+        .bench
+        .get_account(&escrow.temp_nft_account_pubkey)
+        .await;
+    let held = token_balance(temp_nft_account.as_ref()).map_or(0, |(_, amount)| amount);
+    if held == 0 {
+        violations.push(format!(
+            "{} no longer holds the lent NFT",
+            Labeled(&escrow.temp_nft_account_pubkey)
+        ));
+    }
+}
+
+pub async fn check(
+    test_state: &mut State,
+    instructions: &[Instruction],
+    before: &[(Pubkey, Option<Account>)],
+    after: &[(Pubkey, Option<Account>)],
+) -> Vec<String> {
+    let mut violations = vec![];
+    if !test_state.invariants.enabled {
+        return violations;
+    }
+    if instructions
+        .iter()
+        .all(|instruction| instruction.program_id == test_state.program_id)
+    {
+        check_conservation(before, after, &mut violations);
+    }
+    check_escrow(test_state, &mut violations).await;
+    violations
+}
+
+pub fn report(
+    instructions: &[Instruction],
+    violations: &[String],
+    before: &[(Pubkey, Option<Account>)],
+    after: &[(Pubkey, Option<Account>)],
+) -> String {
+    let mut report = format!(
+        "Invariants violated after {}:\n",
+        instruction_names(instructions)
+    );
+    for violation in violations.iter() {
+        report.push_str(&format!("  - {}\n", violation));
+    }
+    report.push_str("Balance changes:\n");
+    report.push_str(&balance_deltas(before, after));
+    report.push_str("\nAccounts after:\n");
+    for (address, account) in after.iter() {
+        report.push_str(&format!("  {}\n", account_dump(address, account)));
+    }
+    report
+}
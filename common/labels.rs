@@ -0,0 +1,135 @@
+use {
+    lazy_static::lazy_static,
+    solana_program::{program_pack::Pack, pubkey::Pubkey, system_program},
+    solana_sdk::account::Account,
+    std::{collections::HashMap, fmt, str::FromStr, sync::RwLock},
+};
+
+lazy_static! {
+    // Shared by every test of a binary. Fixture keys are random, so tests running in parallel
+    // only share the labels of fixed keys such as the admin and the program, which agree.
+    static ref LABELS: RwLock<HashMap<Pubkey, String>> = RwLock::new(
+        [
+            (system_program::id(), "system_program"),
+            (spl_token::id(), "token_program"),
+            (spl_token_2022::id(), "token_2022_program"),
+        ]
+        .iter()
+        .map(|(address, label)| (*address, label.to_string()))
+        .collect()
+    );
+}
+
+// Base58 encodings of 32 bytes are 32 to 44 characters long.
+const MIN_PUBKEY_STR_LEN: usize = 32;
+const MAX_PUBKEY_STR_LEN: usize = 44;
+
+pub fn register(address: &Pubkey, label: &str) {
+    LABELS.write().unwrap().insert(*address, label.to_string());
+}
+
+// Registers the accounts of a fixture, and prints the mapping and writes it to the trace, so
+// that labelled output can still be read back to raw addresses with or without a subscriber.
+pub fn register_all(labels: &[(&str, Pubkey)]) {
+    for (label, address) in labels.iter() {
+        register(address, label);
+        println!("{}: {}", label, address);
+        tracing::info!(label = *label, address = %address, "label");
+    }
+}
+
+pub fn label(address: &Pubkey) -> Option<String> {
+    LABELS.read().unwrap().get(address).cloned()
+}
+
+// Displays the label of a pubkey, or the pubkey itself when it has none.
+pub struct Labeled<'a>(pub &'a Pubkey);
+
+impl fmt::Display for Labeled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match label(self.0) {
+            Some(label) => write!(f, "{}", label),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+// Replaces every labelled pubkey found in `text` by its label.
+pub fn substitute(text: &str) -> String {
+    let labels = LABELS.read().unwrap();
+    let mut substituted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        let (before, word) = rest.split_at(start);
+        substituted.push_str(before);
+        let end = word
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or_else(|| word.len());
+        let (word, after) = word.split_at(end);
+        let label = if (MIN_PUBKEY_STR_LEN..=MAX_PUBKEY_STR_LEN).contains(&word.len()) {
+            Pubkey::from_str(word)
+                .ok()
+                .and_then(|address| labels.get(&address))
+        } else {
+            None
+        };
+        substituted.push_str(label.map(String::as_str).unwrap_or(word));
+        rest = after;
+    }
+    substituted.push_str(rest);
+    substituted
+}
+
+fn token_amount(account: Option<&Account>) -> Option<u64> {
+    account
+        .filter(|account| account.owner == spl_token::id())
+        .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+        .map(|token_account| token_account.amount)
+}
+
+pub fn account_dump(address: &Pubkey, account: &Option<Account>) -> String {
+    match account {
+        Some(account) => {
+            let mut dump = format!(
+                "{}: {} lamports, owner {}, {} bytes",
+                Labeled(address),
+                account.lamports,
+                Labeled(&account.owner),
+                account.data.len()
+            );
+            if let Some(amount) = token_amount(Some(account)) {
+                dump.push_str(&format!(", {} tokens", amount));
+            }
+            dump
+        }
+        None => format!("{}: does not exist", Labeled(address)),
+    }
+}
+
+// One line per account whose lamports or token amount changed between the two snapshots, which
+// must list the same addresses in the same order.
+pub fn balance_deltas(
+    before: &[(Pubkey, Option<Account>)],
+    after: &[(Pubkey, Option<Account>)],
+) -> String {
+    let lamports = |account: &Option<Account>| account.as_ref().map_or(0, |a| a.lamports) as i128;
+    before
+        .iter()
+        .zip(after.iter())
+        .filter_map(|((address, before), (_, after))| {
+            let lamports_delta = lamports(after) - lamports(before);
+            let tokens_delta = token_amount(after.as_ref()).unwrap_or_default() as i128
+                - token_amount(before.as_ref()).unwrap_or_default() as i128;
+            if lamports_delta == 0 && tokens_delta == 0 {
+                return None;
+            }
+            Some(format!(
+                "{}: {:+} lamports, {:+} tokens",
+                Labeled(address),
+                lamports_delta,
+                tokens_delta
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,17 +1,45 @@
+pub mod async_rpc_bench;
+pub mod async_rpc_renft;
+pub mod async_rpc_state;
 pub mod bench;
+pub mod compute_budget;
+pub mod coverage;
+pub mod decode;
 pub mod error;
+pub mod fee;
+pub mod fixture;
+pub mod golden;
+pub mod invariants;
+pub mod labels;
 pub mod program;
+pub mod progress;
 pub mod renft;
 pub mod rpc_bench;
 pub mod rpc_client_utils;
 pub mod rpc_renft;
 pub mod rpc_state;
+pub mod simulation;
+pub mod spoof;
 pub mod state;
+pub mod substitution;
+pub mod telemetry;
+pub mod token_2022;
 use {
     crate::common::error::TestError,
-    arrayref::{array_mut_ref, mut_array_refs},
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        instruction::Instruction,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
+    },
     solana_renft_collateral_free::{
-        instruction::EscrowInstruction, map::Bucket, state::Escrow, util::calculate_rentings_size,
+        admin::{ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
+        instruction::EscrowInstruction,
+        map::Bucket,
+        renting::Renting,
+        state::Escrow,
+        util::calculate_rentings_size,
     },
     solana_sdk::signature::Keypair,
 };
@@ -30,9 +58,80 @@ pub fn clone_keypair(source: &Keypair) -> Keypair {
     Keypair::from_bytes(&source.to_bytes()).unwrap()
 }
 
-pub fn calculate_escrow_state_account_len(max_renters: u32) -> usize {
-    (calculate_rentings_size(max_renters).unwrap() as usize) * Bucket::BUCKET_BUFFER_LEN
-        + Escrow::LEN
+pub fn calculate_escrow_state_account_len(max_renters: u32) -> Result<usize, TestError> {
+    let rentings_size = calculate_rentings_size(max_renters)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    let escrow_state_account_len = (rentings_size as usize)
+        .checked_mul(Bucket::BUCKET_BUFFER_LEN)
+        .and_then(|len| len.checked_add(Escrow::LEN))
+        .ok_or(TestError::TestError(
+            "Escrow state account length overflow.".to_string(),
+        ))?;
+    if escrow_state_account_len > MAX_PERMITTED_DATA_LENGTH as usize {
+        return Err(TestError::TestError(format!(
+            "Escrow state account length {} exceeds {}.",
+            escrow_state_account_len, MAX_PERMITTED_DATA_LENGTH
+        )));
+    }
+    Ok(escrow_state_account_len)
+}
+
+// The first valid renting stored in the data of an escrow state account, or
+// `Renting::NULL_RENTING` when there is none.
+pub fn find_valid_renting(escrow_state_account_data: &[u8]) -> Result<Renting, TestError> {
+    let mut data = escrow_state_account_data.to_vec();
+    let mut escrow_info = Escrow::new();
+
+    let escrow_info = Escrow::unpack(&mut data, &mut escrow_info)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+    for i in 0..escrow_info.rentings.size {
+        let renting = escrow_info
+            .rentings
+            .get_renting(i as usize)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        if renting.is_valid() {
+            return Ok(renting);
+        }
+    }
+    Ok(Renting::NULL_RENTING)
+}
+
+// Copies the payable token accounts stored in the data of an admin state account into
+// `token_accounts` and returns the fee.
+pub fn unpack_admin_state(
+    admin_state_account_data: &[u8],
+    token_accounts: &mut [u8; TOKEN_ACCOUNTS_LEN],
+) -> u32 {
+    let src = array_ref![admin_state_account_data, 0, ADMIN_LEN];
+    let (token_accounts_src, fee_src, _) = array_refs![src, TOKEN_ACCOUNTS_LEN, 4, 1];
+    token_accounts.clone_from_slice(token_accounts_src);
+    u32::from_le_bytes(*fee_src)
+}
+
+// Creates `wrapped_sol_account` holding `lamports`, which must include its rent exemption, as a
+// native mint token account of `owner`.
+pub fn wrap_sol_instructions(
+    owner: &Pubkey,
+    wrapped_sol_account: &Pubkey,
+    lamports: u64,
+) -> Result<Vec<Instruction>, TestError> {
+    Ok(vec![
+        system_instruction::create_account(
+            owner,
+            wrapped_sol_account,
+            lamports,
+            spl_token::state::Account::get_packed_len() as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            wrapped_sol_account,
+            &spl_token::native_mint::id(),
+            owner,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+    ])
 }
 
 pub fn pack_instruction(instruction: EscrowInstruction, dst: &mut [u8]) -> Result<(), TestError> {
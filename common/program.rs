@@ -1,13 +1,17 @@
 use {
-    crate::common::{rpc_client_utils::send_and_confirm_messages_with_spinner, TestError},
+    crate::common::{
+        progress::{ProgressEvent, ProgressReporter},
+        rpc_client_utils::{send_and_confirm_messages_with_spinner, SendConfig},
+        TestError,
+    },
     chrono_humanize::{Accuracy, HumanTime, Tense},
     log::*,
-    solana_client::{rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig},
+    solana_client::rpc_client::RpcClient,
     solana_sdk::{
         account::Account, bpf_loader, bpf_loader_upgradeable::UpgradeableLoaderState,
-        commitment_config::CommitmentConfig, loader_instruction, message::Message,
-        packet::PACKET_DATA_SIZE, pubkey::Pubkey, rent::Rent, signature::Signature,
-        signer::keypair::Keypair, signer::Signer, system_instruction, transaction::Transaction,
+        loader_instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey, rent::Rent,
+        signature::Signature, signer::keypair::Keypair, signer::Signer, system_instruction,
+        transaction::Transaction,
     },
     std::{
         fs::File,
@@ -72,6 +76,8 @@ pub fn deploy_program(
     signers: Vec<&dyn Signer>,
     buffer_keypair: &Keypair,
     rpc_client: &RpcClient,
+    send_config: &SendConfig,
+    progress: &dyn ProgressReporter,
 ) -> Result<(), TestError> {
     let buffer_signer = buffer_keypair as &dyn Signer;
     let buffer_pubkey = buffer_keypair.pubkey();
@@ -85,6 +91,8 @@ pub fn deploy_program(
         .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
     do_process_program_write_and_deploy(
         rpc_client,
+        send_config,
+        progress,
         signers,
         &account.data,
         account.data.len(),
@@ -100,6 +108,8 @@ pub fn deploy_program(
 
 fn do_process_program_write_and_deploy(
     rpc_client: &RpcClient,
+    send_config: &SendConfig,
+    progress: &dyn ProgressReporter,
     signers: Vec<&dyn Signer>,
     program_data: &[u8],
     buffer_data_len: usize,
@@ -165,6 +175,8 @@ fn do_process_program_write_and_deploy(
 
     send_deploy_messages(
         rpc_client,
+        send_config,
+        progress,
         signers,
         &initial_message,
         &Some(write_messages),
@@ -179,6 +191,8 @@ fn do_process_program_write_and_deploy(
 }
 fn send_deploy_messages(
     rpc_client: &RpcClient,
+    send_config: &SendConfig,
+    progress: &dyn ProgressReporter,
     signers: Vec<&dyn Signer>,
     initial_message: &Option<Message>,
     write_messages: &Option<Vec<Message>>,
@@ -189,7 +203,7 @@ fn send_deploy_messages(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let payer_signer = signers[0];
 
-    println!("Sending initial message");
+    progress.report(ProgressEvent::Stage("Sending initial message".to_string()));
     if let Some(message) = initial_message {
         if let Some(initial_signer) = initial_signer {
             trace!("Preparing the required accounts");
@@ -208,11 +222,8 @@ fn send_deploy_messages(
             rpc_client
                 .send_and_confirm_transaction_with_spinner_and_config(
                     &initial_transaction,
-                    CommitmentConfig::confirmed(),
-                    RpcSendTransactionConfig {
-                        skip_preflight: true,
-                        ..RpcSendTransactionConfig::default()
-                    },
+                    send_config.commitment,
+                    send_config.rpc_send_transaction_config(),
                 )
                 .map_err(|err| format!("Account allocation failed: {}", err))?;
         } else {
@@ -220,7 +231,7 @@ fn send_deploy_messages(
         }
     }
 
-    println!("Sending write messages");
+    progress.report(ProgressEvent::Stage("Sending write messages".to_string()));
     if let Some(write_messages) = write_messages {
         if let Some(write_signer) = write_signer {
             trace!("Writing program data");
@@ -228,6 +239,8 @@ fn send_deploy_messages(
                 rpc_client,
                 write_messages,
                 &[payer_signer, write_signer],
+                send_config,
+                progress,
             )
             .map_err(|err| format!("Data writes to account failed: {}", err))?
             .into_iter()
@@ -245,7 +258,7 @@ fn send_deploy_messages(
         }
     }
 
-    println!("Sending final message");
+    progress.report(ProgressEvent::Stage("Sending final message".to_string()));
     if let Some(message) = final_message {
         if let Some(final_signers) = final_signers {
             trace!("Deploying program");
@@ -258,17 +271,14 @@ fn send_deploy_messages(
             rpc_client
                 .send_and_confirm_transaction_with_spinner_and_config(
                     &final_tx,
-                    CommitmentConfig::confirmed(),
-                    RpcSendTransactionConfig {
-                        skip_preflight: true,
-                        ..RpcSendTransactionConfig::default()
-                    },
+                    send_config.commitment,
+                    send_config.rpc_send_transaction_config(),
                 )
                 .map_err(|e| format!("Deploying program failed: {}", e))?;
         }
     }
 
-    println!("Program deployed");
+    progress.report(ProgressEvent::Stage("Program deployed".to_string()));
     Ok(())
 }
 
@@ -0,0 +1,138 @@
+use {
+    indicatif::ProgressBar,
+    std::{fmt, sync::Mutex, time::Duration},
+};
+
+// Progress of a deploy or of a batch of transactions sent by `rpc_client_utils`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    Stage(String),
+    Status {
+        confirmed: usize,
+        total: usize,
+        status: String,
+        // Set while waiting for blocks, to show when the batch is re-signed
+        block_height: Option<u64>,
+        last_valid_block_height: u64,
+    },
+    BlockhashExpired {
+        retries_remaining: usize,
+    },
+}
+
+impl fmt::Display for ProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgressEvent::Stage(stage) => write!(f, "{}", stage),
+            ProgressEvent::Status {
+                confirmed,
+                total,
+                status,
+                block_height,
+                last_valid_block_height,
+            } => {
+                // An empty batch has nothing left to confirm
+                let percent = if *total == 0 {
+                    100.
+                } else {
+                    *confirmed as f64 * 100. / *total as f64
+                };
+                write!(f, "{:>5.1}% | {:<40}", percent, status)?;
+                if let Some(block_height) = block_height {
+                    write!(
+                        f,
+                        " [block height {}; re-sign in {} blocks]",
+                        block_height,
+                        last_valid_block_height.saturating_sub(*block_height),
+                    )?;
+                }
+                Ok(())
+            }
+            ProgressEvent::BlockhashExpired { retries_remaining } => write!(
+                f,
+                "Blockhash expired. {} retries remaining",
+                retries_remaining
+            ),
+        }
+    }
+}
+
+pub trait ProgressReporter {
+    fn report(&self, event: ProgressEvent);
+}
+
+pub struct QuietProgress;
+
+impl ProgressReporter for QuietProgress {
+    fn report(&self, _event: ProgressEvent) {}
+}
+
+// One line per event, which is how progress was always printed
+pub struct LineProgress;
+
+impl ProgressReporter for LineProgress {
+    fn report(&self, event: ProgressEvent) {
+        println!("{}", event);
+    }
+}
+
+// A single spinner line that is updated in place, for interactive terminals
+pub struct BarProgress {
+    bar: ProgressBar,
+}
+
+impl BarProgress {
+    pub fn new() -> Self {
+        let bar = ProgressBar::new_spinner();
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Self { bar }
+    }
+}
+
+impl Default for BarProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressReporter for BarProgress {
+    fn report(&self, event: ProgressEvent) {
+        self.bar.set_message(event.to_string());
+    }
+}
+
+impl Drop for BarProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+// Keeps every event so that a test can report on them afterwards, and passes them on to
+// `display` when there is one
+#[derive(Default)]
+pub struct RecordingProgress {
+    events: Mutex<Vec<ProgressEvent>>,
+    display: Option<Box<dyn ProgressReporter>>,
+}
+
+impl RecordingProgress {
+    pub fn with_display(display: Box<dyn ProgressReporter>) -> Self {
+        Self {
+            events: Mutex::new(vec![]),
+            display: Some(display),
+        }
+    }
+
+    pub fn events(&self) -> Vec<ProgressEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl ProgressReporter for RecordingProgress {
+    fn report(&self, event: ProgressEvent) {
+        if let Some(display) = &self.display {
+            display.report(event.clone());
+        }
+        self.events.lock().unwrap().push(event);
+    }
+}
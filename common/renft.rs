@@ -1,8 +1,11 @@
 use {
+    crate::common::compute_budget::ComputeBudget,
     crate::common::error::TestError,
-    crate::common::state::State,
-    crate::common::{clone_keypair, pack_instruction},
+    crate::common::simulation::Simulation,
+    crate::common::state::{Currency, State},
+    crate::common::{clone_keypair, pack_instruction, wrap_sol_instructions},
     solana_program::instruction::{AccountMeta, Instruction},
+    solana_program::program_pack::Pack,
     solana_program::pubkey::Pubkey,
     solana_renft_collateral_free::instruction::EscrowInstruction,
     solana_sdk::signature::Signer,
@@ -15,6 +18,23 @@ pub async fn lend(
     max_renters: u32,
     max_rent_duration: u8,
     test_state: &mut State,
+) -> Result<(), TestError> {
+    lend_with_currency(
+        daily_rent_price,
+        max_renters,
+        max_rent_duration,
+        Currency::Sol,
+        test_state,
+    )
+    .await
+}
+
+pub async fn lend_with_currency(
+    daily_rent_price: u64,
+    max_renters: u32,
+    max_rent_duration: u8,
+    currency: Currency,
+    test_state: &mut State,
 ) -> Result<(), TestError> {
     lend_impl(
         daily_rent_price,
@@ -22,8 +42,8 @@ pub async fn lend(
         max_rent_duration,
         &clone_keypair(&test_state.lender_keypair),
         &test_state.lender_temp_nft_account_keypair.pubkey(),
-        &test_state.lender_sol_token_account_keypair.pubkey(),
-        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.lender_token_account_pubkey(currency),
+        &test_state.pda_token_account_pubkey(currency),
         &test_state.escrow_state_account_keypair.pubkey(),
         &test_state.admin_state_account_keypair.pubkey(),
         test_state,
@@ -32,18 +52,19 @@ pub async fn lend(
     Ok(())
 }
 
-pub async fn lend_impl(
+pub fn lend_instruction(
     daily_rent_price: u64,
     max_renters: u32,
     max_rent_duration: u8,
-    lender_keypair: &Keypair,
+    lender_pubkey: &Pubkey,
     lender_temp_nft_account_pubkey: &Pubkey,
     lender_sol_token_account_pubkey: &Pubkey,
     pda_sol_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::Lend {
         daily_rent_price,
         max_renters,
@@ -53,22 +74,50 @@ pub async fn lend_impl(
     let data: &mut [u8] = &mut [0; 17];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*lender_temp_nft_account_pubkey, false),
+            AccountMeta::new_readonly(*lender_sol_token_account_pubkey, false),
+            AccountMeta::new(*pda_sol_token_account_pubkey, false),
+            AccountMeta::new(*escrow_state_account_pubkey, false),
+            AccountMeta::new_readonly(*admin_state_account_pubkey, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*lender_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn lend_impl(
+    daily_rent_price: u64,
+    max_renters: u32,
+    max_rent_duration: u8,
+    lender_keypair: &Keypair,
+    lender_temp_nft_account_pubkey: &Pubkey,
+    lender_sol_token_account_pubkey: &Pubkey,
+    pda_sol_token_account_pubkey: &Pubkey,
+    escrow_state_account_pubkey: &Pubkey,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = lend_instruction(
+        daily_rent_price,
+        max_renters,
+        max_rent_duration,
+        &lender_keypair.pubkey(),
+        lender_temp_nft_account_pubkey,
+        lender_sol_token_account_pubkey,
+        pda_sol_token_account_pubkey,
+        escrow_state_account_pubkey,
+        admin_state_account_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*lender_temp_nft_account_pubkey, false),
-                    AccountMeta::new_readonly(*lender_sol_token_account_pubkey, false),
-                    AccountMeta::new(*pda_sol_token_account_pubkey, false),
-                    AccountMeta::new(*escrow_state_account_pubkey, false),
-                    AccountMeta::new_readonly(*admin_state_account_pubkey, false),
-                    AccountMeta::new_readonly(spl_token::ID, false),
-                    AccountMeta::new_readonly(lender_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&lender_keypair.pubkey()),
             &[&lender_keypair],
         )
@@ -77,11 +126,18 @@ pub async fn lend_impl(
 }
 
 pub async fn stop_lend(test_state: &mut State) -> Result<(), TestError> {
+    stop_lend_with_currency(Currency::Sol, test_state).await
+}
+
+pub async fn stop_lend_with_currency(
+    currency: Currency,
+    test_state: &mut State,
+) -> Result<(), TestError> {
     stop_lend_impl(
         &clone_keypair(&test_state.lender_keypair),
         &test_state.lender_temp_nft_account_keypair.pubkey(),
-        &test_state.lender_main_nft_account_keypair.pubkey(),
-        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.lender_main_nft_account_pubkey(),
+        &test_state.pda_token_account_pubkey(currency),
         &test_state.escrow_state_account_keypair.pubkey(),
         test_state,
     )
@@ -89,35 +145,58 @@ pub async fn stop_lend(test_state: &mut State) -> Result<(), TestError> {
     Ok(())
 }
 
-pub async fn stop_lend_impl(
-    lender_keypair: &Keypair,
+pub fn stop_lend_instruction(
+    lender_pubkey: &Pubkey,
     lender_temp_nft_account_pubkey: &Pubkey,
     lender_main_nft_account_pubkey: &Pubkey,
     pda_sol_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    pda_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::StopLend {};
 
     let data: &mut [u8] = &mut [0; 4];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*lender_temp_nft_account_pubkey, false),
+            AccountMeta::new(*lender_main_nft_account_pubkey, false),
+            AccountMeta::new(*pda_sol_token_account_pubkey, false),
+            AccountMeta::new(*escrow_state_account_pubkey, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*pda_pubkey, false),
+            AccountMeta::new_readonly(*lender_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn stop_lend_impl(
+    lender_keypair: &Keypair,
+    lender_temp_nft_account_pubkey: &Pubkey,
+    lender_main_nft_account_pubkey: &Pubkey,
+    pda_sol_token_account_pubkey: &Pubkey,
+    escrow_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = stop_lend_instruction(
+        &lender_keypair.pubkey(),
+        lender_temp_nft_account_pubkey,
+        lender_main_nft_account_pubkey,
+        pda_sol_token_account_pubkey,
+        escrow_state_account_pubkey,
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*lender_temp_nft_account_pubkey, false),
-                    AccountMeta::new(*lender_main_nft_account_pubkey, false),
-                    AccountMeta::new(*pda_sol_token_account_pubkey, false),
-                    AccountMeta::new(*escrow_state_account_pubkey, false),
-                    AccountMeta::new_readonly(spl_token::ID, false),
-                    AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                    AccountMeta::new_readonly(lender_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&lender_keypair.pubkey()),
             &[&lender_keypair],
         )
@@ -133,9 +212,9 @@ pub async fn edit_lend(
         daily_rent_price,
         max_rent_duration,
         &clone_keypair(&test_state.lender_keypair),
-        &test_state.lender_sol_token_account_keypair.pubkey(),
-        &test_state.pda_usdc_token_account_keypair.pubkey(),
-        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Usdc),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
         &test_state.escrow_state_account_keypair.pubkey(),
         &test_state.admin_state_account_keypair.pubkey(),
         test_state,
@@ -144,17 +223,19 @@ pub async fn edit_lend(
     Ok(())
 }
 
-pub async fn edit_lend_impl(
+pub fn edit_lend_instruction(
     daily_rent_price: u64,
     max_rent_duration: u8,
-    lender_keypair: &Keypair,
+    lender_pubkey: &Pubkey,
     lender_sol_token_account_pubkey: &Pubkey,
     old_pda_token_account_pubkey: &Pubkey,
     new_pda_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    pda_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::EditLend {
         daily_rent_price,
         max_rent_duration,
@@ -163,23 +244,50 @@ pub async fn edit_lend_impl(
     let data: &mut [u8] = &mut [0; 13];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*lender_sol_token_account_pubkey, false),
+            AccountMeta::new(*old_pda_token_account_pubkey, false),
+            AccountMeta::new(*new_pda_token_account_pubkey, false),
+            AccountMeta::new(*escrow_state_account_pubkey, false),
+            AccountMeta::new_readonly(*admin_state_account_pubkey, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*pda_pubkey, false),
+            AccountMeta::new_readonly(*lender_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn edit_lend_impl(
+    daily_rent_price: u64,
+    max_rent_duration: u8,
+    lender_keypair: &Keypair,
+    lender_sol_token_account_pubkey: &Pubkey,
+    old_pda_token_account_pubkey: &Pubkey,
+    new_pda_token_account_pubkey: &Pubkey,
+    escrow_state_account_pubkey: &Pubkey,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = edit_lend_instruction(
+        daily_rent_price,
+        max_rent_duration,
+        &lender_keypair.pubkey(),
+        lender_sol_token_account_pubkey,
+        old_pda_token_account_pubkey,
+        new_pda_token_account_pubkey,
+        escrow_state_account_pubkey,
+        admin_state_account_pubkey,
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new_readonly(*lender_sol_token_account_pubkey, false),
-                    AccountMeta::new(*old_pda_token_account_pubkey, false),
-                    AccountMeta::new(*new_pda_token_account_pubkey, false),
-                    AccountMeta::new(*escrow_state_account_pubkey, false),
-                    AccountMeta::new_readonly(*admin_state_account_pubkey, false),
-                    AccountMeta::new_readonly(spl_token::ID, false),
-                    AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                    AccountMeta::new_readonly(lender_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&lender_keypair.pubkey()),
             &[&lender_keypair],
         )
@@ -191,13 +299,21 @@ pub async fn rent(
     rent_amount: u16,
     rent_duration: u8,
     test_state: &mut State,
+) -> Result<(), TestError> {
+    rent_with_currency(rent_amount, rent_duration, Currency::Sol, test_state).await
+}
+pub async fn rent_with_currency(
+    rent_amount: u16,
+    rent_duration: u8,
+    currency: Currency,
+    test_state: &mut State,
 ) -> Result<(), TestError> {
     rent_impl(
         rent_amount,
         rent_duration,
         &clone_keypair(&test_state.renter_keypair),
-        &test_state.renter_temp_sol_token_account_keypair.pubkey(),
-        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.renter_temp_token_account_pubkey(currency),
+        &test_state.pda_token_account_pubkey(currency),
         &test_state.escrow_state_account_keypair.pubkey(),
         test_state,
     )
@@ -205,15 +321,49 @@ pub async fn rent(
 
     Ok(())
 }
-pub async fn rent_impl(
+
+pub async fn rent_with_compute_budget(
     rent_amount: u16,
     rent_duration: u8,
-    renter_keypair: &Keypair,
+    compute_budget: &ComputeBudget,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let instruction = rent_instruction(
+        rent_amount,
+        rent_duration,
+        &renter_keypair.pubkey(),
+        &test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
+    test_state
+        .process_transaction_with_compute_budget(
+            &[instruction],
+            Some(&renter_keypair.pubkey()),
+            &[&renter_keypair],
+            compute_budget,
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub fn rent_instruction(
+    rent_amount: u16,
+    rent_duration: u8,
+    renter_pubkey: &Pubkey,
     renter_temp_sol_token_account_pubkey: &Pubkey,
     pda_sol_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    pda_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::Rent {
         rent_amount,
         rent_duration,
@@ -222,21 +372,44 @@ pub async fn rent_impl(
     let data: &mut [u8] = &mut [0; 7];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*renter_temp_sol_token_account_pubkey, false),
+            AccountMeta::new(*pda_sol_token_account_pubkey, false),
+            AccountMeta::new(*escrow_state_account_pubkey, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*pda_pubkey, false),
+            AccountMeta::new_readonly(*renter_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn rent_impl(
+    rent_amount: u16,
+    rent_duration: u8,
+    renter_keypair: &Keypair,
+    renter_temp_sol_token_account_pubkey: &Pubkey,
+    pda_sol_token_account_pubkey: &Pubkey,
+    escrow_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = rent_instruction(
+        rent_amount,
+        rent_duration,
+        &renter_keypair.pubkey(),
+        renter_temp_sol_token_account_pubkey,
+        pda_sol_token_account_pubkey,
+        escrow_state_account_pubkey,
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*renter_temp_sol_token_account_pubkey, false),
-                    AccountMeta::new(*pda_sol_token_account_pubkey, false),
-                    AccountMeta::new(*escrow_state_account_pubkey, false),
-                    AccountMeta::new_readonly(spl_token::ID, false),
-                    AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                    AccountMeta::new_readonly(renter_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&renter_keypair.pubkey()),
             &[&renter_keypair],
         )
@@ -246,13 +419,21 @@ pub async fn rent_impl(
 }
 
 pub async fn stop_rent(rented_at: i64, test_state: &mut State) -> Result<(), TestError> {
+    stop_rent_with_currency(rented_at, Currency::Sol, test_state).await
+}
+
+pub async fn stop_rent_with_currency(
+    rented_at: i64,
+    currency: Currency,
+    test_state: &mut State,
+) -> Result<(), TestError> {
     stop_rent_impl(
         rented_at,
         &clone_keypair(&test_state.renter_keypair),
-        &test_state.pda_sol_token_account_keypair.pubkey(),
-        &test_state.renter_sol_token_account_keypair.pubkey(),
-        &test_state.lender_sol_token_account_keypair.pubkey(),
-        &test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(currency),
+        &test_state.renter_token_account_pubkey(currency),
+        &test_state.lender_token_account_pubkey(currency),
+        &test_state.admin_token_account_pubkey(currency),
         &test_state.escrow_state_account_keypair.pubkey(),
         &test_state.admin_state_account_keypair.pubkey(),
         test_state,
@@ -262,40 +443,69 @@ pub async fn stop_rent(rented_at: i64, test_state: &mut State) -> Result<(), Tes
     Ok(())
 }
 
-pub async fn stop_rent_impl(
+pub fn stop_rent_instruction(
     rented_at: i64,
-    renter_keypair: &Keypair,
+    renter_pubkey: &Pubkey,
     pda_sol_token_account_pubkey: &Pubkey,
     renter_sol_token_account_pubkey: &Pubkey,
     lender_sol_token_account_pubkey: &Pubkey,
     admin_sol_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    pda_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::StopRent { rented_at };
 
     let data: &mut [u8] = &mut [0; 12];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*pda_sol_token_account_pubkey, false),
+            AccountMeta::new(*renter_sol_token_account_pubkey, false),
+            AccountMeta::new(*lender_sol_token_account_pubkey, false),
+            AccountMeta::new(*admin_sol_token_account_pubkey, false),
+            AccountMeta::new(*escrow_state_account_pubkey, false),
+            AccountMeta::new_readonly(*admin_state_account_pubkey, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*pda_pubkey, false),
+            AccountMeta::new_readonly(*renter_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn stop_rent_impl(
+    rented_at: i64,
+    renter_keypair: &Keypair,
+    pda_sol_token_account_pubkey: &Pubkey,
+    renter_sol_token_account_pubkey: &Pubkey,
+    lender_sol_token_account_pubkey: &Pubkey,
+    admin_sol_token_account_pubkey: &Pubkey,
+    escrow_state_account_pubkey: &Pubkey,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = stop_rent_instruction(
+        rented_at,
+        &renter_keypair.pubkey(),
+        pda_sol_token_account_pubkey,
+        renter_sol_token_account_pubkey,
+        lender_sol_token_account_pubkey,
+        admin_sol_token_account_pubkey,
+        escrow_state_account_pubkey,
+        admin_state_account_pubkey,
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*pda_sol_token_account_pubkey, false),
-                    AccountMeta::new(*renter_sol_token_account_pubkey, false),
-                    AccountMeta::new(*lender_sol_token_account_pubkey, false),
-                    AccountMeta::new(*admin_sol_token_account_pubkey, false),
-                    AccountMeta::new(*escrow_state_account_pubkey, false),
-                    AccountMeta::new_readonly(*admin_state_account_pubkey, false),
-                    AccountMeta::new_readonly(spl_token::ID, false),
-                    AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                    AccountMeta::new_readonly(renter_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&renter_keypair.pubkey()),
             &[&renter_keypair],
         )
@@ -308,14 +518,23 @@ pub async fn claim(
     renter_address: &Pubkey,
     rented_at: i64,
     test_state: &mut State,
+) -> Result<(), TestError> {
+    claim_with_currency(renter_address, rented_at, Currency::Sol, test_state).await
+}
+
+pub async fn claim_with_currency(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    currency: Currency,
+    test_state: &mut State,
 ) -> Result<(), TestError> {
     claim_impl(
         renter_address,
         rented_at,
         &clone_keypair(&test_state.lender_keypair),
-        &test_state.pda_sol_token_account_keypair.pubkey(),
-        &test_state.lender_sol_token_account_keypair.pubkey(),
-        &test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(currency),
+        &test_state.lender_token_account_pubkey(currency),
+        &test_state.admin_token_account_pubkey(currency),
         &test_state.escrow_state_account_keypair.pubkey(),
         &test_state.admin_state_account_keypair.pubkey(),
         test_state,
@@ -325,17 +544,19 @@ pub async fn claim(
     Ok(())
 }
 
-pub async fn claim_impl(
+pub fn claim_instruction(
     renter_address: &Pubkey,
     rented_at: i64,
-    lender_keypair: &Keypair,
+    lender_pubkey: &Pubkey,
     pda_sol_token_account_pubkey: &Pubkey,
     lender_sol_token_account_pubkey: &Pubkey,
     admin_sol_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    pda_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::Claim {
         renter_address: *renter_address,
         rented_at,
@@ -344,23 +565,50 @@ pub async fn claim_impl(
     let data: &mut [u8] = &mut [0; 44];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*pda_sol_token_account_pubkey, false),
+            AccountMeta::new(*lender_sol_token_account_pubkey, false),
+            AccountMeta::new(*admin_sol_token_account_pubkey, false),
+            AccountMeta::new(*escrow_state_account_pubkey, false),
+            AccountMeta::new_readonly(*admin_state_account_pubkey, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*pda_pubkey, false),
+            AccountMeta::new_readonly(*lender_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn claim_impl(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    lender_keypair: &Keypair,
+    pda_sol_token_account_pubkey: &Pubkey,
+    lender_sol_token_account_pubkey: &Pubkey,
+    admin_sol_token_account_pubkey: &Pubkey,
+    escrow_state_account_pubkey: &Pubkey,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = claim_instruction(
+        renter_address,
+        rented_at,
+        &lender_keypair.pubkey(),
+        pda_sol_token_account_pubkey,
+        lender_sol_token_account_pubkey,
+        admin_sol_token_account_pubkey,
+        escrow_state_account_pubkey,
+        admin_state_account_pubkey,
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*pda_sol_token_account_pubkey, false),
-                    AccountMeta::new(*lender_sol_token_account_pubkey, false),
-                    AccountMeta::new(*admin_sol_token_account_pubkey, false),
-                    AccountMeta::new(*escrow_state_account_pubkey, false),
-                    AccountMeta::new_readonly(*admin_state_account_pubkey, false),
-                    AccountMeta::new_readonly(spl_token::ID, false),
-                    AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                    AccountMeta::new_readonly(lender_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&lender_keypair.pubkey()),
             &[&lender_keypair],
         )
@@ -380,28 +628,43 @@ pub async fn initialize_admin_state(fee: u32, test_state: &mut State) -> Result<
     Ok(())
 }
 
-pub async fn initialize_admin_state_impl(
+pub fn initialize_admin_state_instruction(
     fee: u32,
-    admin_keypair: &Keypair,
+    admin_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::InitializeAdminState { fee };
 
     let data: &mut [u8] = &mut [0; 8];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin_state_account_pubkey, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn initialize_admin_state_impl(
+    fee: u32,
+    admin_keypair: &Keypair,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = initialize_admin_state_instruction(
+        fee,
+        &admin_keypair.pubkey(),
+        admin_state_account_pubkey,
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*admin_state_account_pubkey, false),
-                    AccountMeta::new_readonly(admin_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&admin_keypair.pubkey()),
             &[&admin_keypair],
         )
@@ -420,28 +683,43 @@ pub async fn set_fee(fee: u32, test_state: &mut State) -> Result<(), TestError>
     Ok(())
 }
 
-pub async fn set_fee_impl(
+pub fn set_fee_instruction(
     fee: u32,
-    admin_keypair: &Keypair,
+    admin_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::SetFee { fee };
 
     let data: &mut [u8] = &mut [0; 8];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin_state_account_pubkey, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn set_fee_impl(
+    fee: u32,
+    admin_keypair: &Keypair,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = set_fee_instruction(
+        fee,
+        &admin_keypair.pubkey(),
+        admin_state_account_pubkey,
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*admin_state_account_pubkey, false),
-                    AccountMeta::new_readonly(admin_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&admin_keypair.pubkey()),
             &[&admin_keypair],
         )
@@ -463,32 +741,323 @@ pub async fn set_payable_account(
     Ok(())
 }
 
-pub async fn set_payable_account_impl(
+pub fn set_payable_account_instruction(
     admin_token_account: Pubkey,
-    admin_keypair: &Keypair,
+    admin_pubkey: &Pubkey,
     admin_state_account_pubkey: &Pubkey,
-    test_state: &mut State,
-) -> Result<(), TestError> {
+    program_id: &Pubkey,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::SetPayableAccount {};
 
     let data: &mut [u8] = &mut [0; 4];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin_state_account_pubkey, false),
+            AccountMeta::new(admin_token_account, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+pub async fn set_payable_account_impl(
+    admin_token_account: Pubkey,
+    admin_keypair: &Keypair,
+    admin_state_account_pubkey: &Pubkey,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let instruction = set_payable_account_instruction(
+        admin_token_account,
+        &admin_keypair.pubkey(),
+        admin_state_account_pubkey,
+        &test_state.program_id,
+    )?;
+
     test_state
-        .bench
         .process_transaction(
-            &[Instruction {
-                program_id: test_state.program_id,
-                accounts: vec![
-                    AccountMeta::new(*admin_state_account_pubkey, false),
-                    AccountMeta::new(admin_token_account, false),
-                    AccountMeta::new_readonly(admin_keypair.pubkey(), true),
-                ],
-                data: data.to_vec(),
-            }],
+            &[instruction],
             Some(&admin_keypair.pubkey()),
             &[&admin_keypair],
         )
         .await?;
     Ok(())
 }
+
+pub async fn process_instructions(
+    instructions: &[Instruction],
+    payer_keypair: &Keypair,
+    signers: &[&Keypair],
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let mut all_signers = vec![payer_keypair];
+    all_signers.extend(
+        signers
+            .iter()
+            .copied()
+            .filter(|s| s.pubkey() != payer_keypair.pubkey()),
+    );
+
+    test_state
+        .process_transaction(instructions, Some(&payer_keypair.pubkey()), &all_signers)
+        .await
+}
+
+// Wraps `lamports` into a new temporary SOL token account and rents from it in the same
+// transaction, the way a wallet without wrapped SOL would.
+pub async fn wrap_and_rent(
+    rent_amount: u16,
+    rent_duration: u8,
+    lamports: u64,
+    renter_temp_sol_token_account_keypair: &Keypair,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let mut instructions = wrap_sol_instructions(
+        &renter_keypair.pubkey(),
+        &renter_temp_sol_token_account_keypair.pubkey(),
+        test_state
+            .bench
+            .rent
+            .minimum_balance(spl_token::state::Account::get_packed_len())
+            + lamports,
+    )?;
+    instructions.push(rent_instruction(
+        rent_amount,
+        rent_duration,
+        &renter_keypair.pubkey(),
+        &renter_temp_sol_token_account_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?);
+
+    process_instructions(
+        &instructions,
+        &renter_keypair,
+        &[renter_temp_sol_token_account_keypair],
+        test_state,
+    )
+    .await
+}
+
+// Stops renting and closes the renter's SOL token account holding the refund, so that the
+// refund lands in the renter's wallet as native SOL. The bench payer pays for the transaction,
+// which leaves the renter's balance to the refund alone.
+pub async fn stop_rent_and_close(rented_at: i64, test_state: &mut State) -> Result<(), TestError> {
+    let payer_keypair = clone_keypair(&test_state.bench.payer);
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let instructions = [
+        stop_rent_instruction(
+            rented_at,
+            &renter_keypair.pubkey(),
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            &test_state.renter_token_account_pubkey(Currency::Sol),
+            &test_state.lender_token_account_pubkey(Currency::Sol),
+            &test_state.admin_token_account_pubkey(Currency::Sol),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.admin_state_account_keypair.pubkey(),
+            &test_state.pda_pubkey,
+            &spl_token::id(),
+            &test_state.program_id,
+        )?,
+        spl_token::instruction::close_account(
+            &spl_token::id(),
+            &test_state.renter_token_account_pubkey(Currency::Sol),
+            &renter_keypair.pubkey(),
+            &renter_keypair.pubkey(),
+            &[],
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+    ];
+
+    process_instructions(
+        &instructions,
+        &payer_keypair,
+        &[&renter_keypair],
+        test_state,
+    )
+    .await
+}
+
+// The `simulate_*` helpers build the same instructions as the fixture helpers above and simulate
+// them through `State`. Nothing is committed, and no post-state is reported.
+async fn simulate_instruction(
+    instruction: Instruction,
+    signer_keypair: &Keypair,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    test_state
+        .simulate_transaction(
+            &[instruction],
+            Some(&signer_keypair.pubkey()),
+            &[signer_keypair],
+        )
+        .await
+}
+
+pub async fn simulate_lend(
+    daily_rent_price: u64,
+    max_renters: u32,
+    max_rent_duration: u8,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+    let instruction = lend_instruction(
+        daily_rent_price,
+        max_renters,
+        max_rent_duration,
+        &lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &lender_keypair, test_state).await
+}
+
+pub async fn simulate_stop_lend(test_state: &mut State) -> Result<Simulation, TestError> {
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+    let instruction = stop_lend_instruction(
+        &lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &test_state.lender_main_nft_account_pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &lender_keypair, test_state).await
+}
+
+pub async fn simulate_edit_lend(
+    daily_rent_price: u64,
+    max_rent_duration: u8,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+    let instruction = edit_lend_instruction(
+        daily_rent_price,
+        max_rent_duration,
+        &lender_keypair.pubkey(),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Usdc),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &lender_keypair, test_state).await
+}
+
+pub async fn simulate_rent(
+    rent_amount: u16,
+    rent_duration: u8,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let instruction = rent_instruction(
+        rent_amount,
+        rent_duration,
+        &renter_keypair.pubkey(),
+        &test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &renter_keypair, test_state).await
+}
+
+pub async fn simulate_stop_rent(
+    rented_at: i64,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let instruction = stop_rent_instruction(
+        rented_at,
+        &renter_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.renter_token_account_pubkey(Currency::Sol),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &renter_keypair, test_state).await
+}
+
+pub async fn simulate_claim(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+    let instruction = claim_instruction(
+        renter_address,
+        rented_at,
+        &lender_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &lender_keypair, test_state).await
+}
+
+pub async fn simulate_initialize_admin_state(
+    fee: u32,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let admin_keypair = clone_keypair(&test_state.admin_keypair);
+    let instruction = initialize_admin_state_instruction(
+        fee,
+        &admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &admin_keypair, test_state).await
+}
+
+pub async fn simulate_set_fee(fee: u32, test_state: &mut State) -> Result<Simulation, TestError> {
+    let admin_keypair = clone_keypair(&test_state.admin_keypair);
+    let instruction = set_fee_instruction(
+        fee,
+        &admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &admin_keypair, test_state).await
+}
+
+pub async fn simulate_set_payable_account(
+    admin_token_account: Pubkey,
+    test_state: &mut State,
+) -> Result<Simulation, TestError> {
+    let admin_keypair = clone_keypair(&test_state.admin_keypair);
+    let instruction = set_payable_account_instruction(
+        admin_token_account,
+        &admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    simulate_instruction(instruction, &admin_keypair, test_state).await
+}
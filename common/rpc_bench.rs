@@ -1,10 +1,28 @@
 use {
     crate::common::calculate_escrow_state_account_len,
     crate::common::clone_keypair,
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::coverage,
     crate::common::error::TestError,
-    arrayref::{array_ref, array_refs},
+    crate::common::labels::Labeled,
+    crate::common::rpc_client_utils::SendConfig,
+    crate::common::simulation::{writable_accounts, Simulation},
+    crate::common::telemetry::{record_outcome, transaction_span},
+    crate::common::token_2022::{
+        self, get_mint_len, get_token_account_len, unpack_token_account, MintExtension,
+    },
+    crate::common::{unpack_admin_state, wrap_sol_instructions},
     rayon::prelude::*,
-    solana_client::{rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig},
+    solana_account_decoder::UiAccountEncoding,
+    solana_address_lookup_table_program::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+    },
+    solana_client::{
+        client_error::ClientError,
+        rpc_client::{RpcClient, SerializableTransaction},
+        rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+    },
     solana_program::system_program,
     solana_program::{
         instruction::Instruction, native_token::LAMPORTS_PER_SOL, program_pack::Pack,
@@ -12,21 +30,41 @@ use {
     },
     solana_renft_collateral_free::admin::{ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
     solana_sdk::{
-        account::Account, commitment_config::CommitmentConfig, signature::Keypair, signer::Signer,
-        transaction::Transaction,
+        account::Account,
+        address_lookup_table_account::AddressLookupTableAccount,
+        clock::DEFAULT_MS_PER_SLOT,
+        message::{v0, Message, VersionedMessage},
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, VersionedTransaction},
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account,
     },
+    std::time::{Duration, Instant},
+    tracing::field,
 };
 
+// Extended lookup tables activate one slot later, so this is many slots of slack
+const LOOKUP_TABLE_ACTIVATION_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct RpcBench {
     pub rpc_client: RpcClient,
     pub next_id: u8,
+    // Used by every transaction that is not sent with its own config
+    pub send_config: SendConfig,
 }
 
 impl RpcBench {
     pub fn start_new(rpc_client: RpcClient) -> Self {
+        Self::start_new_with_send_config(rpc_client, SendConfig::default())
+    }
+
+    pub fn start_new_with_send_config(rpc_client: RpcClient, send_config: SendConfig) -> Self {
         Self {
             rpc_client,
             next_id: 0,
+            send_config,
         }
     }
 
@@ -36,7 +74,40 @@ impl RpcBench {
         payer: Option<&Pubkey>,
         signers: &[&Keypair],
     ) -> Result<(), TestError> {
-        let mut transaction = Transaction::new_with_payer(instructions, payer);
+        self.process_transaction_with_compute_budget(
+            instructions,
+            payer,
+            signers,
+            &ComputeBudget::default(),
+        )
+    }
+
+    pub fn process_transaction_with_compute_budget(
+        &self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+        compute_budget: &ComputeBudget,
+    ) -> Result<(), TestError> {
+        self.process_transaction_with_config(
+            instructions,
+            payer,
+            signers,
+            compute_budget,
+            &self.send_config,
+        )
+    }
+
+    pub fn process_transaction_with_config(
+        &self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+        compute_budget: &ComputeBudget,
+        send_config: &SendConfig,
+    ) -> Result<(), TestError> {
+        let instructions = compute_budget.prepend_to(instructions);
+        let mut transaction = Transaction::new_with_payer(&instructions, payer);
 
         let recent_blockhash = self
             .rpc_client
@@ -45,18 +116,204 @@ impl RpcBench {
 
         transaction.sign(&Vec::from(signers), recent_blockhash);
 
-        self.rpc_client
+        self.send_transaction(&transaction, &instructions, payer, send_config)
+    }
+
+    // Sends `instructions` as a v0 transaction, resolving the accounts found in `lookup_tables`
+    // through the tables instead of listing them in the message.
+    #[allow(dead_code)]
+    pub fn process_versioned_transaction(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<(), TestError> {
+        let recent_blockhash = self
+            .rpc_client
+            .get_latest_blockhash()
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        let message =
+            v0::Message::try_compile(payer, instructions, lookup_tables, recent_blockhash)
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &Vec::from(signers))
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        self.send_transaction(&transaction, instructions, Some(payer), &self.send_config)
+    }
+
+    fn send_transaction(
+        &self,
+        transaction: &impl SerializableTransaction,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        send_config: &SendConfig,
+    ) -> Result<(), TestError> {
+        let signature = transaction.get_signature();
+        let span = transaction_span(instructions, payer);
+        span.record("signature", &field::display(signature));
+        let started = Instant::now();
+
+        let result = self
+            .rpc_client
             .send_and_confirm_transaction_with_spinner_and_config(
+                transaction,
+                send_config.commitment,
+                send_config.rpc_send_transaction_config(),
+            )
+            .map(|_| ())
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)));
+        let slot = self
+            .rpc_client
+            .get_signature_statuses(&[*signature])
+            .ok()
+            .and_then(|statuses| statuses.value.into_iter().next().flatten())
+            .map(|status| status.slot);
+        record_outcome(&span, started, slot, &result);
+        coverage::record(instructions, &result);
+
+        result
+    }
+
+    #[allow(dead_code)]
+    pub fn simulate_transaction(
+        &self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+    ) -> Result<Simulation, TestError> {
+        let mut transaction = Transaction::new_with_payer(instructions, payer);
+
+        let recent_blockhash = self
+            .rpc_client
+            .get_latest_blockhash()
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        transaction.sign(&Vec::from(signers), recent_blockhash);
+
+        let addresses = writable_accounts(&transaction.message);
+        let simulation = self
+            .rpc_client
+            .simulate_transaction_with_config(
                 &transaction,
-                CommitmentConfig::confirmed(),
-                RpcSendTransactionConfig {
-                    skip_preflight: true,
-                    ..RpcSendTransactionConfig::default()
+                RpcSimulateTransactionConfig {
+                    sig_verify: true,
+                    commitment: Some(self.send_config.commitment),
+                    accounts: Some(RpcSimulateTransactionAccountsConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+                    }),
+                    ..RpcSimulateTransactionConfig::default()
                 },
             )
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?
+            .value;
+
+        let result = match simulation.err {
+            Some(e) => Err(TestError::UnexpectedError(Box::new(ClientError::from(e)))),
+            None => Ok(()),
+        };
+        let accounts = match simulation.accounts {
+            Some(accounts) => addresses
+                .into_iter()
+                .zip(accounts)
+                .map(|(address, account)| {
+                    (
+                        address,
+                        account.and_then(|account| account.decode::<Account>()),
+                    )
+                })
+                .collect(),
+            // Failed simulations return no accounts, and nothing changed
+            None => addresses
+                .into_iter()
+                .map(|address| (address, self.get_account(&address)))
+                .collect(),
+        };
+
+        Ok(Simulation {
+            result,
+            logs: simulation.logs.unwrap_or_default(),
+            units_consumed: simulation.units_consumed.unwrap_or_default(),
+            accounts,
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn create_address_lookup_table(&self, authority: &Keypair) -> Result<Pubkey, TestError> {
+        let recent_slot = self
+            .rpc_client
+            .get_slot()
             .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        let (instruction, lookup_table) =
+            create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
 
-        Ok(())
+        self.process_transaction(&[instruction], Some(&authority.pubkey()), &[authority])?;
+        Ok(lookup_table)
+    }
+
+    #[allow(dead_code)]
+    pub fn extend_address_lookup_table(
+        &self,
+        authority: &Keypair,
+        lookup_table: &Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> Result<(), TestError> {
+        let instruction = extend_lookup_table(
+            *lookup_table,
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            addresses,
+        );
+        self.process_transaction(&[instruction], Some(&authority.pubkey()), &[authority])?;
+
+        // Addresses only become usable through the table in the slot after the one the table
+        // records as last extended, so wait for the cluster to reach it
+        let last_extended_slot = self.get_lookup_table_last_extended_slot(lookup_table)?;
+        let deadline = Instant::now() + LOOKUP_TABLE_ACTIVATION_TIMEOUT;
+        loop {
+            let slot = self
+                .rpc_client
+                .get_slot()
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+            if slot > last_extended_slot {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(TestError::TestError(format!(
+                    "Lookup table {} still not active at slot {}, last extended at slot {}",
+                    lookup_table, slot, last_extended_slot
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(DEFAULT_MS_PER_SLOT));
+        }
+    }
+
+    fn get_lookup_table_last_extended_slot(&self, address: &Pubkey) -> Result<u64, TestError> {
+        let account = self
+            .get_account(address)
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        let lookup_table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        Ok(lookup_table.meta.last_extended_slot)
+    }
+
+    #[allow(dead_code)]
+    pub fn get_address_lookup_table(
+        &self,
+        address: &Pubkey,
+    ) -> Result<AddressLookupTableAccount, TestError> {
+        let account = self
+            .get_account(address)
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        let lookup_table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        Ok(AddressLookupTableAccount {
+            key: *address,
+            addresses: lookup_table.addresses.to_vec(),
+        })
     }
 
     pub fn create_mint(
@@ -137,7 +394,7 @@ impl RpcBench {
             if !(account_data.owner == system_program::id() && system_account_ok) {
                 return Err(TestError::TestError(format!(
                     "Error: Account already exists: {}",
-                    account
+                    Labeled(&account)
                 )));
             }
         }
@@ -147,6 +404,30 @@ impl RpcBench {
         Ok(account)
     }
 
+    pub fn create_associated_token_account(
+        &self,
+        token: Pubkey,
+        owner: &Keypair,
+    ) -> Result<Pubkey, TestError> {
+        let account = get_associated_token_address(&owner.pubkey(), &token);
+        if self.get_account(&account).is_some() {
+            return Err(TestError::TestError(format!(
+                "Error: Account already exists: {}",
+                Labeled(&account)
+            )));
+        }
+
+        let instructions = vec![create_associated_token_account(
+            &owner.pubkey(),
+            &owner.pubkey(),
+            &token,
+            &spl_token::id(),
+        )];
+        self.process_transaction(&instructions, Some(&owner.pubkey()), &[owner])?;
+
+        Ok(account)
+    }
+
     pub fn mint_tokens(
         &self,
         token_account: Pubkey,
@@ -210,7 +491,7 @@ impl RpcBench {
         program_id: Pubkey,
     ) -> Result<Keypair, TestError> {
         let escrow_state_account = Keypair::new();
-        let escrow_state_account_len = calculate_escrow_state_account_len(max_renters);
+        let escrow_state_account_len = calculate_escrow_state_account_len(max_renters)?;
         let rent = self
             .rpc_client
             .get_minimum_balance_for_rent_exemption(escrow_state_account_len)
@@ -250,7 +531,7 @@ impl RpcBench {
                     .confirm_transaction_with_spinner(
                         &signature,
                         &recent_hash,
-                        CommitmentConfig::confirmed(),
+                        self.send_config.commitment,
                     )
                     .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
                 Ok(())
@@ -292,22 +573,11 @@ impl RpcBench {
             .get_minimum_balance_for_rent_exemption(spl_token::state::Account::get_packed_len())
             .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
 
-        let instructions = vec![
-            system_instruction::create_account(
-                &owner.pubkey(),
-                &wrapped_sol_account.pubkey(),
-                rent + lamports,
-                spl_token::state::Account::get_packed_len() as u64,
-                &spl_token::id(),
-            ),
-            spl_token::instruction::initialize_account(
-                &spl_token::id(),
-                &wrapped_sol_account.pubkey(),
-                &spl_token::native_mint::id(),
-                &owner.pubkey(),
-            )
-            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
-        ];
+        let instructions = wrap_sol_instructions(
+            &owner.pubkey(),
+            &wrapped_sol_account.pubkey(),
+            rent + lamports,
+        )?;
 
         let signers = [owner, wrapped_sol_account];
         self.process_transaction(
@@ -318,10 +588,201 @@ impl RpcBench {
         Ok(())
     }
 
+    #[allow(dead_code)]
+    pub fn top_up_wrapped_sol(
+        &self,
+        owner: &Keypair,
+        wrapped_sol_account: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), TestError> {
+        let instructions = vec![
+            system_instruction::transfer(&owner.pubkey(), wrapped_sol_account, lamports),
+            spl_token::instruction::sync_native(&spl_token::id(), wrapped_sol_account)
+                .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+        ];
+
+        self.process_transaction(&instructions, Some(&owner.pubkey()), &[owner])?;
+        Ok(())
+    }
+
+    // `payer` pays for the transaction, as the bench payer does in `ProgramTestBench`, so that
+    // the lamports released to `destination` can be asserted exactly.
+    #[allow(dead_code)]
+    pub fn close_token_account(
+        &self,
+        payer: &Keypair,
+        owner: &Keypair,
+        token_account: &Pubkey,
+        destination: &Pubkey,
+    ) -> Result<(), TestError> {
+        let instructions = vec![spl_token::instruction::close_account(
+            &spl_token::id(),
+            token_account,
+            destination,
+            &owner.pubkey(),
+            &[],
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?];
+
+        self.process_transaction(&instructions, Some(&payer.pubkey()), &[payer, owner])?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn unwrap_sol(
+        &self,
+        payer: &Keypair,
+        owner: &Keypair,
+        wrapped_sol_account: &Pubkey,
+    ) -> Result<u64, TestError> {
+        let lamports = self.get_lamports(wrapped_sol_account);
+        self.close_token_account(payer, owner, wrapped_sol_account, &owner.pubkey())?;
+        Ok(lamports)
+    }
+
+    pub fn get_lamports(&self, address: &Pubkey) -> u64 {
+        self.get_account(address)
+            .map(|account| account.lamports)
+            .unwrap_or_default()
+    }
+
+    #[allow(dead_code)]
+    pub fn assert_lamports(&self, address: &Pubkey, expected: u64) {
+        assert_eq!(
+            self.get_lamports(address),
+            expected,
+            "Lamport balance of {}",
+            address
+        );
+    }
+
+    // Fee the cluster charges for a message with these instructions, without any ComputeBudget
+    // instruction prepended
+    #[allow(dead_code)]
+    pub fn get_fee(&self, instructions: &[Instruction], payer: &Pubkey) -> Result<u64, TestError> {
+        let recent_blockhash = self
+            .rpc_client
+            .get_latest_blockhash()
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        let message = Message::new_with_blockhash(instructions, Some(payer), &recent_blockhash);
+        self.rpc_client
+            .get_fee_for_message(&message)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+    }
+
     pub fn get_account(&self, address: &Pubkey) -> Option<Account> {
         self.rpc_client.get_account(address).ok()
     }
 
+    #[allow(dead_code)]
+    pub fn create_mint_2022(
+        &self,
+        mint_authority: &Keypair,
+        enable_freeze: bool,
+        decimals: u8,
+        extensions: &[MintExtension],
+    ) -> Result<Keypair, TestError> {
+        let mint_keypair = Keypair::new();
+        let mint_rent = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(get_mint_len(extensions)?)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        let freeze_authority_pubkey = mint_authority.pubkey();
+        let freeze_authority = if enable_freeze {
+            Some(&freeze_authority_pubkey)
+        } else {
+            None
+        };
+
+        let instructions = token_2022::create_mint_instructions(
+            &mint_authority.pubkey(),
+            &mint_keypair.pubkey(),
+            &mint_authority.pubkey(),
+            freeze_authority,
+            decimals,
+            extensions,
+            mint_rent,
+        )?;
+        let signers = [mint_authority, &mint_keypair];
+
+        self.process_transaction(&instructions, Some(&mint_authority.pubkey()), &signers)?;
+        Ok(mint_keypair)
+    }
+
+    #[allow(dead_code)]
+    pub fn create_token_account_2022(
+        &self,
+        token: Pubkey,
+        owner: &Keypair,
+        account: &Keypair,
+    ) -> Result<Pubkey, TestError> {
+        let mint = self
+            .get_account(&token)
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        let account_len = get_token_account_len(&mint.data)?;
+        let rent = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(account_len)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+        let instructions = token_2022::create_token_account_instructions(
+            &owner.pubkey(),
+            &account.pubkey(),
+            &token,
+            account_len,
+            rent,
+        )?;
+        if self.get_account(&account.pubkey()).is_some() {
+            return Err(TestError::TestError(format!(
+                "Error: Account already exists: {}",
+                Labeled(&account.pubkey())
+            )));
+        }
+
+        self.process_transaction(&instructions, Some(&owner.pubkey()), &[owner, account])?;
+        Ok(account.pubkey())
+    }
+
+    #[allow(dead_code)]
+    pub fn mint_tokens_2022(
+        &self,
+        token_account: Pubkey,
+        token_mint: &Pubkey,
+        token_mint_authority: &Keypair,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<(), TestError> {
+        let instructions = vec![spl_token_2022::instruction::mint_to_checked(
+            &spl_token_2022::id(),
+            token_mint,
+            &token_account,
+            &token_mint_authority.pubkey(),
+            &[],
+            amount,
+            decimals,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?];
+
+        self.process_transaction(
+            &instructions,
+            Some(&token_mint_authority.pubkey()),
+            &[token_mint_authority],
+        )?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn get_token_account_2022(
+        &self,
+        address: &Pubkey,
+    ) -> Result<spl_token_2022::state::Account, TestError> {
+        let account = self
+            .get_account(address)
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        unpack_token_account(&account.data)
+    }
+
     pub fn get_admin_state_account(
         &self,
         address: &Pubkey,
@@ -330,12 +791,10 @@ impl RpcBench {
         let admin_state_account = self
             .get_account(address)
             .ok_or(TestError::TestError("Account not found".to_string()))?;
-        let admin_state_account_data = admin_state_account.data;
-        let dst = array_ref![admin_state_account_data, 0, ADMIN_LEN];
-        let (token_accounts_dst, fee_dst, _) = array_refs![dst, TOKEN_ACCOUNTS_LEN, 4, 1];
-        token_accounts.clone_from_slice(token_accounts_dst);
-        let fee_bps = u32::from_le_bytes(*fee_dst);
-        Ok(fee_bps)
+        Ok(unpack_admin_state(
+            &admin_state_account.data,
+            token_accounts,
+        ))
     }
 
     #[allow(dead_code)]
@@ -1,9 +1,11 @@
 use {
+    crate::common::progress::{ProgressEvent, ProgressReporter},
     solana_client::{
         rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig,
         rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
     },
     solana_sdk::{
+        commitment_config::{CommitmentConfig, CommitmentLevel},
         message::Message,
         signers::Signers,
         transaction::{Transaction, TransactionError},
@@ -16,16 +18,52 @@ use {
     },
 };
 
+// How transactions are sent to a validator and when they count as landed. The default is the
+// historical behaviour of the harness: no preflight and confirmed commitment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SendConfig {
+    pub skip_preflight: bool,
+    // `None` lets the client use its own commitment for the preflight simulation
+    pub preflight_commitment: Option<CommitmentLevel>,
+    pub commitment: CommitmentConfig,
+    // `None` leaves rebroadcasting to the RPC node's default
+    pub max_retries: Option<usize>,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        Self {
+            skip_preflight: true,
+            preflight_commitment: None,
+            commitment: CommitmentConfig::confirmed(),
+            max_retries: None,
+        }
+    }
+}
+
+impl SendConfig {
+    pub fn rpc_send_transaction_config(&self) -> RpcSendTransactionConfig {
+        RpcSendTransactionConfig {
+            skip_preflight: self.skip_preflight,
+            preflight_commitment: self.preflight_commitment,
+            max_retries: self.max_retries,
+            ..RpcSendTransactionConfig::default()
+        }
+    }
+}
+
 pub fn send_and_confirm_messages_with_spinner<T: Signers>(
     rpc_client: &RpcClient,
     messages: &[Message],
     signers: &T,
+    send_config: &SendConfig,
+    progress: &dyn ProgressReporter,
 ) -> Result<Vec<Option<TransactionError>>, Box<dyn error::Error>> {
     let mut expired_blockhash_retries = 5;
     let send_transaction_interval = Duration::from_millis(10); /* Send at ~100 TPS */
     let transaction_resend_interval = Duration::from_secs(4); /* Retry batch send after 4 seconds */
 
-    println!("Connecting...");
+    progress.report(ProgressEvent::Stage("Connecting...".to_string()));
 
     let mut transactions = messages
         .iter()
@@ -33,33 +71,24 @@ pub fn send_and_confirm_messages_with_spinner<T: Signers>(
         .map(|(i, message)| (i, Transaction::new_unsigned(message.clone())))
         .collect::<Vec<_>>();
     let mut transaction_errors = vec![None; messages.len()];
-    let set_message = |confirmed_transactions,
+    let set_message = |confirmed: usize,
                        block_height: Option<u64>,
                        last_valid_block_height: u64,
                        status: &str| {
-        println!(
-            "{}",
-            format!(
-                "{:>5.1}% | {:<40}{}",
-                confirmed_transactions as f64 * 100. / messages.len() as f64,
-                status,
-                match block_height {
-                    Some(block_height) => format!(
-                        " [block height {}; re-sign in {} blocks]",
-                        block_height,
-                        last_valid_block_height.saturating_sub(block_height),
-                    ),
-                    None => String::new(),
-                },
-            )
-        );
+        progress.report(ProgressEvent::Status {
+            confirmed,
+            total: messages.len(),
+            status: status.to_string(),
+            block_height,
+            last_valid_block_height,
+        });
     };
 
     let mut confirmed_transactions = 0;
     let mut block_height = rpc_client.get_block_height()?;
     while expired_blockhash_retries > 0 {
         let (blockhash, last_valid_block_height) =
-            rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment())?;
+            rpc_client.get_latest_blockhash_with_commitment(send_config.commitment)?;
 
         let mut pending_transactions = HashMap::new();
         for (i, mut transaction) in transactions {
@@ -76,10 +105,7 @@ pub fn send_and_confirm_messages_with_spinner<T: Signers>(
                 for (index, (_i, transaction)) in pending_transactions.values().enumerate() {
                     let _ = rpc_client.send_transaction_with_config(
                         transaction,
-                        RpcSendTransactionConfig {
-                            skip_preflight: true,
-                            ..RpcSendTransactionConfig::default()
-                        },
+                        send_config.rpc_send_transaction_config(),
                     );
                     let method = "RPC";
                     set_message(
@@ -124,7 +150,7 @@ pub fn send_and_confirm_messages_with_spinner<T: Signers>(
                         pending_signatures_chunk.iter().zip(statuses.into_iter())
                     {
                         if let Some(status) = status {
-                            if status.satisfies_commitment(rpc_client.commitment()) {
+                            if status.satisfies_commitment(send_config.commitment) {
                                 if let Some((i, _)) = pending_transactions.remove(signature) {
                                     confirmed_transactions += 1;
                                     transaction_errors[i] = status.err;
@@ -147,13 +173,9 @@ pub fn send_and_confirm_messages_with_spinner<T: Signers>(
         }
 
         transactions = pending_transactions.into_iter().map(|(_k, v)| v).collect();
-        println!(
-            "{}",
-            format!(
-                "Blockhash expired. {} retries remaining",
-                expired_blockhash_retries
-            )
-        );
+        progress.report(ProgressEvent::BlockhashExpired {
+            retries_remaining: expired_blockhash_retries,
+        });
         expired_blockhash_retries -= 1;
     }
     Err("Max retries exceeded".into())
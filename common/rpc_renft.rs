@@ -1,10 +1,17 @@
 use {
+    crate::common::compute_budget::ComputeBudget,
     crate::common::error::TestError,
+    crate::common::renft::{
+        initialize_admin_state_instruction, lend_instruction, rent_instruction,
+        set_fee_instruction, set_payable_account_instruction,
+    },
     crate::common::rpc_state::RpcState,
+    crate::common::simulation::Simulation,
     crate::common::{clone_keypair, pack_instruction},
     solana_program::instruction::{AccountMeta, Instruction},
     solana_program::pubkey::Pubkey,
     solana_renft_collateral_free::instruction::EscrowInstruction,
+    solana_sdk::address_lookup_table_account::AddressLookupTableAccount,
     solana_sdk::signature::Signer,
     solana_sdk::signer::keypair::Keypair,
     spl_token,
@@ -60,6 +67,7 @@ pub fn rpc_rent(
         &test_state.renter_temp_sol_token_account_keypair.pubkey(),
         &test_state.pda_sol_token_account_keypair.pubkey(),
         &test_state.escrow_state_account_keypair.pubkey(),
+        &ComputeBudget::default(),
         test_state,
     )?;
 
@@ -72,6 +80,7 @@ pub fn rpc_rent_impl(
     renter_temp_sol_token_account_pubkey: &Pubkey,
     pda_sol_token_account_pubkey: &Pubkey,
     escrow_state_account_pubkey: &Pubkey,
+    compute_budget: &ComputeBudget,
     test_state: &RpcState,
 ) -> Result<(), TestError> {
     let instruction = EscrowInstruction::Rent {
@@ -82,61 +91,126 @@ pub fn rpc_rent_impl(
     let data: &mut [u8] = &mut [0; 7];
     pack_instruction(instruction, data)?;
 
+    test_state
+        .rpc_bench
+        .process_transaction_with_compute_budget(
+            &[Instruction {
+                program_id: test_state.program_id,
+                accounts: vec![
+                    AccountMeta::new(*renter_temp_sol_token_account_pubkey, false),
+                    AccountMeta::new(*pda_sol_token_account_pubkey, false),
+                    AccountMeta::new(*escrow_state_account_pubkey, false),
+                    AccountMeta::new_readonly(spl_token::ID, false),
+                    AccountMeta::new_readonly(test_state.pda_pubkey, false),
+                    AccountMeta::new_readonly(renter_keypair.pubkey(), true),
+                ],
+                data: data.to_vec(),
+            }],
+            Some(&renter_keypair.pubkey()),
+            &[&renter_keypair],
+            compute_budget,
+        )?;
+
+    Ok(())
+}
+#[allow(dead_code)]
+pub fn rpc_stop_rent(rented_at: i64, test_state: &RpcState) -> Result<(), TestError> {
     test_state.rpc_bench.process_transaction(
-        &[Instruction {
-            program_id: test_state.program_id,
-            accounts: vec![
-                AccountMeta::new(*renter_temp_sol_token_account_pubkey, false),
-                AccountMeta::new(*pda_sol_token_account_pubkey, false),
-                AccountMeta::new(*escrow_state_account_pubkey, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                AccountMeta::new_readonly(renter_keypair.pubkey(), true),
-            ],
-            data: data.to_vec(),
-        }],
-        Some(&renter_keypair.pubkey()),
-        &[&renter_keypair],
+        &[rpc_stop_rent_instruction(rented_at, test_state)?],
+        Some(&test_state.renter_keypair.pubkey()),
+        &[&test_state.renter_keypair],
     )?;
 
     Ok(())
 }
+
 #[allow(dead_code)]
-pub fn rpc_stop_rent(rented_at: i64, test_state: &RpcState) -> Result<(), TestError> {
+pub fn rpc_stop_rent_versioned(
+    rented_at: i64,
+    lookup_tables: &[AddressLookupTableAccount],
+    test_state: &RpcState,
+) -> Result<(), TestError> {
+    test_state.rpc_bench.process_versioned_transaction(
+        &[rpc_stop_rent_instruction(rented_at, test_state)?],
+        &test_state.renter_keypair.pubkey(),
+        &[&test_state.renter_keypair],
+        lookup_tables,
+    )?;
+
+    Ok(())
+}
+
+pub fn rpc_stop_rent_instruction(
+    rented_at: i64,
+    test_state: &RpcState,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::StopRent { rented_at };
 
     let data: &mut [u8] = &mut [0; 12];
     pack_instruction(instruction, data)?;
 
+    Ok(Instruction {
+        program_id: test_state.program_id,
+        accounts: vec![
+            AccountMeta::new(test_state.pda_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.renter_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.lender_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.admin_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.escrow_state_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(test_state.admin_state_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(test_state.pda_pubkey, false),
+            AccountMeta::new_readonly(test_state.renter_keypair.pubkey(), true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+#[allow(dead_code)]
+pub fn rpc_claim(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    test_state: &RpcState,
+) -> Result<(), TestError> {
     test_state.rpc_bench.process_transaction(
-        &[Instruction {
-            program_id: test_state.program_id,
-            accounts: vec![
-                AccountMeta::new(test_state.pda_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.renter_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.lender_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.admin_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.escrow_state_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(test_state.admin_state_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                AccountMeta::new_readonly(test_state.renter_keypair.pubkey(), true),
-            ],
-            data: data.to_vec(),
-        }],
-        Some(&test_state.renter_keypair.pubkey()),
-        &[&test_state.renter_keypair],
+        &[rpc_claim_instruction(
+            renter_address,
+            rented_at,
+            test_state,
+        )?],
+        Some(&test_state.lender_keypair.pubkey()),
+        &[&test_state.lender_keypair],
     )?;
 
     Ok(())
 }
 
 #[allow(dead_code)]
-pub fn rpc_claim(
+pub fn rpc_claim_versioned(
     renter_address: &Pubkey,
     rented_at: i64,
+    lookup_tables: &[AddressLookupTableAccount],
     test_state: &RpcState,
 ) -> Result<(), TestError> {
+    test_state.rpc_bench.process_versioned_transaction(
+        &[rpc_claim_instruction(
+            renter_address,
+            rented_at,
+            test_state,
+        )?],
+        &test_state.lender_keypair.pubkey(),
+        &[&test_state.lender_keypair],
+        lookup_tables,
+    )?;
+
+    Ok(())
+}
+
+pub fn rpc_claim_instruction(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    test_state: &RpcState,
+) -> Result<Instruction, TestError> {
     let instruction = EscrowInstruction::Claim {
         renter_address: *renter_address,
         rented_at,
@@ -145,26 +219,46 @@ pub fn rpc_claim(
     let data: &mut [u8] = &mut [0; 44];
     pack_instruction(instruction, data)?;
 
-    test_state.rpc_bench.process_transaction(
-        &[Instruction {
-            program_id: test_state.program_id,
-            accounts: vec![
-                AccountMeta::new(test_state.pda_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.lender_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.admin_sol_token_account_keypair.pubkey(), false),
-                AccountMeta::new(test_state.escrow_state_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(test_state.admin_state_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(test_state.pda_pubkey, false),
-                AccountMeta::new_readonly(test_state.lender_keypair.pubkey(), true),
-            ],
-            data: data.to_vec(),
-        }],
-        Some(&test_state.lender_keypair.pubkey()),
-        &[&test_state.lender_keypair],
-    )?;
+    Ok(Instruction {
+        program_id: test_state.program_id,
+        accounts: vec![
+            AccountMeta::new(test_state.pda_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.lender_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.admin_sol_token_account_keypair.pubkey(), false),
+            AccountMeta::new(test_state.escrow_state_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(test_state.admin_state_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(test_state.pda_pubkey, false),
+            AccountMeta::new_readonly(test_state.lender_keypair.pubkey(), true),
+        ],
+        data: data.to_vec(),
+    })
+}
 
-    Ok(())
+// Creates a lookup table holding every account shared by Claim and StopRent, so that v0
+// transactions only list the signer in the message.
+#[allow(dead_code)]
+pub fn rpc_create_escrow_lookup_table(
+    test_state: &RpcState,
+) -> Result<AddressLookupTableAccount, TestError> {
+    let lookup_table = test_state
+        .rpc_bench
+        .create_address_lookup_table(&test_state.admin_keypair)?;
+    test_state.rpc_bench.extend_address_lookup_table(
+        &test_state.admin_keypair,
+        &lookup_table,
+        vec![
+            test_state.pda_sol_token_account_keypair.pubkey(),
+            test_state.renter_sol_token_account_keypair.pubkey(),
+            test_state.lender_sol_token_account_keypair.pubkey(),
+            test_state.admin_sol_token_account_keypair.pubkey(),
+            test_state.escrow_state_account_keypair.pubkey(),
+            test_state.admin_state_account_keypair.pubkey(),
+            spl_token::ID,
+            test_state.pda_pubkey,
+        ],
+    )?;
+    test_state.rpc_bench.get_address_lookup_table(&lookup_table)
 }
 
 #[allow(dead_code)]
@@ -236,3 +330,118 @@ pub fn rpc_set_payable_account(
     )?;
     Ok(())
 }
+
+// The `rpc_simulate_*` helpers mirror the helpers above without committing anything. Unlike the
+// BanksClient `simulate_*` helpers, they report the post-state of every writable account.
+fn rpc_simulate_instruction(
+    instruction: Instruction,
+    signer_keypair: &Keypair,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    test_state.rpc_bench.simulate_transaction(
+        &[instruction],
+        Some(&signer_keypair.pubkey()),
+        &[signer_keypair],
+    )
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_lend(
+    daily_rent_price: u64,
+    max_renters: u32,
+    max_rent_duration: u8,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    let instruction = lend_instruction(
+        daily_rent_price,
+        max_renters,
+        max_rent_duration,
+        &test_state.lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &test_state.lender_sol_token_account_keypair.pubkey(),
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    rpc_simulate_instruction(instruction, &test_state.lender_keypair, test_state)
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_rent(
+    rent_amount: u16,
+    rent_duration: u8,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    let instruction = rent_instruction(
+        rent_amount,
+        rent_duration,
+        &test_state.renter_keypair.pubkey(),
+        &test_state.renter_temp_sol_token_account_keypair.pubkey(),
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    rpc_simulate_instruction(instruction, &test_state.renter_keypair, test_state)
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_stop_rent(
+    rented_at: i64,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    let instruction = rpc_stop_rent_instruction(rented_at, test_state)?;
+    rpc_simulate_instruction(instruction, &test_state.renter_keypair, test_state)
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_claim(
+    renter_address: &Pubkey,
+    rented_at: i64,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    let instruction = rpc_claim_instruction(renter_address, rented_at, test_state)?;
+    rpc_simulate_instruction(instruction, &test_state.lender_keypair, test_state)
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_initialize_admin_state(
+    fee: u32,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    let instruction = initialize_admin_state_instruction(
+        fee,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    rpc_simulate_instruction(instruction, &test_state.admin_keypair, test_state)
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_set_fee(fee: u32, test_state: &RpcState) -> Result<Simulation, TestError> {
+    let instruction = set_fee_instruction(
+        fee,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    rpc_simulate_instruction(instruction, &test_state.admin_keypair, test_state)
+}
+
+#[allow(dead_code)]
+pub fn rpc_simulate_set_payable_account(
+    admin_token_account: Pubkey,
+    test_state: &RpcState,
+) -> Result<Simulation, TestError> {
+    let instruction = set_payable_account_instruction(
+        admin_token_account,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    rpc_simulate_instruction(instruction, &test_state.admin_keypair, test_state)
+}
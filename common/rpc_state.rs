@@ -1,10 +1,16 @@
 use {
-    crate::common::{error::TestError, program, rpc_bench::RpcBench},
+    crate::common::{
+        error::TestError,
+        find_valid_renting, labels, program,
+        progress::{LineProgress, ProgressReporter},
+        rpc_bench::RpcBench,
+        rpc_client_utils::SendConfig,
+        telemetry::TestTrace,
+    },
     solana_client::rpc_client::RpcClient,
     solana_program::pubkey::Pubkey,
-    solana_sdk::{
-        commitment_config::CommitmentConfig, signature::Signer, signer::keypair::Keypair,
-    },
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
 };
 pub struct RpcState {
     pub rpc_bench: RpcBench,
@@ -28,17 +34,67 @@ pub struct RpcState {
     pub pda_pubkey: Pubkey,
     pub pda_bump_seed: u8,
     pub sol_token_pubkey: Pubkey,
+    // `None` when the test traces to a subscriber of its own
+    pub trace: Option<TestTrace>,
 }
 
 impl RpcState {
     pub fn initialize(
         max_renters: u32,
         renter_temp_sol_token_amount: u64,
+    ) -> Result<Self, TestError> {
+        Self::initialize_with_send_config(
+            max_renters,
+            renter_temp_sol_token_amount,
+            SendConfig::default(),
+        )
+    }
+
+    // The fixture, including the program deploy, is sent with `send_config`, which also stays the
+    // default of `rpc_bench`
+    pub fn initialize_with_send_config(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        send_config: SendConfig,
+    ) -> Result<Self, TestError> {
+        Self::initialize_with_progress(
+            max_renters,
+            renter_temp_sol_token_amount,
+            send_config,
+            &LineProgress,
+        )
+    }
+
+    // `progress` receives the events of the program deploy
+    pub fn initialize_with_progress(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        send_config: SendConfig,
+        progress: &dyn ProgressReporter,
+    ) -> Result<Self, TestError> {
+        let trace = TestTrace::start()?;
+        let mut state = Self::initialize_impl(
+            max_renters,
+            renter_temp_sol_token_amount,
+            send_config,
+            progress,
+        )?;
+        state.trace = trace;
+        Ok(state)
+    }
+
+    // Without a `TestTrace`, for `AsyncRpcState`, which sets the fixture up on a blocking thread
+    // that is not the test's own
+    pub fn initialize_impl(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        send_config: SendConfig,
+        progress: &dyn ProgressReporter,
     ) -> Result<Self, TestError> {
         let rpc_url = "http://localhost:8899".to_string();
-        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+        let rpc_client = RpcClient::new_with_commitment(rpc_url, send_config.commitment);
 
-        let rpc_bench = RpcBench::start_new(rpc_client);
+        let rpc_bench = RpcBench::start_new_with_send_config(rpc_client, send_config);
 
         let sol_token_pubkey = Pubkey::new_from_array([
             6, 155, 136, 87, 254, 171, 129, 132, 251, 104, 127, 99, 70, 24, 192, 53, 218, 196, 57,
@@ -68,66 +124,66 @@ impl RpcState {
             lender_keypair.pubkey(),
             renter_keypair.pubkey(),
         ];
-        println!("Airdropping SOL to minter, lender and renter");
+        tracing::info!("Airdropping SOL to minter, lender and renter");
         rpc_bench.airdrop(airdropped_addresses)?;
 
-        println!("Creating and initializing mint account for x token");
+        tracing::info!("Creating and initializing mint account for x token");
         let x_token_keypair =
             rpc_bench.create_mint(&minter_keypair.pubkey(), false, &minter_keypair)?;
 
-        println!("Creating a SOL token account for PDA");
+        tracing::info!("Creating a SOL token account for PDA");
         rpc_bench.create_token_account(
             sol_token_pubkey,
             &lender_keypair,
             &pda_sol_token_account_keypair,
         )?;
 
-        println!("Creating a SOL token account for admin");
+        tracing::info!("Creating a SOL token account for admin");
         rpc_bench.create_token_account(
             sol_token_pubkey,
             &admin_keypair,
             &admin_sol_token_account_keypair,
         )?;
 
-        println!("Creating a SOL token account for lender");
+        tracing::info!("Creating a SOL token account for lender");
         rpc_bench.create_token_account(
             sol_token_pubkey,
             &lender_keypair,
             &lender_sol_token_account_keypair,
         )?;
 
-        println!("Creating a SOL token account for renter");
+        tracing::info!("Creating a SOL token account for renter");
         rpc_bench.create_token_account(
             sol_token_pubkey,
             &renter_keypair,
             &renter_sol_token_account_keypair,
         )?;
 
-        println!("Wrapping native SOL into spl-token SOL");
+        tracing::info!("Wrapping native SOL into spl-token SOL");
         rpc_bench.wrap_sol(
             &renter_keypair,
             &renter_temp_sol_token_account_keypair,
             renter_temp_sol_token_amount,
         )?;
 
-        println!("Creating and initializing mint account for NFT");
+        tracing::info!("Creating and initializing mint account for NFT");
         let nft_keypair = rpc_bench.create_mint(&minter_keypair.pubkey(), true, &minter_keypair)?;
 
-        println!("Creating a temporary NFT account for lender");
+        tracing::info!("Creating a temporary NFT account for lender");
         rpc_bench.create_token_account(
             nft_keypair.pubkey(),
             &lender_keypair,
             &lender_temp_nft_account_keypair,
         )?;
 
-        println!("Creating an NFT account for lender");
+        tracing::info!("Creating an NFT account for lender");
         rpc_bench.create_token_account(
             nft_keypair.pubkey(),
             &lender_keypair,
             &lender_main_nft_account_keypair,
         )?;
 
-        println!("Minting  NFT to NFT account for lender");
+        tracing::info!("Minting  NFT to NFT account for lender");
         rpc_bench.mint_tokens(
             lender_temp_nft_account_keypair.pubkey(),
             &nft_keypair,
@@ -135,11 +191,11 @@ impl RpcState {
             max_renters.into(),
         )?;
 
-        println!("Creating admin state account");
+        tracing::info!("Creating admin state account");
         let admin_state_account_keypair =
             rpc_bench.create_admin_state_account(&admin_keypair, program_id)?;
 
-        println!("Creating escrow state account");
+        tracing::info!("Creating escrow state account");
         let escrow_state_account_keypair =
             rpc_bench.create_escrow_state_account(max_renters, &lender_keypair, program_id)?;
 
@@ -149,32 +205,16 @@ impl RpcState {
             signers,
             &program_keypair,
             &rpc_bench.rpc_client,
+            &rpc_bench.send_config,
+            progress,
         )?;
 
         let (pda_pubkey, pda_bump_seed) =
             Pubkey::find_program_address(&["escrow".as_bytes()], &program_id);
 
-        println!("Initialization complete.");
-
-        println!(
-        "program: {}\nminter: {}\nadmin: {}\nlender: {}\nrenter: {}\nsol_token: {}\nlender_sol_token_account: {}\nrenter_sol_token_account: {}\nrenter_temp_sol_token_account: {}\npda_sol_token_account: {}\nnft: {}\nlender_temp_nft_account: {}\nadmin_state_account: {}\nescrow_state_account: {}",
-        program_id,
-        minter_keypair.pubkey(),
-        admin_keypair.pubkey(),
-        lender_keypair.pubkey(),
-        renter_keypair.pubkey(),
-        x_token_keypair.pubkey(),
-        lender_sol_token_account_keypair.pubkey(),
-        renter_sol_token_account_keypair.pubkey(),
-        renter_temp_sol_token_account_keypair.pubkey(),
-        pda_sol_token_account_keypair.pubkey(),
-        nft_keypair.pubkey(),
-        lender_temp_nft_account_keypair.pubkey(),
-        admin_state_account_keypair.pubkey(),
-        escrow_state_account_keypair.pubkey(),
-        );
-
-        Ok(Self {
+        tracing::info!("Initialization complete.");
+
+        let state = Self {
             rpc_bench,
             program_id,
             minter_keypair,
@@ -196,6 +236,72 @@ impl RpcState {
             pda_pubkey,
             pda_bump_seed,
             sol_token_pubkey,
-        })
+            trace: None,
+        };
+        state.register_labels();
+        Ok(state)
+    }
+
+    fn register_labels(&self) {
+        labels::register_all(&[
+            ("program", self.program_id),
+            ("pda", self.pda_pubkey),
+            ("minter", self.minter_keypair.pubkey()),
+            ("admin", self.admin_keypair.pubkey()),
+            ("lender", self.lender_keypair.pubkey()),
+            ("renter", self.renter_keypair.pubkey()),
+            ("sol_token", self.sol_token_pubkey),
+            ("x_token", self.x_token_keypair.pubkey()),
+            (
+                "payer_sol_token_account",
+                self.payer_sol_token_account_keypair.pubkey(),
+            ),
+            (
+                "admin_sol_token_account",
+                self.admin_sol_token_account_keypair.pubkey(),
+            ),
+            (
+                "lender_sol_token_account",
+                self.lender_sol_token_account_keypair.pubkey(),
+            ),
+            (
+                "renter_sol_token_account",
+                self.renter_sol_token_account_keypair.pubkey(),
+            ),
+            (
+                "renter_temp_sol_token_account",
+                self.renter_temp_sol_token_account_keypair.pubkey(),
+            ),
+            (
+                "pda_sol_token_account",
+                self.pda_sol_token_account_keypair.pubkey(),
+            ),
+            ("nft", self.nft_keypair.pubkey()),
+            (
+                "lender_temp_nft_account",
+                self.lender_temp_nft_account_keypair.pubkey(),
+            ),
+            (
+                "lender_main_nft_account",
+                self.lender_main_nft_account_keypair.pubkey(),
+            ),
+            (
+                "admin_state_account",
+                self.admin_state_account_keypair.pubkey(),
+            ),
+            (
+                "escrow_state_account",
+                self.escrow_state_account_keypair.pubkey(),
+            ),
+        ]);
+    }
+
+    #[allow(dead_code)]
+    pub fn find_valid_renting(&self) -> Result<Renting, TestError> {
+        let account = self
+            .rpc_bench
+            .get_account(&self.escrow_state_account_keypair.pubkey())
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        find_valid_renting(&account.data)
     }
 }
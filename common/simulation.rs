@@ -0,0 +1,64 @@
+use {
+    crate::common::error::TestError,
+    crate::common::labels::{account_dump, substitute},
+    solana_program::{message::Message, program_pack::Pack, pubkey::Pubkey},
+    solana_sdk::account::Account,
+    std::fmt,
+};
+
+// Outcome of a transaction that was simulated instead of committed.
+pub struct Simulation {
+    pub result: Result<(), TestError>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+    // State of every writable account after the transaction, or before it when it failed. `None`
+    // when the account does not exist.
+    pub accounts: Vec<(Pubkey, Option<Account>)>,
+}
+
+impl Simulation {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Option<&Account> {
+        self.accounts
+            .iter()
+            .find(|(key, _)| key == address)
+            .and_then(|(_, account)| account.as_ref())
+    }
+
+    pub fn token_account(&self, address: &Pubkey) -> Result<spl_token::state::Account, TestError> {
+        let account = self
+            .account(address)
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        spl_token::state::Account::unpack(&account.data)
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+    }
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.result {
+            Ok(()) => writeln!(f, "ok, {} compute units", self.units_consumed)?,
+            Err(e) => writeln!(f, "failed: {}, {} compute units", e, self.units_consumed)?,
+        }
+        for log in self.logs.iter() {
+            writeln!(f, "  {}", substitute(log))?;
+        }
+        for (address, account) in self.accounts.iter() {
+            writeln!(f, "  {}", account_dump(address, account))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn writable_accounts(message: &Message) -> Vec<Pubkey> {
+    message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_writable(*i))
+        .map(|(_, key)| *key)
+        .collect()
+}
@@ -0,0 +1,49 @@
+use {
+    arrayref::{array_mut_ref, mut_array_refs},
+    solana_program::{pubkey::Pubkey, rent::Rent},
+    solana_renft_collateral_free::admin::{ADMIN_LEN, TOKEN_ACCOUNTS_LEN},
+    solana_sdk::account::Account,
+};
+
+pub fn pack_admin_state(
+    token_accounts: &[u8; TOKEN_ACCOUNTS_LEN],
+    fee_bps: u32,
+    is_initialized: bool,
+) -> Vec<u8> {
+    let mut data = vec![0; ADMIN_LEN];
+    let dst = array_mut_ref![data, 0, ADMIN_LEN];
+    let (token_accounts_dst, fee_dst, is_initialized_dst) =
+        mut_array_refs![dst, TOKEN_ACCOUNTS_LEN, 4, 1];
+    token_accounts_dst.copy_from_slice(token_accounts);
+    *fee_dst = fee_bps.to_le_bytes();
+    is_initialized_dst[0] = is_initialized as u8;
+    data
+}
+
+// Escrow accounts are crafted from the bytes of a genuine escrow rather than packed field by
+// field, so every pubkey stored in them can be swapped without depending on the layout.
+pub fn replace_pubkey(data: &mut [u8], from: &Pubkey, to: &Pubkey) -> usize {
+    let from = from.to_bytes();
+    let mut replaced = 0;
+    let mut i = 0;
+    while i + from.len() <= data.len() {
+        if data[i..i + from.len()] == from {
+            data[i..i + from.len()].copy_from_slice(&to.to_bytes());
+            replaced += 1;
+            i += from.len();
+        } else {
+            i += 1;
+        }
+    }
+    replaced
+}
+
+pub fn spoofed_account(data: Vec<u8>, owner: &Pubkey, rent: &Rent) -> Account {
+    Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
@@ -1,13 +1,69 @@
 use {
     crate::common::bench::ProgramTestBench,
-    crate::common::clone_keypair,
+    crate::common::compute_budget::ComputeBudget,
     crate::common::error::TestError,
-    solana_program::pubkey::Pubkey,
+    crate::common::invariants::{self, Invariants},
+    crate::common::labels,
+    crate::common::simulation::{writable_accounts, Simulation},
+    crate::common::telemetry::TestTrace,
+    crate::common::{clone_keypair, find_valid_renting},
+    solana_program::{instruction::Instruction, message::Message, pubkey::Pubkey},
     solana_program_test::{processor, ProgramTest},
-    solana_renft_collateral_free::processor::process_instruction,
-    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+    solana_renft_collateral_free::{processor::process_instruction, renting::Renting},
+    solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair},
+    spl_associated_token_account::get_associated_token_address,
     std::str::FromStr,
 };
+const USDC_DECIMALS: u8 = 6;
+const SOL_TOKEN_PUBKEY: Pubkey = Pubkey::new_from_array([
+    6, 155, 136, 87, 254, 171, 129, 132, 251, 104, 127, 99, 70, 24, 192, 53, 218, 196, 57, 220, 26,
+    235, 59, 85, 152, 160, 240, 0, 0, 0, 0, 1,
+]);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Currency {
+    Sol,
+    Usdc,
+}
+
+// In `Associated` mode the wallet token accounts of admin, lender and renter, including the
+// lender's main NFT account, are associated token accounts. Their keypair fields are then
+// unused and the `*_pubkey` accessors must be used instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenAccountMode {
+    Keypair,
+    Associated,
+}
+
+// Addresses of the fixture that are fixed before the bank starts, so that accounts loaded at
+// genesis can refer to them.
+#[derive(Clone, Copy, Debug)]
+pub struct GenesisKeys {
+    pub program_id: Pubkey,
+    pub lender: Pubkey,
+    pub renter: Pubkey,
+    pub admin_sol_token_account: Pubkey,
+    pub lender_sol_token_account: Pubkey,
+    pub renter_temp_sol_token_account: Pubkey,
+    pub pda_sol_token_account: Pubkey,
+    pub lender_temp_nft_account: Pubkey,
+}
+
+// Accounts added with `ProgramTest::add_account` before the bank starts
+pub type GenesisAccounts = Box<dyn FnOnce(&GenesisKeys) -> Vec<(Pubkey, Account)>>;
+
+fn wallet_token_account_pubkey(
+    token_account_mode: TokenAccountMode,
+    owner: &Pubkey,
+    token: &Pubkey,
+    account: &Keypair,
+) -> Pubkey {
+    match token_account_mode {
+        TokenAccountMode::Keypair => account.pubkey(),
+        TokenAccountMode::Associated => get_associated_token_address(owner, token),
+    }
+}
+
 pub struct State {
     pub bench: ProgramTestBench,
     pub program_id: Pubkey,
@@ -19,6 +75,9 @@ pub struct State {
     pub escrow_state_account_keypair: Keypair,
     pub x_token_keypair: Keypair,
     pub payer_sol_token_account_keypair: Keypair,
+    // The admin, lender and renter accounts and the lender's main NFT account only exist under
+    // `TokenAccountMode::Keypair`. Helpers address them through the `*_pubkey` accessors, which
+    // follow `token_account_mode`.
     pub admin_sol_token_account_keypair: Keypair,
     pub lender_sol_token_account_keypair: Keypair,
     pub renter_sol_token_account_keypair: Keypair,
@@ -26,6 +85,8 @@ pub struct State {
     pub pda_sol_token_account_keypair: Keypair,
     pub admin_usdc_token_account_keypair: Keypair,
     pub lender_usdc_token_account_keypair: Keypair,
+    pub renter_usdc_token_account_keypair: Keypair,
+    pub renter_temp_usdc_token_account_keypair: Keypair,
     pub pda_usdc_token_account_keypair: Keypair,
     pub nft_keypair: Keypair,
     pub lender_temp_nft_account_keypair: Keypair,
@@ -34,6 +95,23 @@ pub struct State {
     pub pda_bump_seed: u8,
     pub sol_token_pubkey: Pubkey,
     pub usdc_token_pubkey: Pubkey,
+    pub token_account_mode: TokenAccountMode,
+    pub invariants: Invariants,
+    // `None` when the test traces to a subscriber of its own
+    pub trace: Option<TestTrace>,
+}
+
+async fn create_wallet_token_account(
+    bench: &mut ProgramTestBench,
+    token_account_mode: TokenAccountMode,
+    token: Pubkey,
+    owner: &Keypair,
+    account: &Keypair,
+) -> Result<Pubkey, TestError> {
+    match token_account_mode {
+        TokenAccountMode::Keypair => bench.create_token_account(token, owner, account).await,
+        TokenAccountMode::Associated => bench.create_associated_token_account(token, owner).await,
+    }
 }
 
 impl State {
@@ -41,24 +119,62 @@ impl State {
         max_renters: u32,
         renter_temp_sol_token_amount: u64,
     ) -> Result<Self, TestError> {
-        let mut program_test = ProgramTest::default();
-        let program_id = Pubkey::from_str("ReNFTCFtqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM").unwrap();
-        program_test.add_program(
-            "solana_renft_collateral_free",
-            program_id,
-            processor!(process_instruction),
-        );
+        Self::initialize_with_accounts(max_renters, renter_temp_sol_token_amount, 0, vec![]).await
+    }
 
-        let mut bench = ProgramTestBench::start_new(program_test).await;
-        let sol_token_pubkey = Pubkey::new_from_array([
-            6, 155, 136, 87, 254, 171, 129, 132, 251, 104, 127, 99, 70, 24, 192, 53, 218, 196, 57,
-            220, 26, 235, 59, 85, 152, 160, 240, 0, 0, 0, 0, 1,
-        ]);
-        let usdc_token_pubkey = Pubkey::new_from_array([
-            198, 250, 122, 243, 190, 219, 173, 58, 61, 101, 243, 106, 171, 201, 116, 49, 177, 187,
-            228, 194, 210, 246, 224, 228, 124, 166, 2, 3, 69, 47, 93, 97,
-        ]);
+    pub async fn initialize_with_usdc(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        renter_temp_usdc_token_amount: u64,
+    ) -> Result<Self, TestError> {
+        Self::initialize_with_accounts(
+            max_renters,
+            renter_temp_sol_token_amount,
+            renter_temp_usdc_token_amount,
+            vec![],
+        )
+        .await
+    }
 
+    pub async fn initialize_with_ata(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        renter_temp_usdc_token_amount: u64,
+    ) -> Result<Self, TestError> {
+        Self::initialize_impl(
+            max_renters,
+            renter_temp_sol_token_amount,
+            renter_temp_usdc_token_amount,
+            TokenAccountMode::Associated,
+            Box::new(|_| vec![]),
+        )
+        .await
+    }
+
+    pub async fn initialize_with_accounts(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        renter_temp_usdc_token_amount: u64,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<Self, TestError> {
+        Self::initialize_impl(
+            max_renters,
+            renter_temp_sol_token_amount,
+            renter_temp_usdc_token_amount,
+            TokenAccountMode::Keypair,
+            Box::new(move |_| accounts),
+        )
+        .await
+    }
+
+    pub async fn initialize_impl(
+        max_renters: u32,
+        renter_temp_sol_token_amount: u64,
+        renter_temp_usdc_token_amount: u64,
+        token_account_mode: TokenAccountMode,
+        accounts: GenesisAccounts,
+    ) -> Result<Self, TestError> {
+        let trace = TestTrace::start()?;
         let minter_keypair = Keypair::new();
         let admin_keypair = Keypair::from_base58_string("37VJVob2dCRVhFVFoazgTnvi39Jt5aTRfzS1nGjGbMPmjwxo2uZeGMLrGtaR9w95CgnQZCYDCcfJYF33wXpBAK4u");
         let lender_keypair = Keypair::new();
@@ -71,37 +187,78 @@ impl State {
         let pda_sol_token_account_keypair = Keypair::new();
         let admin_usdc_token_account_keypair = Keypair::new();
         let lender_usdc_token_account_keypair = Keypair::new();
+        let renter_usdc_token_account_keypair = Keypair::new();
+        let renter_temp_usdc_token_account_keypair = Keypair::new();
         let pda_usdc_token_account_keypair = Keypair::new();
         let lender_temp_nft_account_keypair = Keypair::new();
         let lender_main_nft_account_keypair = Keypair::new();
 
+        let program_id = Pubkey::from_str("ReNFTCFtqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM").unwrap();
+        let genesis_keys = GenesisKeys {
+            program_id,
+            lender: lender_keypair.pubkey(),
+            renter: renter_keypair.pubkey(),
+            admin_sol_token_account: wallet_token_account_pubkey(
+                token_account_mode,
+                &admin_keypair.pubkey(),
+                &SOL_TOKEN_PUBKEY,
+                &admin_sol_token_account_keypair,
+            ),
+            lender_sol_token_account: wallet_token_account_pubkey(
+                token_account_mode,
+                &lender_keypair.pubkey(),
+                &SOL_TOKEN_PUBKEY,
+                &lender_sol_token_account_keypair,
+            ),
+            renter_temp_sol_token_account: renter_temp_sol_token_account_keypair.pubkey(),
+            pda_sol_token_account: pda_sol_token_account_keypair.pubkey(),
+            lender_temp_nft_account: lender_temp_nft_account_keypair.pubkey(),
+        };
+
+        let mut program_test = ProgramTest::default();
+        program_test.add_program(
+            "solana_renft_collateral_free",
+            program_id,
+            processor!(process_instruction),
+        );
+        for (address, account) in accounts(&genesis_keys) {
+            program_test.add_account(address, account);
+        }
+
+        let mut bench = ProgramTestBench::start_new(program_test).await;
+        let sol_token_pubkey = SOL_TOKEN_PUBKEY;
+        let usdc_token_pubkey = Pubkey::new_from_array([
+            198, 250, 122, 243, 190, 219, 173, 58, 61, 101, 243, 106, 171, 201, 116, 49, 177, 187,
+            228, 194, 210, 246, 224, 228, 124, 166, 2, 3, 69, 47, 93, 97,
+        ]);
+
         let airdropped_addresses = vec![
             minter_keypair.pubkey(),
             admin_keypair.pubkey(),
             lender_keypair.pubkey(),
             renter_keypair.pubkey(),
         ];
-        println!("Airdropping SOL to minter, lender and renter");
+        tracing::info!("Airdropping SOL to minter, lender and renter");
         bench.airdrop(airdropped_addresses).await?;
 
-        println!("Creating and initializing mint account for x token");
+        tracing::info!("Creating and initializing mint account for x token");
         let x_token_keypair = bench
             .create_mint(&minter_keypair.pubkey(), false, &minter_keypair)
             .await?;
 
-        println!("Creating and initializing mint account for USDC token");
+        tracing::info!("Creating and initializing mint account for USDC token");
         bench
             .create_mint_with_address(
                 &minter_keypair.pubkey(),
                 false,
                 &minter_keypair,
-                6,
+                USDC_DECIMALS,
                 4000000,
                 &usdc_token_pubkey,
             )
             .await?;
 
-        println!("Creating a SOL token account for PDA");
+        tracing::info!("Creating a SOL token account for PDA");
         bench
             .create_token_account(
                 sol_token_pubkey,
@@ -110,34 +267,37 @@ impl State {
             )
             .await?;
 
-        println!("Creating a SOL token account for admin");
-        bench
-            .create_token_account(
-                sol_token_pubkey,
-                &admin_keypair,
-                &admin_sol_token_account_keypair,
-            )
-            .await?;
+        tracing::info!("Creating a SOL token account for admin");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            sol_token_pubkey,
+            &admin_keypair,
+            &admin_sol_token_account_keypair,
+        )
+        .await?;
 
-        println!("Creating a SOL token account for lender");
-        bench
-            .create_token_account(
-                sol_token_pubkey,
-                &lender_keypair,
-                &lender_sol_token_account_keypair,
-            )
-            .await?;
+        tracing::info!("Creating a SOL token account for lender");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            sol_token_pubkey,
+            &lender_keypair,
+            &lender_sol_token_account_keypair,
+        )
+        .await?;
 
-        println!("Creating a SOL token account for renter");
-        bench
-            .create_token_account(
-                sol_token_pubkey,
-                &renter_keypair,
-                &renter_sol_token_account_keypair,
-            )
-            .await?;
+        tracing::info!("Creating a SOL token account for renter");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            sol_token_pubkey,
+            &renter_keypair,
+            &renter_sol_token_account_keypair,
+        )
+        .await?;
 
-        println!("Creating a temporary SOL token account for renter");
+        tracing::info!("Creating a temporary SOL token account for renter");
         bench
             .create_token_account(
                 sol_token_pubkey,
@@ -146,25 +306,46 @@ impl State {
             )
             .await?;
 
-        println!("Creating a USDC token account for admin");
-        bench
-            .create_token_account(
-                usdc_token_pubkey,
-                &admin_keypair,
-                &admin_usdc_token_account_keypair,
-            )
-            .await?;
+        tracing::info!("Creating a USDC token account for admin");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            usdc_token_pubkey,
+            &admin_keypair,
+            &admin_usdc_token_account_keypair,
+        )
+        .await?;
 
-        println!("Creating a USDC token account for lender");
+        tracing::info!("Creating a USDC token account for lender");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            usdc_token_pubkey,
+            &lender_keypair,
+            &lender_usdc_token_account_keypair,
+        )
+        .await?;
+
+        tracing::info!("Creating a USDC token account for renter");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            usdc_token_pubkey,
+            &renter_keypair,
+            &renter_usdc_token_account_keypair,
+        )
+        .await?;
+
+        tracing::info!("Creating a temporary USDC token account for renter");
         bench
             .create_token_account(
                 usdc_token_pubkey,
-                &lender_keypair,
-                &lender_usdc_token_account_keypair,
+                &renter_keypair,
+                &renter_temp_usdc_token_account_keypair,
             )
             .await?;
 
-        println!("Creating a USDC token account for PDA");
+        tracing::info!("Creating a USDC token account for PDA");
         bench
             .create_token_account(
                 usdc_token_pubkey,
@@ -173,7 +354,7 @@ impl State {
             )
             .await?;
 
-        println!("Wrapping native SOL into spl-token SOL");
+        tracing::info!("Wrapping native SOL into spl-token SOL");
         bench
             .wrap_sol(
                 &clone_keypair(&bench.payer),
@@ -181,7 +362,7 @@ impl State {
                 renter_temp_sol_token_amount,
             )
             .await?;
-        println!("Transfer SOL tokens to temporary SOL token account for renter");
+        tracing::info!("Transfer SOL tokens to temporary SOL token account for renter");
         bench
             .transfer_tokens(
                 &clone_keypair(&bench.payer),
@@ -191,12 +372,25 @@ impl State {
             )
             .await?;
 
-        println!("Creating and initializing mint account for NFT");
+        if renter_temp_usdc_token_amount > 0 {
+            tracing::info!("Minting USDC tokens to temporary USDC token account for renter");
+            bench
+                .mint_tokens_with_address(
+                    renter_temp_usdc_token_account_keypair.pubkey(),
+                    &usdc_token_pubkey,
+                    &minter_keypair,
+                    renter_temp_usdc_token_amount,
+                    USDC_DECIMALS,
+                )
+                .await?;
+        }
+
+        tracing::info!("Creating and initializing mint account for NFT");
         let nft_keypair = bench
             .create_mint(&minter_keypair.pubkey(), true, &minter_keypair)
             .await?;
 
-        println!("Creating a temporary NFT account for lender");
+        tracing::info!("Creating a temporary NFT account for lender");
         bench
             .create_token_account(
                 nft_keypair.pubkey(),
@@ -205,16 +399,17 @@ impl State {
             )
             .await?;
 
-        println!("Creating an NFT account for lender");
-        bench
-            .create_token_account(
-                nft_keypair.pubkey(),
-                &lender_keypair,
-                &lender_main_nft_account_keypair,
-            )
-            .await?;
+        tracing::info!("Creating an NFT account for lender");
+        create_wallet_token_account(
+            &mut bench,
+            token_account_mode,
+            nft_keypair.pubkey(),
+            &lender_keypair,
+            &lender_main_nft_account_keypair,
+        )
+        .await?;
 
-        println!("Minting  NFT to NFT account for lender");
+        tracing::info!("Minting  NFT to NFT account for lender");
         bench
             .mint_tokens(
                 lender_temp_nft_account_keypair.pubkey(),
@@ -224,12 +419,12 @@ impl State {
             )
             .await?;
 
-        println!("Creating admin state account");
+        tracing::info!("Creating admin state account");
         let admin_state_account_keypair = bench
             .create_admin_state_account(&admin_keypair, program_id)
             .await?;
 
-        println!("Creating escrow state account");
+        tracing::info!("Creating escrow state account");
         let escrow_state_account_keypair = bench
             .create_escrow_state_account(max_renters, &lender_keypair, program_id)
             .await?;
@@ -237,35 +432,9 @@ impl State {
         let (pda_pubkey, pda_bump_seed) =
             Pubkey::find_program_address(&["escrow".as_bytes()], &program_id);
 
-        println!("Initialization complete.");
-
-        println!(
-        "program: {}\npda: {}\nminter: {}\nadmin: {}\nlender: {}\nrenter: {}\nsol_token: {}\nusdc_token: {}\nx_token: {}\nadmin_sol_token_account: {}\nlender_sol_token_account: {}\nrenter_sol_token_account: {}\nrenter_temp_sol_token_account: {}\npda_sol_token_account: {}\nadmin_usdc_token_account: {}\nlender_usdc_token_account: {}\npda_usdc_token_account: {}\nnft: {}\nlender_temp_nft_account: {}\nlender_main_nft_account: {}\nadmin_state_account: {}\nescrow_state_account: {}",
-        program_id,
-        pda_pubkey,
-        minter_keypair.pubkey(),
-        admin_keypair.pubkey(),
-        lender_keypair.pubkey(),
-        renter_keypair.pubkey(),
-        sol_token_pubkey,
-        usdc_token_pubkey,
-        x_token_keypair.pubkey(),
-        admin_sol_token_account_keypair.pubkey(),
-        lender_sol_token_account_keypair.pubkey(),
-        renter_sol_token_account_keypair.pubkey(),
-        renter_temp_sol_token_account_keypair.pubkey(),
-        pda_sol_token_account_keypair.pubkey(),
-        admin_usdc_token_account_keypair.pubkey(),
-        lender_usdc_token_account_keypair.pubkey(),
-        pda_usdc_token_account_keypair.pubkey(),
-        nft_keypair.pubkey(),
-        lender_temp_nft_account_keypair.pubkey(),
-        lender_main_nft_account_keypair.pubkey(),
-        admin_state_account_keypair.pubkey(),
-        escrow_state_account_keypair.pubkey(),
-        );
+        tracing::info!("Initialization complete.");
 
-        Ok(Self {
+        let state = Self {
             bench,
             program_id,
             minter_keypair,
@@ -281,6 +450,8 @@ impl State {
             pda_sol_token_account_keypair,
             admin_usdc_token_account_keypair,
             lender_usdc_token_account_keypair,
+            renter_usdc_token_account_keypair,
+            renter_temp_usdc_token_account_keypair,
             pda_usdc_token_account_keypair,
             nft_keypair,
             lender_temp_nft_account_keypair,
@@ -291,6 +462,242 @@ impl State {
             pda_bump_seed,
             sol_token_pubkey,
             usdc_token_pubkey,
-        })
+            token_account_mode,
+            invariants: Invariants::default(),
+            trace,
+        };
+        state.register_labels();
+        Ok(state)
+    }
+
+    fn register_labels(&self) {
+        labels::register_all(&[
+            ("program", self.program_id),
+            ("pda", self.pda_pubkey),
+            ("minter", self.minter_keypair.pubkey()),
+            ("admin", self.admin_keypair.pubkey()),
+            ("lender", self.lender_keypair.pubkey()),
+            ("renter", self.renter_keypair.pubkey()),
+            ("payer", self.bench.payer.pubkey()),
+            ("sol_token", self.sol_token_pubkey),
+            ("usdc_token", self.usdc_token_pubkey),
+            ("x_token", self.x_token_keypair.pubkey()),
+            (
+                "payer_sol_token_account",
+                self.payer_sol_token_account_keypair.pubkey(),
+            ),
+            (
+                "admin_sol_token_account",
+                self.admin_token_account_pubkey(Currency::Sol),
+            ),
+            (
+                "lender_sol_token_account",
+                self.lender_token_account_pubkey(Currency::Sol),
+            ),
+            (
+                "renter_sol_token_account",
+                self.renter_token_account_pubkey(Currency::Sol),
+            ),
+            (
+                "renter_temp_sol_token_account",
+                self.renter_temp_token_account_pubkey(Currency::Sol),
+            ),
+            (
+                "pda_sol_token_account",
+                self.pda_token_account_pubkey(Currency::Sol),
+            ),
+            (
+                "admin_usdc_token_account",
+                self.admin_token_account_pubkey(Currency::Usdc),
+            ),
+            (
+                "lender_usdc_token_account",
+                self.lender_token_account_pubkey(Currency::Usdc),
+            ),
+            (
+                "renter_usdc_token_account",
+                self.renter_token_account_pubkey(Currency::Usdc),
+            ),
+            (
+                "renter_temp_usdc_token_account",
+                self.renter_temp_token_account_pubkey(Currency::Usdc),
+            ),
+            (
+                "pda_usdc_token_account",
+                self.pda_token_account_pubkey(Currency::Usdc),
+            ),
+            ("nft", self.nft_keypair.pubkey()),
+            (
+                "lender_temp_nft_account",
+                self.lender_temp_nft_account_keypair.pubkey(),
+            ),
+            (
+                "lender_main_nft_account",
+                self.lender_main_nft_account_pubkey(),
+            ),
+            (
+                "admin_state_account",
+                self.admin_state_account_keypair.pubkey(),
+            ),
+            (
+                "escrow_state_account",
+                self.escrow_state_account_keypair.pubkey(),
+            ),
+        ]);
+    }
+
+    pub fn genesis_keys(&self) -> GenesisKeys {
+        GenesisKeys {
+            program_id: self.program_id,
+            lender: self.lender_keypair.pubkey(),
+            renter: self.renter_keypair.pubkey(),
+            admin_sol_token_account: self.admin_token_account_pubkey(Currency::Sol),
+            lender_sol_token_account: self.lender_token_account_pubkey(Currency::Sol),
+            renter_temp_sol_token_account: self.renter_temp_token_account_pubkey(Currency::Sol),
+            pda_sol_token_account: self.pda_token_account_pubkey(Currency::Sol),
+            lender_temp_nft_account: self.lender_temp_nft_account_keypair.pubkey(),
+        }
+    }
+
+    pub fn token_pubkey(&self, currency: Currency) -> Pubkey {
+        match currency {
+            Currency::Sol => self.sol_token_pubkey,
+            Currency::Usdc => self.usdc_token_pubkey,
+        }
+    }
+
+    pub fn admin_token_account_pubkey(&self, currency: Currency) -> Pubkey {
+        match (self.token_account_mode, currency) {
+            (TokenAccountMode::Keypair, Currency::Sol) => {
+                self.admin_sol_token_account_keypair.pubkey()
+            }
+            (TokenAccountMode::Keypair, Currency::Usdc) => {
+                self.admin_usdc_token_account_keypair.pubkey()
+            }
+            (TokenAccountMode::Associated, _) => get_associated_token_address(
+                &self.admin_keypair.pubkey(),
+                &self.token_pubkey(currency),
+            ),
+        }
+    }
+
+    pub fn lender_token_account_pubkey(&self, currency: Currency) -> Pubkey {
+        match (self.token_account_mode, currency) {
+            (TokenAccountMode::Keypair, Currency::Sol) => {
+                self.lender_sol_token_account_keypair.pubkey()
+            }
+            (TokenAccountMode::Keypair, Currency::Usdc) => {
+                self.lender_usdc_token_account_keypair.pubkey()
+            }
+            (TokenAccountMode::Associated, _) => get_associated_token_address(
+                &self.lender_keypair.pubkey(),
+                &self.token_pubkey(currency),
+            ),
+        }
+    }
+
+    pub fn renter_token_account_pubkey(&self, currency: Currency) -> Pubkey {
+        match (self.token_account_mode, currency) {
+            (TokenAccountMode::Keypair, Currency::Sol) => {
+                self.renter_sol_token_account_keypair.pubkey()
+            }
+            (TokenAccountMode::Keypair, Currency::Usdc) => {
+                self.renter_usdc_token_account_keypair.pubkey()
+            }
+            (TokenAccountMode::Associated, _) => get_associated_token_address(
+                &self.renter_keypair.pubkey(),
+                &self.token_pubkey(currency),
+            ),
+        }
+    }
+
+    pub fn lender_main_nft_account_pubkey(&self) -> Pubkey {
+        match self.token_account_mode {
+            TokenAccountMode::Keypair => self.lender_main_nft_account_keypair.pubkey(),
+            TokenAccountMode::Associated => get_associated_token_address(
+                &self.lender_keypair.pubkey(),
+                &self.nft_keypair.pubkey(),
+            ),
+        }
+    }
+
+    pub fn renter_temp_token_account_pubkey(&self, currency: Currency) -> Pubkey {
+        match currency {
+            Currency::Sol => self.renter_temp_sol_token_account_keypair.pubkey(),
+            Currency::Usdc => self.renter_temp_usdc_token_account_keypair.pubkey(),
+        }
+    }
+
+    pub fn pda_token_account_pubkey(&self, currency: Currency) -> Pubkey {
+        match currency {
+            Currency::Sol => self.pda_sol_token_account_keypair.pubkey(),
+            Currency::Usdc => self.pda_usdc_token_account_keypair.pubkey(),
+        }
+    }
+
+    pub async fn process_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+    ) -> Result<(), TestError> {
+        self.process_transaction_with_compute_budget(
+            instructions,
+            payer,
+            signers,
+            &ComputeBudget::default(),
+        )
+        .await
+    }
+
+    // Sends through the bench and fails the test if the transaction left the fixture in a state
+    // that breaks one of the `invariants`. The transaction's own result is returned untouched,
+    // so rejections can still be asserted.
+    pub async fn process_transaction_with_compute_budget(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+        compute_budget: &ComputeBudget,
+    ) -> Result<(), TestError> {
+        let addresses = writable_accounts(&Message::new(instructions, payer));
+        let before = self.bench.snapshot_accounts(&addresses).await;
+        let result = self
+            .bench
+            .process_transaction_with_compute_budget(instructions, payer, signers, compute_budget)
+            .await;
+        let after = self.bench.snapshot_accounts(&addresses).await;
+
+        let violations = invariants::check(self, instructions, &before, &after).await;
+        if !violations.is_empty() {
+            panic!(
+                "{}",
+                invariants::report(instructions, &violations, &before, &after)
+            );
+        }
+        result
+    }
+
+    // Simulations commit nothing, so the `invariants` are not checked against their post-state.
+    // They still go through here so that every transaction of the `renft` helpers has one entry
+    // point.
+    pub async fn simulate_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&Keypair],
+    ) -> Result<Simulation, TestError> {
+        self.bench
+            .simulate_transaction(instructions, payer, signers)
+            .await
+    }
+
+    pub async fn find_valid_renting(&mut self) -> Result<Renting, TestError> {
+        let account = self
+            .bench
+            .get_account(&self.escrow_state_account_keypair.pubkey())
+            .await
+            .ok_or(TestError::TestError("Account not found".to_string()))?;
+        find_valid_renting(&account.data)
     }
 }
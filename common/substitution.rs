@@ -0,0 +1,220 @@
+use {
+    crate::common::{
+        clone_keypair,
+        error::{is_instruction_error, TestError},
+        state::State,
+    },
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_program, sysvar,
+    },
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+    std::future::Future,
+};
+
+pub struct Variant {
+    pub description: String,
+    pub instruction: Instruction,
+    // Variants that do not give the caller anything it could not already do are expected to go
+    // through, and are not reported when they do
+    pub harmless: bool,
+}
+
+pub struct Candidate {
+    pub label: String,
+    pub pubkey: Pubkey,
+}
+
+impl Candidate {
+    pub fn new(label: &str, pubkey: Pubkey) -> Self {
+        Self {
+            label: label.to_string(),
+            pubkey,
+        }
+    }
+}
+
+// One instruction under attack together with a fixture it is valid against. `run_variants`
+// builds a new scenario for every variant so that no variant sees what another one changed.
+pub struct Scenario {
+    pub test_state: State,
+    pub instruction: Instruction,
+    pub candidates: Vec<Candidate>,
+    // Caller-chosen inputs, which are not swapped
+    pub free_accounts: Vec<usize>,
+    // Swaps, as account index and candidate label, for an account that is as good as the
+    // original, such as another token account of the same owner and mint
+    pub equivalent_swaps: Vec<(usize, String)>,
+    pub keyring: Vec<Keypair>,
+}
+
+#[derive(Default)]
+pub struct SubstitutionReport {
+    pub rejected: usize,
+    pub unsignable: usize,
+    pub harmless: usize,
+    pub accepted: Vec<String>,
+    // Variants that failed before the program ran, which says nothing about the program
+    pub not_executed: Vec<String>,
+}
+
+fn is_program_account(pubkey: &Pubkey) -> bool {
+    *pubkey == system_program::id()
+        || *pubkey == spl_token::id()
+        || *pubkey == spl_token_2022::id()
+        || sysvar::is_sysvar_id(pubkey)
+}
+
+// Builds every variant of `scenario.instruction` where a single account is swapped for one of
+// the candidates, plus one variant per account with its writable or signer flag flipped.
+// Dropping a flag must be rejected. Promoting one is harmless, since the program only ever
+// checks that a flag is set, and promotions of program and sysvar accounts are left out because
+// the runtime refuses them before the program runs.
+pub fn generate_variants(scenario: &Scenario) -> Vec<Variant> {
+    let instruction = &scenario.instruction;
+    let mut variants = vec![];
+
+    for (index, account_meta) in instruction.accounts.iter().enumerate() {
+        if !scenario.free_accounts.contains(&index) {
+            for candidate in scenario.candidates.iter() {
+                if candidate.pubkey == account_meta.pubkey {
+                    continue;
+                }
+                let mut variant = instruction.clone();
+                variant.accounts[index] = AccountMeta {
+                    pubkey: candidate.pubkey,
+                    ..account_meta.clone()
+                };
+                variants.push(Variant {
+                    description: format!("account {} swapped for {}", index, candidate.label),
+                    instruction: variant,
+                    harmless: scenario
+                        .equivalent_swaps
+                        .iter()
+                        .any(|(i, label)| *i == index && *label == candidate.label),
+                });
+            }
+        }
+
+        let promotable = !is_program_account(&account_meta.pubkey);
+        if account_meta.is_writable || promotable {
+            let mut variant = instruction.clone();
+            variant.accounts[index].is_writable = !account_meta.is_writable;
+            variants.push(Variant {
+                description: format!(
+                    "account {} passed as {}",
+                    index,
+                    if account_meta.is_writable {
+                        "readonly"
+                    } else {
+                        "writable"
+                    }
+                ),
+                instruction: variant,
+                harmless: !account_meta.is_writable,
+            });
+        }
+
+        if account_meta.is_signer || promotable {
+            let mut variant = instruction.clone();
+            variant.accounts[index].is_signer = !account_meta.is_signer;
+            variants.push(Variant {
+                description: format!(
+                    "account {} passed {} signature",
+                    index,
+                    if account_meta.is_signer {
+                        "without"
+                    } else {
+                        "with"
+                    }
+                ),
+                instruction: variant,
+                harmless: !account_meta.is_signer,
+            });
+        }
+    }
+    variants
+}
+
+// The bench payer signs every variant and pays for it, so that dropping the signer flag of an
+// instruction account is not undone by that account also paying for the transaction. `None`
+// when an account needs a signature from a key missing from the keyring.
+fn signers<'a>(
+    instruction: &Instruction,
+    payer: &'a Keypair,
+    keyring: &'a [Keypair],
+) -> Option<Vec<&'a Keypair>> {
+    let mut signers = vec![payer];
+    for account_meta in instruction.accounts.iter() {
+        if !account_meta.is_signer || signers.iter().any(|s| s.pubkey() == account_meta.pubkey) {
+            continue;
+        }
+        signers.push(keyring.iter().find(|k| k.pubkey() == account_meta.pubkey)?);
+    }
+    Some(signers)
+}
+
+async fn send(scenario: &mut Scenario, instruction: Instruction) -> Option<Result<(), TestError>> {
+    let payer = clone_keypair(&scenario.test_state.bench.payer);
+    let signers = signers(&instruction, &payer, &scenario.keyring)?;
+    Some(
+        scenario
+            .test_state
+            .process_transaction(&[instruction], Some(&payer.pubkey()), &signers)
+            .await,
+    )
+}
+
+// Sends every variant through `State`, so the fixture invariants are checked as well, each on a
+// scenario of its own from `build`. The unaltered instruction is sent last on one more scenario
+// and must succeed, otherwise the rejections would say nothing.
+pub async fn run_variants<F, Fut>(mut build: F) -> Result<SubstitutionReport, TestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Scenario, TestError>>,
+{
+    let mut report = SubstitutionReport::default();
+
+    let mut index = 0;
+    loop {
+        let mut scenario = build().await?;
+        let variant = match generate_variants(&scenario).into_iter().nth(index) {
+            Some(variant) => variant,
+            None => break,
+        };
+        index += 1;
+
+        match send(&mut scenario, variant.instruction).await {
+            None => report.unsignable += 1,
+            Some(Ok(())) if variant.harmless => report.harmless += 1,
+            Some(Ok(())) => {
+                println!("POTENTIAL VULNERABILITY: {}", variant.description);
+                report.accepted.push(variant.description);
+            }
+            Some(result) if is_instruction_error(&result) => report.rejected += 1,
+            Some(Err(e)) => {
+                println!("Not executed: {}: {}", variant.description, e);
+                report.not_executed.push(variant.description);
+            }
+        }
+    }
+
+    let mut scenario = build().await?;
+    let instruction = scenario.instruction.clone();
+    send(&mut scenario, instruction)
+        .await
+        .ok_or(TestError::TestError(
+            "The unaltered instruction cannot be signed".to_string(),
+        ))??;
+
+    println!(
+        "Substitution variants: {} rejected, {} unsignable, {} harmless, {} accepted, {} not executed",
+        report.rejected,
+        report.unsignable,
+        report.harmless,
+        report.accepted.len(),
+        report.not_executed.len()
+    );
+    Ok(report)
+}
@@ -0,0 +1,171 @@
+use {
+    crate::common::error::TestError,
+    crate::common::labels::Labeled,
+    solana_program::{instruction::Instruction, pubkey::Pubkey, system_program},
+    solana_renft_collateral_free::instruction::EscrowInstruction,
+    std::{
+        fs::{self, File},
+        path::PathBuf,
+        sync::Mutex,
+        time::Instant,
+    },
+    tracing::{
+        field,
+        span::EnteredSpan,
+        subscriber::{DefaultGuard, NoSubscriber},
+        Span, Subscriber,
+    },
+    tracing_subscriber::{filter::filter_fn, fmt::format::FmtSpan, prelude::*},
+};
+
+const TRACE_DIR_VAR: &str = "RENFT_TRACE_DIR";
+const DEFAULT_TRACE_DIR: &str = "target/test-traces";
+
+// Names the escrow instruction as the program unpacks it, or the program that the instruction
+// goes to when it is not an escrow instruction.
+pub fn instruction_name(instruction: &Instruction) -> &'static str {
+    if instruction.program_id == system_program::id() {
+        return "System";
+    }
+    if instruction.program_id == spl_token::id() {
+        return "Token";
+    }
+    if instruction.program_id == spl_token_2022::id() {
+        return "Token2022";
+    }
+    if instruction.program_id == spl_associated_token_account::id() {
+        return "AssociatedToken";
+    }
+    if instruction.program_id == solana_sdk::compute_budget::id() {
+        return "ComputeBudget";
+    }
+    if instruction.program_id == solana_address_lookup_table_program::id() {
+        return "AddressLookupTable";
+    }
+    match EscrowInstruction::unpack(&instruction.data) {
+        Ok(EscrowInstruction::Lend { .. }) => "Lend",
+        Ok(EscrowInstruction::StopLend { .. }) => "StopLend",
+        Ok(EscrowInstruction::EditLend { .. }) => "EditLend",
+        Ok(EscrowInstruction::Rent { .. }) => "Rent",
+        Ok(EscrowInstruction::StopRent { .. }) => "StopRent",
+        Ok(EscrowInstruction::Claim { .. }) => "Claim",
+        Ok(EscrowInstruction::InitializeAdminState { .. }) => "InitializeAdminState",
+        Ok(EscrowInstruction::SetFee { .. }) => "SetFee",
+        Ok(EscrowInstruction::SetPayableAccount { .. }) => "SetPayableAccount",
+        Err(_) => "Unknown",
+    }
+}
+
+// Compute budget instructions are left out, they are on every transaction that sets a budget.
+pub fn instruction_names(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(instruction_name)
+        .filter(|name| *name != "ComputeBudget")
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// One span per transaction sent by a bench. `signature`, `slot` and `elapsed_ms` are recorded
+// once they are known.
+pub fn transaction_span(instructions: &[Instruction], actor: Option<&Pubkey>) -> Span {
+    tracing::info_span!(
+        "transaction",
+        instruction = %instruction_names(instructions),
+        actor = %actor.map(|actor| Labeled(actor).to_string()).unwrap_or_default(),
+        signature = field::Empty,
+        slot = field::Empty,
+        elapsed_ms = field::Empty,
+    )
+}
+
+pub fn record_outcome(
+    span: &Span,
+    started: Instant,
+    slot: Option<u64>,
+    result: &Result<(), TestError>,
+) {
+    if let Some(slot) = slot {
+        span.record("slot", &slot);
+    }
+    span.record("elapsed_ms", &(started.elapsed().as_millis() as u64));
+    match result {
+        Ok(()) => tracing::info!(parent: span, "confirmed"),
+        Err(e) => tracing::error!(parent: span, error = %e, "failed"),
+    }
+}
+
+pub fn trace_file_path(test_name: &str) -> PathBuf {
+    std::env::var(TRACE_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_TRACE_DIR))
+        .join(format!("{}.jsonl", test_name))
+}
+
+fn trace_file(test_name: &str) -> Result<Mutex<File>, TestError> {
+    let path = trace_file_path(test_name);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    }
+    let file = File::create(path).map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    Ok(Mutex::new(file))
+}
+
+// Every span and event goes to the JSON trace file. The steps of a test are also printed to the
+// test output, the "confirmed" and "failed" events of each transaction are only in the file.
+fn subscriber(test_name: &str) -> Result<impl Subscriber + Send + Sync, TestError> {
+    let json = tracing_subscriber::fmt::layer()
+        .json()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(trace_file(test_name)?);
+    let steps = tracing_subscriber::fmt::layer()
+        .without_time()
+        .with_target(false)
+        .with_test_writer()
+        .with_filter(filter_fn(|metadata| {
+            !metadata.target().ends_with("::telemetry")
+        }));
+    Ok(tracing_subscriber::registry().with(json).with(steps))
+}
+
+// Writes the spans and events of the current thread as JSON lines to
+// `target/test-traces/<test_name>.jsonl` (or `$RENFT_TRACE_DIR`) until the guard is dropped.
+// Enough for `#[tokio::test]`, which runs the whole test on one thread.
+pub fn trace_to_json_file(test_name: &str) -> Result<DefaultGuard, TestError> {
+    Ok(tracing::subscriber::set_default(subscriber(test_name)?))
+}
+
+// Same as `trace_to_json_file` for every thread, for tests that send from rayon or a
+// multi-threaded runtime. Only one test per binary can install it.
+#[allow(dead_code)]
+pub fn trace_to_json_file_globally(test_name: &str) -> Result<(), TestError> {
+    tracing::subscriber::set_global_default(subscriber(test_name)?)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+}
+
+// Trace of one test, started by the fixture: the subscriber of `trace_to_json_file` and an
+// "operation" span around the whole test, so that its steps and transactions are traced in it.
+// Dropping it closes the span and then removes the subscriber.
+pub struct TestTrace {
+    _operation: EnteredSpan,
+    _subscriber: DefaultGuard,
+}
+
+impl TestTrace {
+    // libtest runs every test on a thread named after it, which names the trace file. Returns
+    // `None` when the test already traces to a subscriber of its own, e.g. a global one or the
+    // one of another fixture.
+    pub fn start() -> Result<Option<Self>, TestError> {
+        if tracing::dispatcher::get_default(|dispatch| !dispatch.is::<NoSubscriber>()) {
+            return Ok(None);
+        }
+        let thread = std::thread::current();
+        let test_name = thread.name().unwrap_or("main");
+        let subscriber = trace_to_json_file(test_name)?;
+        let operation = tracing::info_span!("operation", test = test_name).entered();
+        Ok(Some(Self {
+            _operation: operation,
+            _subscriber: subscriber,
+        }))
+    }
+}
@@ -0,0 +1,146 @@
+use {
+    crate::common::error::TestError,
+    solana_program::{instruction::Instruction, pubkey::Pubkey, system_instruction},
+    spl_token_2022::{
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config, BaseStateWithExtensions,
+            ExtensionType, StateWithExtensions,
+        },
+        state::{Account, Mint},
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MintExtension {
+    TransferFee {
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    },
+    NonTransferable,
+}
+
+impl MintExtension {
+    pub fn extension_type(&self) -> ExtensionType {
+        match self {
+            MintExtension::TransferFee { .. } => ExtensionType::TransferFeeConfig,
+            MintExtension::NonTransferable => ExtensionType::NonTransferable,
+        }
+    }
+
+    // Extensions have to be initialized before the mint itself.
+    pub fn instruction(&self, mint: &Pubkey, authority: &Pubkey) -> Result<Instruction, TestError> {
+        match self {
+            MintExtension::TransferFee {
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                mint,
+                Some(authority),
+                Some(authority),
+                *transfer_fee_basis_points,
+                *maximum_fee,
+            ),
+            MintExtension::NonTransferable => {
+                spl_token_2022::instruction::initialize_non_transferable_mint(
+                    &spl_token_2022::id(),
+                    mint,
+                )
+            }
+        }
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+    }
+}
+
+pub fn get_mint_len(extensions: &[MintExtension]) -> Result<usize, TestError> {
+    let extension_types: Vec<ExtensionType> =
+        extensions.iter().map(|e| e.extension_type()).collect();
+    ExtensionType::get_account_len::<Mint>(&extension_types)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+}
+
+// The account extensions required by a mint, such as `TransferFeeAmount` for a transfer fee
+// mint, are read back from the mint so that the token account is allocated large enough.
+pub fn get_token_account_len(mint_data: &[u8]) -> Result<usize, TestError> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    let mint_extension_types = mint
+        .get_extension_types()
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    let account_extension_types =
+        ExtensionType::get_required_init_account_extensions(&mint_extension_types);
+    ExtensionType::get_account_len::<Account>(&account_extension_types)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+}
+
+pub fn create_mint_instructions(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+    extensions: &[MintExtension],
+    lamports: u64,
+) -> Result<Vec<Instruction>, TestError> {
+    let mint_len = get_mint_len(extensions)?;
+
+    let mut instructions = vec![system_instruction::create_account(
+        payer,
+        mint,
+        lamports,
+        mint_len as u64,
+        &spl_token_2022::id(),
+    )];
+    for extension in extensions {
+        instructions.push(extension.instruction(mint, mint_authority)?);
+    }
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint(
+            &spl_token_2022::id(),
+            mint,
+            mint_authority,
+            freeze_authority,
+            decimals,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+    );
+    Ok(instructions)
+}
+
+pub fn create_token_account_instructions(
+    owner: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    account_len: usize,
+    lamports: u64,
+) -> Result<Vec<Instruction>, TestError> {
+    Ok(vec![
+        system_instruction::create_account(
+            owner,
+            account,
+            lamports,
+            account_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::instruction::initialize_account(
+            &spl_token_2022::id(),
+            account,
+            mint,
+            owner,
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+    ])
+}
+
+pub fn unpack_token_account(data: &[u8]) -> Result<Account, TestError> {
+    Ok(StateWithExtensions::<Account>::unpack(data)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?
+        .base)
+}
+
+pub fn get_mint_extension_types(data: &[u8]) -> Result<Vec<ExtensionType>, TestError> {
+    StateWithExtensions::<Mint>::unpack(data)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?
+        .get_extension_types()
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))
+}
@@ -0,0 +1,139 @@
+mod common;
+use {
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::fixture::{Fixture, Listing, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+};
+
+const LARGE_ESCROW_LISTING: Listing = Listing {
+    max_renters: 8000,
+    rent_duration: 1,
+    ..LISTING
+};
+const RENT_COMPUTE_UNIT_LIMIT: u32 = 100_000;
+
+async fn setup() -> Result<State, TestError> {
+    Fixture::new(LARGE_ESCROW_LISTING).build(Stage::Lent).await
+}
+
+// Rents every slot but one to renters of their own, so that the fixture renter takes the last
+// free slot of the escrow
+async fn setup_full_escrow() -> Result<State, TestError> {
+    let mut test_state = setup().await?;
+    let prepaid_rent = LARGE_ESCROW_LISTING.prepaid_rent()?;
+
+    tracing::info!(
+        "Filling the escrow with {} renters",
+        LARGE_ESCROW_LISTING.max_renters - 1
+    );
+    let renter_keypairs: Vec<Keypair> = (1..LARGE_ESCROW_LISTING.max_renters)
+        .map(|_| Keypair::new())
+        .collect();
+    for chunk in renter_keypairs.chunks(16) {
+        test_state
+            .bench
+            .airdrop(chunk.iter().map(|k| k.pubkey()).collect())
+            .await?;
+    }
+    for renter_keypair in renter_keypairs.iter() {
+        let renter_temp_sol_token_account_keypair = Keypair::new();
+        test_state
+            .bench
+            .wrap_sol(
+                renter_keypair,
+                &renter_temp_sol_token_account_keypair,
+                prepaid_rent,
+            )
+            .await?;
+        rent_impl(
+            LARGE_ESCROW_LISTING.rent_amount,
+            LARGE_ESCROW_LISTING.rent_duration,
+            renter_keypair,
+            &renter_temp_sol_token_account_keypair.pubkey(),
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await?;
+    }
+    Ok(test_state)
+}
+
+#[tokio::test]
+async fn test_compute_budget_rent_large_escrow() -> Result<(), TestError> {
+    let mut test_state = setup_full_escrow().await?;
+
+    tracing::info!("Renting with a compute unit limit that is too low");
+    assert!(is_instruction_error(
+        &rent_with_compute_budget(
+            LARGE_ESCROW_LISTING.rent_amount,
+            LARGE_ESCROW_LISTING.rent_duration,
+            &ComputeBudget {
+                unit_limit: Some(1000),
+                ..ComputeBudget::default()
+            },
+            &mut test_state,
+        )
+        .await
+    ));
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+
+    tracing::info!("Renting within {} compute units", RENT_COMPUTE_UNIT_LIMIT);
+    rent_with_compute_budget(
+        LARGE_ESCROW_LISTING.rent_amount,
+        LARGE_ESCROW_LISTING.rent_duration,
+        &ComputeBudget {
+            unit_limit: Some(RENT_COMPUTE_UNIT_LIMIT),
+            ..ComputeBudget::default()
+        },
+        &mut test_state,
+    )
+    .await?;
+    assert_eq!(
+        test_state.find_valid_renting().await?.rent_duration,
+        LARGE_ESCROW_LISTING.rent_duration
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_compute_budget_priority_fee() -> Result<(), TestError> {
+    let mut test_state = setup().await?;
+    let renter_pubkey = test_state.renter_keypair.pubkey();
+    // One lamport per compute unit
+    let compute_budget = ComputeBudget {
+        unit_limit: Some(RENT_COMPUTE_UNIT_LIMIT),
+        unit_price: Some(1_000_000),
+        heap_size: Some(64 * 1024),
+    };
+
+    tracing::info!("Renting with a priority fee and a larger heap");
+    let renter_lamports = test_state.bench.get_lamports(&renter_pubkey).await;
+    rent_with_compute_budget(
+        LARGE_ESCROW_LISTING.rent_amount,
+        LARGE_ESCROW_LISTING.rent_duration,
+        &compute_budget,
+        &mut test_state,
+    )
+    .await?;
+    assert_eq!(
+        test_state.find_valid_renting().await?.rent_duration,
+        LARGE_ESCROW_LISTING.rent_duration
+    );
+    // The renter is the only signer, and the priority fee is the unit price, in micro-lamports,
+    // times the requested unit limit
+    let base_fee = test_state.bench.get_fee(&[], &renter_pubkey).await?;
+    let priority_fee = RENT_COMPUTE_UNIT_LIMIT as u64;
+    test_state
+        .bench
+        .assert_lamports(&renter_pubkey, renter_lamports - base_fee - priority_fee)
+        .await;
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+mod common;
+use {
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::coverage::{outcomes, report, OK},
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::state::Currency,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::error::EscrowError,
+    solana_sdk::signature::Signer,
+};
+
+#[tokio::test]
+async fn test_coverage_outcomes() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::Lent).await?;
+
+    // The compute budget instruction comes first, the failing index must still land on Rent
+    let compute_budget = ComputeBudget {
+        unit_price: Some(1),
+        ..ComputeBudget::default()
+    };
+    let rent_duration = LISTING.max_rent_duration + 1;
+    let instructions = compute_budget.prepend_to(&[rent_instruction(
+        LISTING.rent_amount,
+        rent_duration,
+        &test_state.renter_keypair.pubkey(),
+        &test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?]);
+    let result = rent_with_compute_budget(
+        LISTING.rent_amount,
+        rent_duration,
+        &compute_budget,
+        &mut test_state,
+    )
+    .await;
+    let rejected = outcomes(&instructions, &result);
+    assert_eq!(
+        rejected,
+        vec![(
+            "Rent",
+            format!("{:#x}", EscrowError::MaxRentDurationExceeded as u32)
+        )]
+    );
+
+    let result = set_fee(LISTING.fee, &mut test_state).await;
+    let set_fee_instruction = set_fee_instruction(
+        LISTING.fee,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    assert_eq!(
+        outcomes(&[set_fee_instruction], &result),
+        vec![("SetFee", OK.to_string())]
+    );
+
+    Ok(())
+}
+
+// Prints the matrix of the last `cargo test` rather than testing anything. It is a step of its
+// own, run after the suite:
+//
+//   cargo test && cargo test --test coverage -- --ignored coverage_report --nocapture
+//
+// Set RENFT_COVERAGE_RUN to the same id for both to report on that run whatever ran in between.
+#[test]
+#[ignore]
+fn coverage_report() -> Result<(), TestError> {
+    println!("Escrow instruction coverage:\n{}", report()?);
+    Ok(())
+}
@@ -0,0 +1,385 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::fee::{get_fee_split_with_currency, FeeSplit},
+    crate::common::fixture::{Fixture, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program::pubkey::Pubkey,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::signature::Signer,
+};
+
+const CURRENCIES: [Currency; 2] = [Currency::Sol, Currency::Usdc];
+
+fn other_currency(currency: Currency) -> Currency {
+    match currency {
+        Currency::Sol => Currency::Usdc,
+        Currency::Usdc => Currency::Sol,
+    }
+}
+
+// Renters hold the prepaid rent in both currencies, so that a listing in one currency can be
+// checked to leave the other untouched.
+fn fixture() -> Result<Fixture, TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    Ok(Fixture::new(LISTING)
+        .renter_sol_token_amount(prepaid_rent)
+        .renter_usdc_token_amount(prepaid_rent))
+}
+
+async fn setup_rented(currency: Currency) -> Result<(State, i64), TestError> {
+    let mut test_state = fixture()?
+        .currency(currency)
+        .payable_currencies(&CURRENCIES)
+        .build(Stage::Rented)
+        .await?;
+    let rented_at = test_state.find_valid_renting().await?.rented_at;
+    Ok((test_state, rented_at))
+}
+
+async fn get_token_amount(test_state: &mut State, address: &Pubkey) -> Result<u64, TestError> {
+    Ok(test_state.bench.get_token_account(address).await?.amount)
+}
+
+// Balances of the currency a listing is not priced in must never move.
+async fn assert_untouched(currency: Currency, test_state: &mut State) -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let other = other_currency(currency);
+
+    assert_eq!(
+        get_fee_split_with_currency(other, test_state).await?,
+        FeeSplit {
+            lender: 0,
+            admin: 0,
+            renter: 0,
+        }
+    );
+    let renter_temp_token_account_pubkey = test_state.renter_temp_token_account_pubkey(other);
+    assert_eq!(
+        get_token_amount(test_state, &renter_temp_token_account_pubkey).await?,
+        prepaid_rent
+    );
+    let pda_token_account_pubkey = test_state.pda_token_account_pubkey(other);
+    assert_eq!(
+        get_token_amount(test_state, &pda_token_account_pubkey).await?,
+        0
+    );
+    Ok(())
+}
+
+async fn assert_settled(currency: Currency, test_state: &mut State) -> Result<(), TestError> {
+    let pda_token_account_pubkey = test_state.pda_token_account_pubkey(currency);
+    assert_eq!(
+        get_token_amount(test_state, &pda_token_account_pubkey).await?,
+        0
+    );
+    let renter_temp_token_account_pubkey = test_state.renter_temp_token_account_pubkey(currency);
+    assert_eq!(
+        get_token_amount(test_state, &renter_temp_token_account_pubkey).await?,
+        0
+    );
+    assert_untouched(currency, test_state).await
+}
+
+#[tokio::test]
+async fn test_currency_fixture() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = fixture()?
+        .payable_currencies(&[])
+        .build(Stage::AdminReady)
+        .await?;
+
+    for currency in CURRENCIES.iter() {
+        let token_pubkey = test_state.token_pubkey(*currency);
+        let renter_temp_token_account = test_state
+            .bench
+            .get_token_account(&test_state.renter_temp_token_account_pubkey(*currency))
+            .await?;
+        assert_eq!(renter_temp_token_account.mint, token_pubkey);
+        assert_eq!(
+            renter_temp_token_account.owner,
+            test_state.renter_keypair.pubkey()
+        );
+        assert_eq!(renter_temp_token_account.amount, prepaid_rent);
+
+        for address in [
+            test_state.admin_token_account_pubkey(*currency),
+            test_state.lender_token_account_pubkey(*currency),
+            test_state.renter_token_account_pubkey(*currency),
+            test_state.pda_token_account_pubkey(*currency),
+        ]
+        .iter()
+        {
+            let token_account = test_state.bench.get_token_account(address).await?;
+            assert_eq!(token_account.mint, token_pubkey);
+            assert_eq!(token_account.amount, 0);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_currency_claim() -> Result<(), TestError> {
+    for currency in CURRENCIES.iter() {
+        let (mut test_state, rented_at) = setup_rented(*currency).await?;
+        test_state
+            .bench
+            .set_unix_timestamp(LISTING.expires_at(rented_at))
+            .await?;
+
+        tracing::info!("Claim rent in {:?}", currency);
+        claim_with_currency(
+            &test_state.renter_keypair.pubkey(),
+            rented_at,
+            *currency,
+            &mut test_state,
+        )
+        .await?;
+
+        assert_eq!(
+            get_fee_split_with_currency(*currency, &mut test_state).await?,
+            LISTING.fee_split()?
+        );
+        assert_settled(*currency, &mut test_state).await?;
+
+        tracing::info!("Stop lending in {:?}", currency);
+        stop_lend_with_currency(*currency, &mut test_state).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_currency_stop_rent() -> Result<(), TestError> {
+    let days_charged: u8 = 1;
+
+    for currency in CURRENCIES.iter() {
+        let (mut test_state, rented_at) = setup_rented(*currency).await?;
+        test_state
+            .bench
+            .set_unix_timestamp(rented_at + SECONDS_IN_DAY * (days_charged as i64))
+            .await?;
+
+        tracing::info!("Stop renting in {:?}", currency);
+        stop_rent_with_currency(rented_at, *currency, &mut test_state).await?;
+
+        assert_eq!(
+            get_fee_split_with_currency(*currency, &mut test_state).await?,
+            LISTING.partial_fee_split(days_charged)?
+        );
+        assert_settled(*currency, &mut test_state).await?;
+
+        tracing::info!("Stop lending in {:?}", currency);
+        stop_lend_with_currency(*currency, &mut test_state).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_currency_mismatch_rent() -> Result<(), TestError> {
+    for currency in CURRENCIES.iter() {
+        let other = other_currency(*currency);
+        let mut test_state = fixture()?
+            .payable_currencies(&CURRENCIES)
+            .build(Stage::AdminReady)
+            .await?;
+
+        tracing::info!("Start lending in {:?}", currency);
+        LISTING.lend(*currency, &mut test_state).await?;
+
+        tracing::info!(
+            "Renting with {:?} into the {:?} PDA account",
+            other,
+            currency
+        );
+        assert!(is_instruction_error(
+            &rent_impl(
+                LISTING.rent_amount,
+                LISTING.rent_duration,
+                &clone_keypair(&test_state.renter_keypair),
+                &test_state.renter_temp_token_account_pubkey(other),
+                &test_state.pda_token_account_pubkey(*currency),
+                &test_state.escrow_state_account_keypair.pubkey(),
+                &mut test_state,
+            )
+            .await
+        ));
+
+        tracing::info!("Renting with {:?} into the {:?} PDA account", other, other);
+        assert!(is_instruction_error(
+            &rent_with_currency(
+                LISTING.rent_amount,
+                LISTING.rent_duration,
+                other,
+                &mut test_state
+            )
+            .await
+        ));
+
+        assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+        assert_untouched(*currency, &mut test_state).await?;
+
+        tracing::info!("Start renting in {:?}", currency);
+        rent_with_currency(
+            LISTING.rent_amount,
+            LISTING.rent_duration,
+            *currency,
+            &mut test_state,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_currency_mismatch_payable_account() -> Result<(), TestError> {
+    let (mut test_state, rented_at) = setup_rented(Currency::Usdc).await?;
+
+    tracing::info!("Stop renting with SOL payable accounts");
+    test_state
+        .bench
+        .set_unix_timestamp(rented_at + SECONDS_IN_DAY)
+        .await?;
+    assert!(is_instruction_error(
+        &stop_rent_impl(
+            rented_at,
+            &clone_keypair(&test_state.renter_keypair),
+            &test_state.pda_token_account_pubkey(Currency::Usdc),
+            &test_state.renter_token_account_pubkey(Currency::Usdc),
+            &test_state.lender_token_account_pubkey(Currency::Usdc),
+            &test_state.admin_token_account_pubkey(Currency::Sol),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await
+    ));
+    assert!(is_instruction_error(
+        &stop_rent_impl(
+            rented_at,
+            &clone_keypair(&test_state.renter_keypair),
+            &test_state.pda_token_account_pubkey(Currency::Usdc),
+            &test_state.renter_token_account_pubkey(Currency::Sol),
+            &test_state.lender_token_account_pubkey(Currency::Usdc),
+            &test_state.admin_token_account_pubkey(Currency::Usdc),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await
+    ));
+
+    tracing::info!("Claim rent with SOL payable accounts");
+    test_state
+        .bench
+        .set_unix_timestamp(LISTING.expires_at(rented_at))
+        .await?;
+    assert!(is_instruction_error(
+        &claim_impl(
+            &test_state.renter_keypair.pubkey(),
+            rented_at,
+            &clone_keypair(&test_state.lender_keypair),
+            &test_state.pda_token_account_pubkey(Currency::Usdc),
+            &test_state.lender_token_account_pubkey(Currency::Usdc),
+            &test_state.admin_token_account_pubkey(Currency::Sol),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await
+    ));
+    assert!(is_instruction_error(
+        &claim_impl(
+            &test_state.renter_keypair.pubkey(),
+            rented_at,
+            &clone_keypair(&test_state.lender_keypair),
+            &test_state.pda_token_account_pubkey(Currency::Usdc),
+            &test_state.lender_token_account_pubkey(Currency::Sol),
+            &test_state.admin_token_account_pubkey(Currency::Usdc),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await
+    ));
+
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let pda_usdc_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Usdc);
+    assert_eq!(
+        get_token_amount(&mut test_state, &pda_usdc_token_account_pubkey).await?,
+        prepaid_rent
+    );
+    assert_untouched(Currency::Usdc, &mut test_state).await?;
+
+    tracing::info!("Claim rent in USDC");
+    claim_with_currency(
+        &test_state.renter_keypair.pubkey(),
+        rented_at,
+        Currency::Usdc,
+        &mut test_state,
+    )
+    .await?;
+    assert_eq!(
+        get_fee_split_with_currency(Currency::Usdc, &mut test_state).await?,
+        LISTING.fee_split()?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_currency_unregistered_payable_account() -> Result<(), TestError> {
+    let mut test_state = fixture()?.build(Stage::AdminReady).await?;
+
+    tracing::info!("Start lending in USDC");
+    LISTING.lend(Currency::Usdc, &mut test_state).await?;
+    tracing::info!("Start renting in USDC");
+    let rented_at = LISTING
+        .rent(Currency::Usdc, &mut test_state)
+        .await?
+        .rented_at;
+    test_state
+        .bench
+        .set_unix_timestamp(LISTING.expires_at(rented_at))
+        .await?;
+
+    tracing::info!("Claim rent before the USDC payable account is set");
+    assert!(is_instruction_error(
+        &claim_with_currency(
+            &test_state.renter_keypair.pubkey(),
+            rented_at,
+            Currency::Usdc,
+            &mut test_state,
+        )
+        .await
+    ));
+
+    tracing::info!("Set payable account for USDC");
+    set_payable_account(
+        test_state.admin_token_account_pubkey(Currency::Usdc),
+        &mut test_state,
+    )
+    .await?;
+    test_state.bench.next_slot().await?;
+    tracing::info!("Claim rent in USDC");
+    claim_with_currency(
+        &test_state.renter_keypair.pubkey(),
+        rented_at,
+        Currency::Usdc,
+        &mut test_state,
+    )
+    .await?;
+    assert_eq!(
+        get_fee_split_with_currency(Currency::Usdc, &mut test_state).await?,
+        LISTING.fee_split()?
+    );
+    assert_settled(Currency::Usdc, &mut test_state).await?;
+
+    Ok(())
+}
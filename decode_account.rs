@@ -0,0 +1,117 @@
+mod common;
+use {
+    crate::common::decode::{decode_account, format_timestamp},
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::state::Currency,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{map::Bucket, state::Escrow},
+    solana_sdk::signature::Signer,
+};
+
+const START_TIMESTAMP: i64 = 1_700_000_000;
+
+#[tokio::test]
+async fn test_decode_account_fixture() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::Lent).await?;
+    test_state.bench.set_unix_timestamp(START_TIMESTAMP).await?;
+    let renting = LISTING.rent(Currency::Sol, &mut test_state).await?;
+    assert_eq!(renting.rented_at, START_TIMESTAMP);
+    let sol_token_pubkey = test_state.token_pubkey(Currency::Sol);
+    let usdc_token_pubkey = test_state.token_pubkey(Currency::Usdc);
+
+    tracing::info!("Decoding the admin state account");
+    let admin_state_account = test_state
+        .bench
+        .get_account(&test_state.admin_state_account_keypair.pubkey())
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    let decoded = decode_account(
+        &admin_state_account.data,
+        &[sol_token_pubkey, usdc_token_pubkey],
+    )?;
+    println!("{}", decoded);
+    assert!(decoded.contains("  is_initialized: true\n"));
+    assert!(decoded.contains(&format!("  fee: {} bps\n", LISTING.fee)));
+    assert!(decoded.contains(&format!(
+        "  payable account for {}: {}\n",
+        sol_token_pubkey,
+        test_state.admin_token_account_pubkey(Currency::Sol)
+    )));
+    assert!(decoded.contains(&format!(
+        "  payable account for {}: none\n",
+        usdc_token_pubkey
+    )));
+
+    tracing::info!("Decoding the escrow state account");
+    let escrow_state_account = test_state
+        .bench
+        .get_account(&test_state.escrow_state_account_keypair.pubkey())
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    let decoded = decode_account(&escrow_state_account.data, &[])?;
+    println!("{}", decoded);
+    for line in [
+        format!("  lender: {}", test_state.lender_keypair.pubkey()),
+        format!(
+            "  lender_token_account: {}",
+            test_state.lender_token_account_pubkey(Currency::Sol)
+        ),
+        format!(
+            "  pda_token_account: {}",
+            test_state.pda_token_account_pubkey(Currency::Sol)
+        ),
+        format!("  daily_rent_price: {}", LISTING.daily_rent_price),
+        "  current_renters: 1".to_string(),
+        format!("    renter: {}", test_state.renter_keypair.pubkey()),
+        format!("    rented_at: 2023-11-14T22:13:20Z ({})", START_TIMESTAMP),
+        format!("    duration: {} days", LISTING.rent_duration),
+        "    ends_at: 2023-11-17T22:13:20Z".to_string(),
+        format!("    amount: {}", LISTING.rent_amount),
+    ]
+    .iter()
+    {
+        assert!(decoded.contains(&format!("{}\n", line)), "{}", line);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_account_garbage() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::Lent).await?;
+    let escrow_state_account = test_state
+        .bench
+        .get_account(&test_state.escrow_state_account_keypair.pubkey())
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    let data = escrow_state_account.data;
+    decode_account(&data, &[])?;
+
+    tracing::info!("Decoding data shorter than an escrow state account");
+    assert!(decode_account(&[], &[]).is_err());
+    assert!(decode_account(&data[..Escrow::LEN - 1], &[]).is_err());
+    assert!(decode_account(&data[..data.len() - 1], &[]).is_err());
+
+    // Whole buckets, but more than max_renters needs
+    tracing::info!("Decoding an escrow state account with a bucket too many");
+    let mut longer = data.clone();
+    longer.extend_from_slice(&[0; Bucket::BUCKET_BUFFER_LEN]);
+    assert!(decode_account(&longer, &[]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_decode_account_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(format_timestamp(-1), "1969-12-31T23:59:59Z");
+    assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+    assert_eq!(
+        format_timestamp(START_TIMESTAMP + SECONDS_IN_DAY),
+        "2023-11-15T22:13:20Z"
+    );
+    // Any i64 is formatted, however far from the present
+    assert!(format_timestamp(i64::MAX).ends_with("T15:30:07Z"));
+    assert!(format_timestamp(i64::MIN).ends_with("T08:29:52Z"));
+}
@@ -0,0 +1,236 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::TestError,
+    crate::common::fee::{get_fee_split, get_pda_balance, FeeSplit},
+    crate::common::fixture::{Fixture, Listing, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::signature::Signer,
+};
+
+// A renting expires at `rented_at + rent_duration * SECONDS_IN_DAY`. Before that instant only
+// the renter may stop the rent, from that instant on only the lender may claim it.
+#[derive(Clone, Copy, Debug)]
+enum Boundary {
+    BeforeExpiry,
+    AtExpiry,
+    AfterExpiry,
+    LongAfterExpiry,
+}
+
+impl Boundary {
+    fn offset(self) -> i64 {
+        match self {
+            Boundary::BeforeExpiry => -1,
+            Boundary::AtExpiry => 0,
+            Boundary::AfterExpiry => 1,
+            Boundary::LongAfterExpiry => 30 * SECONDS_IN_DAY,
+        }
+    }
+
+    fn is_expired(self) -> bool {
+        self.offset() >= 0
+    }
+}
+
+async fn rent_and_set_clock(
+    listing: Listing,
+    boundary: Boundary,
+) -> Result<(State, Renting), TestError> {
+    let mut test_state = Fixture::new(listing).build(Stage::Lent).await?;
+    let test_renting = listing.rent(Currency::Sol, &mut test_state).await?;
+    assert_eq!(
+        test_renting.renter_address,
+        test_state.renter_keypair.pubkey()
+    );
+
+    test_state
+        .bench
+        .set_unix_timestamp(listing.expires_at(test_renting.rented_at) + boundary.offset())
+        .await?;
+
+    Ok((test_state, test_renting))
+}
+
+// Every `rent_duration` the default listing allows
+fn listings() -> Vec<Listing> {
+    (1..=LISTING.max_rent_duration)
+        .map(|rent_duration| Listing {
+            rent_duration,
+            ..LISTING
+        })
+        .collect()
+}
+
+// The longest renting the instruction can express, where `rent_duration * SECONDS_IN_DAY` is
+// largest
+const MAX_DURATION_LISTING: Listing = Listing {
+    daily_rent_price: 1,
+    max_rent_duration: u8::MAX,
+    rent_duration: u8::MAX,
+    ..LISTING
+};
+
+async fn check_boundary(listing: Listing, boundary: Boundary) -> Result<(), TestError> {
+    println!(
+        "Checking rent_duration {} at {:?}",
+        listing.rent_duration, boundary
+    );
+    let (mut test_state, test_renting) = rent_and_set_clock(listing, boundary).await?;
+    let prepaid = listing.prepaid_rent()?;
+
+    if boundary.is_expired() {
+        assert!(
+            stop_rent(test_renting.rented_at, &mut test_state)
+                .await
+                .is_err(),
+            "renter stopped an expired rent ({} days, {:?})",
+            listing.rent_duration,
+            boundary
+        );
+    } else {
+        assert!(
+            claim(
+                &test_state.renter_keypair.pubkey(),
+                test_renting.rented_at,
+                &mut test_state,
+            )
+            .await
+            .is_err(),
+            "lender claimed an active rent ({} days, {:?})",
+            listing.rent_duration,
+            boundary
+        );
+    }
+
+    assert_eq!(get_pda_balance(&mut test_state).await?, prepaid);
+    assert_eq!(
+        get_fee_split(&mut test_state).await?,
+        FeeSplit {
+            lender: 0,
+            admin: 0,
+            renter: 0
+        }
+    );
+    assert!(test_state.find_valid_renting().await? == test_renting);
+
+    let expected = if boundary.is_expired() {
+        claim(
+            &test_state.renter_keypair.pubkey(),
+            test_renting.rented_at,
+            &mut test_state,
+        )
+        .await?;
+        listing.fee_split()?
+    } else {
+        stop_rent(test_renting.rented_at, &mut test_state).await?;
+        listing.partial_fee_split(listing.rent_duration - 1)?
+    };
+
+    let fee_split = get_fee_split(&mut test_state).await?;
+    assert_eq!(get_pda_balance(&mut test_state).await?, 0);
+    assert_eq!(fee_split, expected);
+    assert_eq!(fee_split.total(), prepaid);
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+
+    println!("Stop lending");
+    stop_lend(&mut test_state).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiry_before() -> Result<(), TestError> {
+    for listing in listings() {
+        check_boundary(listing, Boundary::BeforeExpiry).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiry_at() -> Result<(), TestError> {
+    for listing in listings() {
+        check_boundary(listing, Boundary::AtExpiry).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiry_after() -> Result<(), TestError> {
+    for listing in listings() {
+        check_boundary(listing, Boundary::AfterExpiry).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiry_long_after() -> Result<(), TestError> {
+    for listing in listings() {
+        check_boundary(listing, Boundary::LongAfterExpiry).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiry_max_rent_duration() -> Result<(), TestError> {
+    for boundary in [
+        Boundary::BeforeExpiry,
+        Boundary::AtExpiry,
+        Boundary::AfterExpiry,
+        Boundary::LongAfterExpiry,
+    ] {
+        check_boundary(MAX_DURATION_LISTING, boundary).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiry_wrong_party() -> Result<(), TestError> {
+    let (mut test_state, test_renting) = rent_and_set_clock(LISTING, Boundary::AfterExpiry).await?;
+
+    println!("Renter claims own expired rent");
+    assert!(claim_impl(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &clone_keypair(&test_state.renter_keypair),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.renter_token_account_pubkey(Currency::Sol),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &mut test_state,
+    )
+    .await
+    .is_err());
+
+    test_state
+        .bench
+        .set_unix_timestamp(test_renting.rented_at + 1)
+        .await?;
+
+    println!("Lender stops an active rent");
+    assert!(stop_rent_impl(
+        test_renting.rented_at,
+        &clone_keypair(&test_state.lender_keypair),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.renter_token_account_pubkey(Currency::Sol),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &mut test_state,
+    )
+    .await
+    .is_err());
+
+    assert_eq!(
+        get_pda_balance(&mut test_state).await?,
+        LISTING.prepaid_rent()?
+    );
+    assert!(test_state.find_valid_renting().await? == test_renting);
+
+    Ok(())
+}
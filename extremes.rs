@@ -0,0 +1,218 @@
+mod common;
+use {
+    crate::common::error::{test_error_to_program_error, TestError},
+    crate::common::fee::{calculate_fee_split, get_fee_split, get_pda_balance},
+    crate::common::fixture::{Fixture, Listing, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    crate::common::{calculate_escrow_state_account_len, clone_keypair},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{error::EscrowError, renting::Renting, state::Escrow},
+    solana_sdk::signature::Signer,
+    spl_token::error::TokenError,
+};
+
+async fn setup(max_renters: u32, renter_sol_token_amount: u64) -> Result<State, TestError> {
+    Fixture::new(Listing {
+        max_renters,
+        ..LISTING
+    })
+    .renter_sol_token_amount(renter_sol_token_amount)
+    .build(Stage::AdminReady)
+    .await
+}
+
+async fn edit_sol_lend(
+    daily_rent_price: u64,
+    max_rent_duration: u8,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    edit_lend_impl(
+        daily_rent_price,
+        max_rent_duration,
+        &clone_keypair(&test_state.lender_keypair),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        test_state,
+    )
+    .await
+}
+
+async fn assert_no_rentings(test_state: &mut State) -> Result<(), TestError> {
+    let account = test_state
+        .bench
+        .get_account(&test_state.escrow_state_account_keypair.pubkey())
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    let mut data = account.data.clone();
+    let mut escrow_info = Escrow::new();
+
+    let escrow_info = Escrow::unpack(&mut data, &mut escrow_info)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+
+    assert_eq!(escrow_info.current_renters, 0);
+    assert_eq!(escrow_info.rentings.length, 0);
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+    assert_eq!(get_pda_balance(test_state).await?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_extremes_escrow_state_account_len() -> Result<(), TestError> {
+    assert!(calculate_escrow_state_account_len(1)? > Escrow::LEN);
+    assert!(calculate_escrow_state_account_len(8000)? > calculate_escrow_state_account_len(1)?);
+    assert!(calculate_escrow_state_account_len(u32::MAX).is_err());
+    assert!(calculate_escrow_state_account_len(u32::MAX / 2).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extremes_max_renters() -> Result<(), TestError> {
+    let mut test_state = setup(1, 2000).await?;
+
+    tracing::info!("Creating escrow state account for u32::MAX renters");
+    assert!(test_state
+        .bench
+        .create_escrow_state_account(
+            u32::MAX,
+            &clone_keypair(&test_state.lender_keypair),
+            test_state.program_id,
+        )
+        .await
+        .is_err());
+
+    tracing::info!("Start lending with u32::MAX renters");
+    // The escrow state account was sized for a single renter
+    assert_eq!(
+        test_error_to_program_error(lend(2000, u32::MAX, 3, &mut test_state).await),
+        EscrowError::EscrowLengthMismatch.into()
+    );
+
+    lend(2000, 1, 3, &mut test_state).await?;
+    assert_no_rentings(&mut test_state).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extremes_daily_rent_price() -> Result<(), TestError> {
+    let max_renters: u32 = 2;
+    let mut test_state = setup(max_renters, 2000).await?;
+
+    tracing::info!("Start lending at u64::MAX");
+    lend(u64::MAX, max_renters, 3, &mut test_state).await?;
+
+    tracing::info!("Renting with an overflowing rent amount");
+    assert_eq!(
+        test_error_to_program_error(rent(2, 1, &mut test_state).await),
+        EscrowError::AmountOverflow.into()
+    );
+
+    tracing::info!("Renting with an overflowing rent duration");
+    assert_eq!(
+        test_error_to_program_error(rent(1, 2, &mut test_state).await),
+        EscrowError::AmountOverflow.into()
+    );
+
+    tracing::info!("Renting without enough funds");
+    // Refused by the token program when the prepaid rent is transferred
+    assert_eq!(
+        test_error_to_program_error(rent(1, 1, &mut test_state).await),
+        TokenError::InsufficientFunds.into()
+    );
+
+    assert_no_rentings(&mut test_state).await?;
+
+    tracing::info!("Edit lending to u64::MAX - 1");
+    edit_sol_lend(u64::MAX - 1, 255, &mut test_state).await?;
+    assert_eq!(
+        test_error_to_program_error(rent(2, 255, &mut test_state).await),
+        EscrowError::AmountOverflow.into()
+    );
+    assert_eq!(
+        test_error_to_program_error(rent(1, 255, &mut test_state).await),
+        EscrowError::AmountOverflow.into()
+    );
+
+    assert_no_rentings(&mut test_state).await?;
+
+    tracing::info!("Edit lending back to an affordable price");
+    edit_sol_lend(1000, 3, &mut test_state).await?;
+    test_state.bench.next_slot().await?;
+    rent(1, 2, &mut test_state).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extremes_rent_amount() -> Result<(), TestError> {
+    let max_renters: u32 = 1;
+    let mut test_state = setup(max_renters, 2000).await?;
+
+    lend(1, max_renters, 3, &mut test_state).await?;
+
+    tracing::info!("Renting u16::MAX tokens");
+    assert_eq!(
+        test_error_to_program_error(rent(u16::MAX, 1, &mut test_state).await),
+        EscrowError::MaxRentersExceeded.into()
+    );
+
+    assert_no_rentings(&mut test_state).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extremes_rent_duration() -> Result<(), TestError> {
+    let daily_rent_price: u64 = 1;
+    let max_renters: u32 = 1;
+    let rent_amount: u16 = 1;
+
+    let renter_sol_token_amount = daily_rent_price * (u8::MAX as u64);
+    let mut test_state = setup(max_renters, renter_sol_token_amount).await?;
+
+    tracing::info!("Start lending with a max rent duration of 3");
+    lend(daily_rent_price, max_renters, 3, &mut test_state).await?;
+
+    tracing::info!("Renting for 255 days");
+    assert_eq!(
+        test_error_to_program_error(rent(rent_amount, u8::MAX, &mut test_state).await),
+        EscrowError::MaxRentDurationExceeded.into()
+    );
+
+    assert_no_rentings(&mut test_state).await?;
+
+    tracing::info!("Edit lending to a max rent duration of 255");
+    edit_sol_lend(daily_rent_price, u8::MAX, &mut test_state).await?;
+    test_state.bench.next_slot().await?;
+
+    tracing::info!("Renting for 255 days");
+    rent(rent_amount, u8::MAX, &mut test_state).await?;
+
+    let test_renting = test_state.find_valid_renting().await?;
+    assert_eq!(test_renting.rent_duration, u8::MAX);
+
+    test_state
+        .bench
+        .set_unix_timestamp(test_renting.rented_at + SECONDS_IN_DAY * (u8::MAX as i64))
+        .await?;
+
+    tracing::info!("Claim rent");
+    claim(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &mut test_state,
+    )
+    .await?;
+
+    assert_eq!(
+        get_fee_split(&mut test_state).await?,
+        calculate_fee_split(daily_rent_price, rent_amount, u8::MAX, LISTING.fee)?
+    );
+    assert_no_rentings(&mut test_state).await?;
+
+    Ok(())
+}
@@ -0,0 +1,294 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::TestError,
+    crate::common::fee::{
+        calculate_fee_split, calculate_partial_fee_split, get_fee_split, get_pda_balance, FeeSplit,
+        MAX_FEE_BPS,
+    },
+    crate::common::fixture::{Fixture, Listing, Stage, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program_test::tokio,
+    solana_sdk::signature::Signer,
+};
+
+const AWKWARD_PRICES: [u64; 7] = [1, 3, 7, 333, 1001, 9999, 123457];
+const AWKWARD_FEES: [u32; 6] = [1, 3, 333, 3333, 6667, 9999];
+
+// A listing of `rent_amount` tokens rented in full for its whole `rent_duration`.
+fn listing(daily_rent_price: u64, rent_amount: u16, rent_duration: u8, fee: u32) -> Listing {
+    Listing {
+        daily_rent_price,
+        max_renters: rent_amount as u32,
+        max_rent_duration: rent_duration,
+        rent_amount,
+        rent_duration,
+        fee,
+    }
+}
+
+async fn lend_and_rent(listing: Listing) -> Result<(State, i64), TestError> {
+    // The admin state starts without a fee so that the fee under test is the one set by SetFee
+    let mut test_state = Fixture::new(Listing { fee: 0, ..listing })
+        .build(Stage::AdminReady)
+        .await?;
+    set_fee(listing.fee, &mut test_state).await?;
+    listing.lend(Currency::Sol, &mut test_state).await?;
+    let test_renting = listing.rent(Currency::Sol, &mut test_state).await?;
+    Ok((test_state, test_renting.rented_at))
+}
+
+async fn assert_settled(
+    test_state: &mut State,
+    expected: FeeSplit,
+    prepaid: u64,
+) -> Result<(), TestError> {
+    let fee_split = get_fee_split(test_state).await?;
+
+    assert_eq!(get_pda_balance(test_state).await?, 0, "dust left in PDA");
+    assert_eq!(fee_split, expected);
+    assert_eq!(fee_split.total(), prepaid);
+    Ok(())
+}
+
+async fn check_claim(listing: Listing) -> Result<(), TestError> {
+    tracing::info!(
+        "Claim: price {} amount {} duration {} fee {}",
+        listing.daily_rent_price,
+        listing.rent_amount,
+        listing.rent_duration,
+        listing.fee
+    );
+    let (mut test_state, rented_at) = lend_and_rent(listing).await?;
+
+    test_state
+        .bench
+        .set_unix_timestamp(listing.expires_at(rented_at))
+        .await?;
+    claim(
+        &test_state.renter_keypair.pubkey(),
+        rented_at,
+        &mut test_state,
+    )
+    .await?;
+
+    assert_settled(
+        &mut test_state,
+        listing.fee_split()?,
+        listing.prepaid_rent()?,
+    )
+    .await
+}
+
+async fn check_stop_rent(listing: Listing, days_charged: u8) -> Result<(), TestError> {
+    tracing::info!(
+        "Stop rent: price {} amount {} duration {} charged {} fee {}",
+        listing.daily_rent_price,
+        listing.rent_amount,
+        listing.rent_duration,
+        days_charged,
+        listing.fee
+    );
+    let (mut test_state, rented_at) = lend_and_rent(listing).await?;
+
+    test_state
+        .bench
+        .set_unix_timestamp(rented_at + SECONDS_IN_DAY * (days_charged as i64))
+        .await?;
+    stop_rent(rented_at, &mut test_state).await?;
+
+    assert_settled(
+        &mut test_state,
+        listing.partial_fee_split(days_charged)?,
+        listing.prepaid_rent()?,
+    )
+    .await
+}
+
+// Derives the split from the definition rather than from the helper's formula: the charged rent
+// is summed day by day and the admin fee is the largest whole amount that is at most `fee_bps`
+// ten-thousandths of it.
+fn assert_oracle(
+    daily_rent_price: u64,
+    rent_amount: u16,
+    rent_duration: u8,
+    days_charged: u8,
+    fee_bps: u32,
+) -> Result<(), TestError> {
+    let fee_split = calculate_partial_fee_split(
+        daily_rent_price,
+        rent_amount,
+        rent_duration,
+        days_charged,
+        fee_bps,
+    )?;
+    let daily_rent = (daily_rent_price as u128) * (rent_amount as u128);
+    let charged = (0..days_charged).fold(0, |charged, _| charged + daily_rent);
+    let prepaid = (0..rent_duration).fold(0, |prepaid, _| prepaid + daily_rent);
+    let admin = fee_split.admin as u128;
+    let fee_bps = fee_bps as u128;
+    let max_fee_bps = MAX_FEE_BPS as u128;
+
+    assert!(
+        admin * max_fee_bps <= charged * fee_bps,
+        "admin fee rounded up"
+    );
+    assert!(
+        (admin + 1) * max_fee_bps > charged * fee_bps,
+        "admin fee short by a whole lamport"
+    );
+    assert_eq!(fee_split.lender as u128, charged - admin);
+    assert_eq!(fee_split.renter as u128, prepaid - charged);
+    Ok(())
+}
+
+#[test]
+fn test_fee_split_oracle() -> Result<(), TestError> {
+    // (daily rent price, rent amount, rent duration, days charged, fee) and the split worked out
+    // by hand. Pairs straddle a rounding boundary of the admin fee.
+    let cases: [((u64, u16, u8, u8, u32), (u64, u64, u64)); 14] = [
+        ((2000, 1, 1, 1, 1000), (1800, 200, 0)),
+        ((2000, 1, 2, 1, 1000), (1800, 200, 2000)),
+        ((2000, 1, 1, 1, 0), (2000, 0, 0)),
+        ((2000, 1, 1, 1, MAX_FEE_BPS), (0, 2000, 0)),
+        ((7, 1, 1, 1, 3333), (5, 2, 0)),
+        ((1, 1, 1, 1, 9999), (1, 0, 0)),
+        ((1, 1, 1, 1, MAX_FEE_BPS), (0, 1, 0)),
+        ((9999, 1, 1, 1, 1), (9999, 0, 0)),
+        ((10000, 1, 1, 1, 1), (9999, 1, 0)),
+        ((4999, 1, 2, 1, 2), (4999, 0, 4999)),
+        ((5000, 1, 2, 1, 2), (4999, 1, 5000)),
+        ((3, 1, 1, 1, 3333), (3, 0, 0)),
+        ((3, 1, 1, 1, 3334), (2, 1, 0)),
+        ((3, 3, 3, 2, 5000), (9, 9, 9)),
+    ];
+    for ((daily_rent_price, rent_amount, rent_duration, days_charged, fee), split) in
+        cases.iter().copied()
+    {
+        let (lender, admin, renter) = split;
+        assert_eq!(
+            calculate_partial_fee_split(
+                daily_rent_price,
+                rent_amount,
+                rent_duration,
+                days_charged,
+                fee
+            )?,
+            FeeSplit {
+                lender,
+                admin,
+                renter
+            }
+        );
+        assert_oracle(
+            daily_rent_price,
+            rent_amount,
+            rent_duration,
+            days_charged,
+            fee,
+        )?;
+    }
+    assert!(calculate_fee_split(2000, 1, 1, MAX_FEE_BPS + 1).is_err());
+    assert!(calculate_partial_fee_split(2000, 1, 1, 2, 1000).is_err());
+    assert!(calculate_fee_split(u64::MAX, 2, 1, 1000).is_err());
+
+    for fee in 0..=MAX_FEE_BPS {
+        for daily_rent_price in AWKWARD_PRICES {
+            for days_charged in 0..=3 {
+                assert_oracle(daily_rent_price, 3, 3, days_charged, fee)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Lends `listing` once and then, for every fee from 0 to MAX_FEE_BPS, sets the fee, rents the
+// listing, settles it after `days_charged` days (a claim when that is the whole duration) and
+// checks what each party received. One fixture is reused so that the sweep stays affordable.
+async fn sweep_fees(listing: Listing, days_charged: u8) -> Result<(), TestError> {
+    let (mut test_state, mut rented_at) = lend_and_rent(Listing { fee: 0, ..listing }).await?;
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let renter_temp_sol_token_account_pubkey =
+        test_state.renter_temp_token_account_pubkey(Currency::Sol);
+    let prepaid = listing.prepaid_rent()?;
+
+    for fee in 0..=MAX_FEE_BPS {
+        let listing = Listing { fee, ..listing };
+        if fee > 0 {
+            set_fee(fee, &mut test_state).await?;
+            test_state
+                .bench
+                .top_up_wrapped_sol(
+                    &renter_keypair,
+                    &renter_temp_sol_token_account_pubkey,
+                    prepaid,
+                )
+                .await?;
+            rented_at = listing
+                .rent(Currency::Sol, &mut test_state)
+                .await?
+                .rented_at;
+        }
+
+        let before = get_fee_split(&mut test_state).await?;
+        test_state
+            .bench
+            .set_unix_timestamp(rented_at + SECONDS_IN_DAY * (days_charged as i64))
+            .await?;
+        if days_charged == listing.rent_duration {
+            claim(&renter_keypair.pubkey(), rented_at, &mut test_state).await?;
+        } else {
+            stop_rent(rented_at, &mut test_state).await?;
+        }
+        let after = get_fee_split(&mut test_state).await?;
+
+        assert_eq!(
+            get_pda_balance(&mut test_state).await?,
+            0,
+            "dust left in PDA"
+        );
+        assert_eq!(
+            FeeSplit {
+                lender: after.lender - before.lender,
+                admin: after.admin - before.admin,
+                renter: after.renter - before.renter,
+            },
+            listing.partial_fee_split(days_charged)?,
+            "fee {}",
+            fee
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fee_sweep_claim() -> Result<(), TestError> {
+    // A price that does not divide evenly by most fees, so that every fee rounds differently
+    sweep_fees(listing(9999, 1, 1, 0), 1).await
+}
+
+#[tokio::test]
+async fn test_fee_sweep_stop_rent() -> Result<(), TestError> {
+    sweep_fees(listing(9999, 1, 3, 0), 1).await
+}
+
+#[tokio::test]
+async fn test_fee_awkward_prices_claim() -> Result<(), TestError> {
+    for daily_rent_price in AWKWARD_PRICES {
+        for fee in AWKWARD_FEES {
+            check_claim(listing(daily_rent_price, 3, 3, fee)).await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fee_awkward_prices_stop_rent() -> Result<(), TestError> {
+    for daily_rent_price in AWKWARD_PRICES {
+        for fee in AWKWARD_FEES {
+            check_stop_rent(listing(daily_rent_price, 3, 3, fee), 2).await?;
+        }
+    }
+    Ok(())
+}
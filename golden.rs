@@ -0,0 +1,50 @@
+mod common;
+use {
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Listing, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::golden::Golden,
+    crate::common::renft::*,
+    crate::common::state::Currency,
+    solana_program_test::tokio,
+};
+
+// Rentals are stamped with the clock, which starts at the time the test runs
+const START_TIMESTAMP: i64 = 1_700_000_000;
+
+const GOLDEN_LISTING: Listing = Listing {
+    rent_duration: 2,
+    ..LISTING
+};
+
+#[tokio::test]
+async fn test_golden_rent_lifecycle() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(GOLDEN_LISTING).build(Stage::Created).await?;
+    test_state.bench.set_unix_timestamp(START_TIMESTAMP).await?;
+    let mut golden = Golden::new("rent_lifecycle");
+
+    initialize_admin_state(GOLDEN_LISTING.fee, &mut test_state).await?;
+    golden
+        .snapshot("initialize_admin_state", &mut test_state)
+        .await?;
+
+    set_payable_account(
+        test_state.admin_token_account_pubkey(Currency::Sol),
+        &mut test_state,
+    )
+    .await?;
+    golden
+        .snapshot("set_payable_account", &mut test_state)
+        .await?;
+
+    GOLDEN_LISTING.lend(Currency::Sol, &mut test_state).await?;
+    golden.snapshot("lend", &mut test_state).await?;
+
+    GOLDEN_LISTING.rent(Currency::Sol, &mut test_state).await?;
+    golden.snapshot("rent", &mut test_state).await?;
+
+    test_state.bench.advance_seconds(SECONDS_IN_DAY).await?;
+    stop_rent(START_TIMESTAMP, &mut test_state).await?;
+    golden.snapshot("stop_rent", &mut test_state).await?;
+
+    golden.check()
+}
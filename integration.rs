@@ -1,11 +1,16 @@
 mod common;
 use {
     crate::common::clone_keypair,
+    crate::common::compute_budget::ComputeBudget,
     crate::common::error::TestError,
+    crate::common::labels,
+    crate::common::progress::{BarProgress, ProgressEvent, RecordingProgress},
+    crate::common::rpc_client_utils::SendConfig,
     crate::common::rpc_renft::{
         rpc_initialize_admin_state, rpc_lend, rpc_rent_impl, rpc_set_payable_account,
     },
     crate::common::rpc_state::RpcState,
+    crate::common::telemetry::{trace_file_path, trace_to_json_file_globally},
     rayon::prelude::*,
     solana_renft_collateral_free::admin,
     solana_sdk::signature::Signer,
@@ -13,9 +18,45 @@ use {
     std::sync::{Arc, Mutex},
 };
 
+const PRIORITY_FEE_VAR: &str = "RENFT_PRIORITY_FEE";
+const PROGRESS_BAR_VAR: &str = "RENFT_PROGRESS_BAR";
+// Micro-lamports per compute unit
+const DEFAULT_UNIT_PRICE: u64 = 1000;
+// Enough for a rent on a full escrow of 8000 renters, see compute_budget.rs
+const RENT_COMPUTE_UNIT_LIMIT: u32 = 100_000;
+
+// Rentals compete for the same escrow account, so they bid for priority like a busy client
+// would. RENFT_PRIORITY_FEE sets the unit price in micro-lamports, 0 sends them without one.
+fn rent_compute_budget() -> Result<ComputeBudget, TestError> {
+    let unit_price = match std::env::var(PRIORITY_FEE_VAR) {
+        Ok(unit_price) => unit_price
+            .parse()
+            .map_err(|e| TestError::UnexpectedError(Box::new(e)))?,
+        Err(_) => DEFAULT_UNIT_PRICE,
+    };
+    if unit_price == 0 {
+        return Ok(ComputeBudget::default());
+    }
+    Ok(ComputeBudget {
+        unit_limit: Some(RENT_COMPUTE_UNIT_LIMIT),
+        unit_price: Some(unit_price),
+        ..ComputeBudget::default()
+    })
+}
+
+// The unit price times the requested unit limit, rounded up to a whole lamport
+fn priority_fee(compute_budget: &ComputeBudget) -> u64 {
+    let unit_price = compute_budget.unit_price.unwrap_or_default() as u128;
+    let unit_limit = compute_budget.unit_limit.unwrap_or_default() as u128;
+    ((unit_price * unit_limit + 999_999) / 1_000_000) as u64
+}
+
 #[test]
 #[cfg_attr(tarpaulin, ignore)]
 fn test_integration_0() -> Result<(), TestError> {
+    // Renters are sent from rayon threads, so the subscriber has to be global
+    trace_to_json_file_globally("test_integration_0")?;
+
     let daily_rent_price: u64 = 2000;
     let max_renters: u32 = 8000;
     let max_rent_duration: u8 = 3;
@@ -23,11 +64,48 @@ fn test_integration_0() -> Result<(), TestError> {
     let rent_amount: u16 = 1;
     let rent_duration: u8 = 1;
     let fee: u32 = 1000;
+    let compute_budget = rent_compute_budget()?;
+    let priority_fee = priority_fee(&compute_budget);
 
     let renter_sol_token_amount = daily_rent_price
         .checked_mul((rent_duration as u64) * (rent_amount as u64))
         .unwrap_or_default();
-    let test_state = RpcState::initialize(max_renters, renter_sol_token_amount)?;
+    // RENFT_PROGRESS_BAR shows the deploy on a spinner line while it is recorded
+    let deploy_progress = if std::env::var(PROGRESS_BAR_VAR).is_ok() {
+        RecordingProgress::with_display(Box::new(BarProgress::default()))
+    } else {
+        RecordingProgress::default()
+    };
+    let test_state = RpcState::initialize_with_progress(
+        max_renters,
+        renter_sol_token_amount,
+        SendConfig::default(),
+        &deploy_progress,
+    )?;
+
+    let deploy_events = deploy_progress.events();
+    // Clears the spinner before the rest of the test prints
+    drop(deploy_progress);
+    assert_eq!(
+        deploy_events.last(),
+        Some(&ProgressEvent::Stage("Program deployed".to_string()))
+    );
+    let blockhash_expiries = deploy_events
+        .iter()
+        .filter(|event| matches!(event, ProgressEvent::BlockhashExpired { .. }))
+        .count();
+    if let Some(ProgressEvent::Status {
+        confirmed, total, ..
+    }) = deploy_events
+        .iter()
+        .rev()
+        .find(|event| matches!(event, ProgressEvent::Status { .. }))
+    {
+        println!(
+            "Deploy: {}/{} write transactions confirmed, {} blockhash expiries",
+            confirmed, total, blockhash_expiries
+        );
+    }
 
     println!("Initialize admin state");
     rpc_initialize_admin_state(fee, &test_state)?;
@@ -60,12 +138,23 @@ fn test_integration_0() -> Result<(), TestError> {
         &test_state,
     )?;
 
+    // Every renter pays for its own rent transaction, which it signs alone
+    let base_fee = test_state
+        .rpc_bench
+        .get_fee(&[], &test_state.renter_keypair.pubkey())?;
     let number_renters = Arc::new(Mutex::new(0_usize));
+    let priority_fees_paid = Arc::new(Mutex::new(0_u64));
 
     let rent_by_index = |i: usize| -> Result<(), TestError> {
         println!("Start renting {}", i);
+        let _renter = tracing::info_span!("renter", index = i).entered();
         let renter_keypair = Keypair::new();
         let renter_temp_sol_token_account_keypair = Keypair::new();
+        labels::register(&renter_keypair.pubkey(), &format!("renter_{}", i));
+        labels::register(
+            &renter_temp_sol_token_account_keypair.pubkey(),
+            &format!("renter_temp_sol_token_account_{}", i),
+        );
         test_state
             .rpc_bench
             .airdrop(vec![renter_keypair.pubkey()])?;
@@ -76,6 +165,7 @@ fn test_integration_0() -> Result<(), TestError> {
             renter_sol_token_amount,
         )?;
 
+        let renter_lamports = test_state.rpc_bench.get_lamports(&renter_keypair.pubkey());
         rpc_rent_impl(
             rent_amount,
             rent_duration,
@@ -83,8 +173,17 @@ fn test_integration_0() -> Result<(), TestError> {
             &renter_temp_sol_token_account_keypair.pubkey(),
             &test_state.pda_sol_token_account_keypair.pubkey(),
             &test_state.escrow_state_account_keypair.pubkey(),
+            &compute_budget,
             &test_state,
         )?;
+        let paid = renter_lamports - test_state.rpc_bench.get_lamports(&renter_keypair.pubkey());
+        if paid != base_fee + priority_fee {
+            return Err(TestError::TestError(format!(
+                "Renter {} paid {} lamports for its rent, expected a fee of {} and a priority fee of {}",
+                i, paid, base_fee, priority_fee
+            )));
+        }
+        *priority_fees_paid.lock().unwrap() += paid - base_fee;
         let number_renters_clone = Arc::clone(&number_renters);
         let mut number_renters_data = number_renters_clone.lock().unwrap();
         *number_renters_data += 1;
@@ -95,8 +194,21 @@ fn test_integration_0() -> Result<(), TestError> {
         .try_for_each(|i| rent_by_index(i))
         .map_err(|e| {
             println!("Number of Renters: {}", *number_renters.lock().unwrap());
+            println!(
+                "Transactions traced to {}",
+                trace_file_path("test_integration_0").display()
+            );
             return TestError::UnexpectedError(Box::new(e));
         })?;
 
+    let priority_fees_paid = *priority_fees_paid.lock().unwrap();
+    println!(
+        "Rent: {} renters, {} lamports of priority fees at {} micro-lamports per unit",
+        *number_renters.lock().unwrap(),
+        priority_fees_paid,
+        compute_budget.unit_price.unwrap_or_default()
+    );
+    assert_eq!(priority_fees_paid, priority_fee * max_renters as u64);
+
     Ok(())
 }
@@ -0,0 +1,58 @@
+mod common;
+use {
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program::program_pack::Pack,
+    solana_program_test::tokio,
+};
+
+async fn setup() -> Result<State, TestError> {
+    Fixture::new(LISTING).build(Stage::Rented).await
+}
+
+// Adds a token to the PDA account behind the program's back, so that it no longer matches the
+// prepaid rent of the rentings.
+async fn add_dust_to_pda(test_state: &mut State) -> Result<(), TestError> {
+    let pda_sol_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Sol);
+    let mut account = test_state
+        .bench
+        .get_account(&pda_sol_token_account_pubkey)
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    let mut token_account = spl_token::state::Account::unpack(&account.data)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    token_account.amount += 1;
+    spl_token::state::Account::pack(token_account, &mut account.data)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    test_state
+        .bench
+        .set_account(&pda_sol_token_account_pubkey, &account)
+        .await;
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "pda_sol_token_account holds 6001 but 6000 of prepaid rent is outstanding"
+)]
+async fn test_invariants_pda_balance_violation() {
+    let mut test_state = setup().await.unwrap();
+    add_dust_to_pda(&mut test_state).await.unwrap();
+
+    tracing::info!("Set fee");
+    set_fee(LISTING.fee, &mut test_state).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_invariants_disabled() -> Result<(), TestError> {
+    let mut test_state = setup().await?;
+    add_dust_to_pda(&mut test_state).await?;
+
+    test_state.invariants.enabled = false;
+    tracing::info!("Set fee");
+    set_fee(LISTING.fee, &mut test_state).await?;
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+mod common;
+use {
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::labels::{account_dump, balance_deltas, label, substitute},
+    crate::common::state::Currency,
+    solana_program_test::tokio,
+    solana_sdk::{pubkey::Pubkey, signature::Signer},
+};
+
+#[tokio::test]
+async fn test_labels_fixture() -> Result<(), TestError> {
+    let test_state = Fixture::new(LISTING).build(Stage::Created).await?;
+    let lender_pubkey = test_state.lender_keypair.pubkey();
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+
+    assert_eq!(label(&lender_pubkey), Some("lender".to_string()));
+    assert_eq!(
+        label(&test_state.pda_token_account_pubkey(Currency::Sol)),
+        Some("pda_sol_token_account".to_string())
+    );
+    assert_eq!(
+        substitute(&format!(
+            "transfer from {} to {}: ok",
+            lender_pubkey, escrow_state_account_pubkey
+        )),
+        "transfer from lender to escrow_state_account: ok"
+    );
+    // Words that are not labelled pubkeys are left alone
+    let unlabelled = Pubkey::new_unique().to_string();
+    assert_eq!(substitute(&unlabelled), unlabelled);
+
+    // Errors are labelled when displayed, not only where the error helpers print them
+    let error = TestError::TestError(format!("Account not found: {}", lender_pubkey));
+    assert_eq!(error.to_string(), "Account not found: lender");
+    assert_eq!(
+        format!("{:?}", error),
+        "TestError(\"Account not found: lender\")"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_labels_rent_report() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = Fixture::new(LISTING).build(Stage::Lent).await?;
+
+    let addresses = [
+        test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        test_state.pda_token_account_pubkey(Currency::Sol),
+        test_state.lender_token_account_pubkey(Currency::Sol),
+    ];
+    let before = test_state.bench.snapshot_accounts(&addresses).await;
+    LISTING.rent(Currency::Sol, &mut test_state).await?;
+    let after = test_state.bench.snapshot_accounts(&addresses).await;
+
+    let report = balance_deltas(&before, &after);
+    println!("{}", report);
+    assert_eq!(
+        report,
+        format!(
+            "renter_temp_sol_token_account: +0 lamports, -{} tokens\n\
+             pda_sol_token_account: +0 lamports, +{} tokens",
+            prepaid_rent, prepaid_rent
+        )
+    );
+
+    let dump = account_dump(&addresses[1], &after[1].1);
+    assert!(dump.starts_with("pda_sol_token_account: "));
+    assert!(dump.contains("owner token_program"));
+    assert!(dump.ends_with(&format!(", {} tokens", prepaid_rent)));
+
+    Ok(())
+}
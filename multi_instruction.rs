@@ -0,0 +1,213 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::{
+        test_error_to_indexed_program_error, test_error_to_program_error, TestError,
+    },
+    crate::common::fee::get_fee_split,
+    crate::common::fixture::{Fixture, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{
+        admin::TOKEN_ACCOUNTS_LEN, error::EscrowError, renting::Renting,
+    },
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+};
+
+async fn setup(renter_sol_token_amount: u64) -> Result<State, TestError> {
+    Fixture::new(LISTING)
+        .renter_sol_token_amount(renter_sol_token_amount)
+        .build(Stage::Lent)
+        .await
+}
+
+#[tokio::test]
+async fn test_multi_instruction_wrap_and_rent() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup(0).await?;
+    let renter_temp_sol_token_account_keypair = Keypair::new();
+
+    tracing::info!("Wrapping too little SOL and renting in one transaction");
+    let (index, _) = test_error_to_indexed_program_error(
+        wrap_and_rent(
+            LISTING.rent_amount,
+            LISTING.rent_duration,
+            prepaid_rent - 1,
+            &renter_temp_sol_token_account_keypair,
+            &mut test_state,
+        )
+        .await,
+    );
+    assert_eq!(index, 2);
+    // The account created by the first instruction is rolled back with the failed rent
+    assert!(test_state
+        .bench
+        .get_account(&renter_temp_sol_token_account_keypair.pubkey())
+        .await
+        .is_none());
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+
+    tracing::info!("Wrapping SOL and renting in one transaction");
+    wrap_and_rent(
+        LISTING.rent_amount,
+        LISTING.rent_duration,
+        prepaid_rent,
+        &renter_temp_sol_token_account_keypair,
+        &mut test_state,
+    )
+    .await?;
+
+    let renter_temp_sol_token_account = test_state
+        .bench
+        .get_token_account(&renter_temp_sol_token_account_keypair.pubkey())
+        .await?;
+    assert_eq!(renter_temp_sol_token_account.amount, 0);
+    let pda_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.pda_token_account_pubkey(Currency::Sol))
+        .await?;
+    assert_eq!(pda_sol_token_account.amount, prepaid_rent);
+    assert_eq!(
+        test_state.find_valid_renting().await?.rent_duration,
+        LISTING.rent_duration
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_instruction_stop_rent_and_close() -> Result<(), TestError> {
+    let days_charged: u8 = 1;
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup(prepaid_rent).await?;
+
+    tracing::info!("Start renting");
+    let rented_at = LISTING
+        .rent(Currency::Sol, &mut test_state)
+        .await?
+        .rented_at;
+    test_state
+        .bench
+        .set_unix_timestamp(rented_at + SECONDS_IN_DAY * (days_charged as i64))
+        .await?;
+
+    let renter_pubkey = test_state.renter_keypair.pubkey();
+    let renter_sol_token_account_pubkey = test_state.renter_token_account_pubkey(Currency::Sol);
+    let renter_lamports = test_state.bench.get_lamports(&renter_pubkey).await;
+    let renter_sol_token_account_lamports = test_state
+        .bench
+        .get_lamports(&renter_sol_token_account_pubkey)
+        .await;
+
+    tracing::info!("Stop renting and close the refund account in one transaction");
+    stop_rent_and_close(rented_at, &mut test_state).await?;
+
+    let expected = LISTING.partial_fee_split(days_charged)?;
+    assert!(test_state
+        .bench
+        .get_account(&renter_sol_token_account_pubkey)
+        .await
+        .is_none());
+    test_state
+        .bench
+        .assert_lamports(
+            &renter_pubkey,
+            renter_lamports + renter_sol_token_account_lamports + expected.renter,
+        )
+        .await;
+
+    let lender_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.lender_token_account_pubkey(Currency::Sol))
+        .await?;
+    assert_eq!(lender_sol_token_account.amount, expected.lender);
+    let admin_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.admin_token_account_pubkey(Currency::Sol))
+        .await?;
+    assert_eq!(admin_sol_token_account.amount, expected.admin);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_instruction_error_index() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup(prepaid_rent * 2).await?;
+    let admin_keypair = clone_keypair(&test_state.admin_keypair);
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+    let admin_state_account_pubkey = test_state.admin_state_account_keypair.pubkey();
+
+    tracing::info!("Set fee followed by an unauthorized set fee");
+    let instructions = [
+        set_fee_instruction(
+            LISTING.fee / 2,
+            &admin_keypair.pubkey(),
+            &admin_state_account_pubkey,
+            &test_state.program_id,
+        )?,
+        set_fee_instruction(
+            0,
+            &lender_keypair.pubkey(),
+            &admin_state_account_pubkey,
+            &test_state.program_id,
+        )?,
+    ];
+    let result = process_instructions(
+        &instructions,
+        &admin_keypair,
+        &[&lender_keypair],
+        &mut test_state,
+    )
+    .await;
+    assert_eq!(
+        test_error_to_indexed_program_error(result),
+        (1, EscrowError::AddressUnauthorized.into())
+    );
+    let mut token_accounts = [0; TOKEN_ACCOUNTS_LEN];
+    assert_eq!(
+        test_state
+            .bench
+            .get_admin_state_account(&admin_state_account_pubkey, &mut token_accounts)
+            .await?,
+        LISTING.fee
+    );
+
+    tracing::info!("Renting twice in one transaction");
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let instruction = rent_instruction(
+        LISTING.rent_amount,
+        LISTING.rent_duration,
+        &renter_keypair.pubkey(),
+        &test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    let result = process_instructions(
+        &[instruction.clone(), instruction],
+        &renter_keypair,
+        &[],
+        &mut test_state,
+    )
+    .await;
+    let (index, program_error) = test_error_to_indexed_program_error(result);
+    assert_eq!(index, 1);
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+    assert_eq!(get_fee_split(&mut test_state).await?.total(), 0);
+
+    // The same error as a lone instruction reports, only at another index
+    LISTING.rent(Currency::Sol, &mut test_state).await?;
+    test_state.bench.next_slot().await?;
+    assert_eq!(
+        program_error,
+        test_error_to_program_error(
+            rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state).await
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::fee::get_pda_balance,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program::pubkey::Pubkey,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{admin::TOKEN_ACCOUNTS_LEN, state::Escrow},
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+};
+
+async fn get_account_data(test_state: &mut State, address: &Pubkey) -> Result<Vec<u8>, TestError> {
+    Ok(test_state
+        .bench
+        .get_account(address)
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?
+        .data)
+}
+
+async fn get_escrow(test_state: &mut State) -> Result<Escrow, TestError> {
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+    let mut data = get_account_data(test_state, &escrow_state_account_pubkey).await?;
+    let mut escrow_info = Escrow::new();
+
+    let escrow_info = Escrow::unpack(&mut data, &mut escrow_info)
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    Ok(escrow_info)
+}
+
+#[tokio::test]
+async fn test_reinit_admin_state() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+    let admin_state_account_pubkey = test_state.admin_state_account_keypair.pubkey();
+    let admin_state = get_account_data(&mut test_state, &admin_state_account_pubkey).await?;
+
+    tracing::info!("Initialize admin state again");
+    assert!(is_instruction_error(
+        &initialize_admin_state(LISTING.fee / 2, &mut test_state).await
+    ));
+
+    tracing::info!("Initialize admin state again with another signer");
+    for keypair in [
+        clone_keypair(&test_state.lender_keypair),
+        clone_keypair(&test_state.renter_keypair),
+    ]
+    .iter()
+    {
+        assert!(is_instruction_error(
+            &initialize_admin_state_impl(0, keypair, &admin_state_account_pubkey, &mut test_state)
+                .await
+        ));
+    }
+
+    assert_eq!(
+        get_account_data(&mut test_state, &admin_state_account_pubkey).await?,
+        admin_state
+    );
+    let mut token_accounts = [0; TOKEN_ACCOUNTS_LEN];
+    let fee = test_state
+        .bench
+        .get_admin_state_account(&admin_state_account_pubkey, &mut token_accounts)
+        .await?;
+    assert_eq!(fee, LISTING.fee);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reinit_double_lend() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+
+    tracing::info!("Start lending");
+    LISTING.lend(Currency::Sol, &mut test_state).await?;
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+    let escrow_state = get_account_data(&mut test_state, &escrow_state_account_pubkey).await?;
+
+    test_state.bench.next_slot().await?;
+    tracing::info!("Start lending again with the same terms");
+    assert!(is_instruction_error(
+        &lend(
+            LISTING.daily_rent_price,
+            LISTING.max_renters,
+            LISTING.max_rent_duration,
+            &mut test_state,
+        )
+        .await
+    ));
+
+    tracing::info!("Start lending again with other terms");
+    assert!(is_instruction_error(
+        &lend(1, LISTING.max_renters, u8::MAX, &mut test_state).await
+    ));
+
+    tracing::info!("Start lending again with another signer");
+    assert!(is_instruction_error(
+        &lend_impl(
+            1,
+            LISTING.max_renters,
+            u8::MAX,
+            &clone_keypair(&test_state.renter_keypair),
+            &test_state.lender_temp_nft_account_keypair.pubkey(),
+            &test_state.renter_token_account_pubkey(Currency::Sol),
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            &escrow_state_account_pubkey,
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await
+    ));
+
+    assert_eq!(
+        get_account_data(&mut test_state, &escrow_state_account_pubkey).await?,
+        escrow_state
+    );
+
+    tracing::info!("Start renting");
+    LISTING.rent(Currency::Sol, &mut test_state).await?;
+    let escrow_info = get_escrow(&mut test_state).await?;
+    assert_eq!(escrow_info.daily_rent_price, LISTING.daily_rent_price);
+    assert_eq!(escrow_info.max_rent_duration, LISTING.max_rent_duration);
+    assert_eq!(escrow_info.current_renters, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reinit_lend_after_stop_lend() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+
+    tracing::info!("Start lending");
+    LISTING.lend(Currency::Sol, &mut test_state).await?;
+    tracing::info!("Stop lending");
+    stop_lend(&mut test_state).await?;
+
+    tracing::info!("Creating a second NFT for lender");
+    let nft_keypair = test_state
+        .bench
+        .create_mint(
+            &test_state.minter_keypair.pubkey(),
+            true,
+            &clone_keypair(&test_state.minter_keypair),
+        )
+        .await?;
+    let lender_temp_nft_account_keypair = Keypair::new();
+    test_state
+        .bench
+        .create_token_account(
+            nft_keypair.pubkey(),
+            &clone_keypair(&test_state.lender_keypair),
+            &lender_temp_nft_account_keypair,
+        )
+        .await?;
+    test_state
+        .bench
+        .mint_tokens(
+            lender_temp_nft_account_keypair.pubkey(),
+            &nft_keypair,
+            &clone_keypair(&test_state.minter_keypair),
+            LISTING.max_renters.into(),
+        )
+        .await?;
+
+    // Whatever stopping the listing left behind, closed or not, must stay as it is
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+    let escrow_state = test_state
+        .bench
+        .get_account(&escrow_state_account_pubkey)
+        .await
+        .map(|account| account.data);
+
+    tracing::info!("Start lending the second NFT on the same escrow account");
+    assert!(is_instruction_error(
+        &lend_impl(
+            LISTING.daily_rent_price,
+            LISTING.max_renters,
+            LISTING.max_rent_duration,
+            &clone_keypair(&test_state.lender_keypair),
+            &lender_temp_nft_account_keypair.pubkey(),
+            &test_state.lender_token_account_pubkey(Currency::Sol),
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            &escrow_state_account_pubkey,
+            &test_state.admin_state_account_keypair.pubkey(),
+            &mut test_state,
+        )
+        .await
+    ));
+
+    let lender_temp_nft_account = test_state
+        .bench
+        .get_token_account(&lender_temp_nft_account_keypair.pubkey())
+        .await?;
+    assert_eq!(lender_temp_nft_account.amount, LISTING.max_renters as u64);
+    assert_eq!(
+        lender_temp_nft_account.owner,
+        test_state.lender_keypair.pubkey()
+    );
+    assert_eq!(
+        test_state
+            .bench
+            .get_account(&escrow_state_account_pubkey)
+            .await
+            .map(|account| account.data),
+        escrow_state
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reinit_rent_never_lent() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+    let escrow_state = get_account_data(&mut test_state, &escrow_state_account_pubkey).await?;
+
+    tracing::info!("Renting from an escrow that was never lent");
+    assert!(is_instruction_error(
+        &rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state).await
+    ));
+
+    assert_eq!(
+        get_account_data(&mut test_state, &escrow_state_account_pubkey).await?,
+        escrow_state
+    );
+    let renter_temp_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.renter_temp_token_account_pubkey(Currency::Sol))
+        .await?;
+    assert_eq!(
+        renter_temp_sol_token_account.amount,
+        LISTING.prepaid_rent()?
+    );
+    assert_eq!(get_pda_balance(&mut test_state).await?, 0);
+
+    Ok(())
+}
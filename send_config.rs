@@ -0,0 +1,98 @@
+mod common;
+use {
+    crate::common::compute_budget::ComputeBudget,
+    crate::common::error::{test_error_to_program_error, TestError},
+    crate::common::renft::rent_instruction,
+    crate::common::rpc_client_utils::SendConfig,
+    crate::common::rpc_renft::{
+        rpc_initialize_admin_state, rpc_lend, rpc_rent, rpc_set_payable_account,
+    },
+    crate::common::rpc_state::RpcState,
+    solana_sdk::{
+        commitment_config::{CommitmentConfig, CommitmentLevel},
+        signature::Signer,
+    },
+    spl_token::error::TokenError,
+};
+
+const DAILY_RENT_PRICE: u64 = 2000;
+const MAX_RENTERS: u32 = 2;
+const MAX_RENT_DURATION: u8 = 3;
+const RENT_AMOUNT: u16 = 1;
+const RENT_DURATION: u8 = 1;
+const FEE: u32 = 1000;
+
+#[test]
+#[cfg_attr(tarpaulin, ignore)]
+fn test_send_config_finalized_with_preflight() -> Result<(), TestError> {
+    let prepaid_rent = DAILY_RENT_PRICE * (RENT_AMOUNT as u64) * (RENT_DURATION as u64);
+    let send_config = SendConfig {
+        skip_preflight: false,
+        preflight_commitment: Some(CommitmentLevel::Finalized),
+        commitment: CommitmentConfig::finalized(),
+        max_retries: Some(5),
+    };
+    let test_state = RpcState::initialize_with_send_config(MAX_RENTERS, prepaid_rent, send_config)?;
+
+    tracing::info!("Initialize admin state");
+    rpc_initialize_admin_state(FEE, &test_state)?;
+    tracing::info!("Set payable account");
+    rpc_set_payable_account(
+        test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state,
+    )?;
+    tracing::info!("Start lending");
+    rpc_lend(
+        DAILY_RENT_PRICE,
+        MAX_RENTERS,
+        MAX_RENT_DURATION,
+        &test_state,
+    )?;
+    tracing::info!("Start renting");
+    rpc_rent(RENT_AMOUNT, RENT_DURATION, &test_state)?;
+    let renting = test_state.find_valid_renting()?;
+
+    // The temporary SOL token account was drained by the first rent
+    tracing::info!("Renting again with preflight");
+    let error = rpc_rent(RENT_AMOUNT, RENT_DURATION, &test_state)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Transaction simulation failed"));
+
+    tracing::info!("Renting again without preflight");
+    let instruction = rent_instruction(
+        RENT_AMOUNT,
+        RENT_DURATION,
+        &test_state.renter_keypair.pubkey(),
+        &test_state.renter_temp_sol_token_account_keypair.pubkey(),
+        &test_state.pda_sol_token_account_keypair.pubkey(),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    let result = test_state.rpc_bench.process_transaction_with_config(
+        &[instruction],
+        Some(&test_state.renter_keypair.pubkey()),
+        &[&test_state.renter_keypair],
+        &ComputeBudget::default(),
+        &SendConfig {
+            skip_preflight: true,
+            ..send_config
+        },
+    );
+    // The transaction lands and is refused by the token program when the prepaid rent is
+    // transferred
+    assert_eq!(
+        test_error_to_program_error(result),
+        TokenError::InsufficientFunds.into()
+    );
+
+    assert!(test_state.find_valid_renting()? == renting);
+    let pda_sol_token_account = test_state
+        .rpc_bench
+        .get_token_account(&test_state.pda_sol_token_account_keypair.pubkey())?;
+    assert_eq!(pda_sol_token_account.amount, prepaid_rent);
+
+    Ok(())
+}
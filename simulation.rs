@@ -0,0 +1,168 @@
+mod common;
+use {
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::fee::{get_fee_split, FeeSplit},
+    crate::common::find_valid_renting,
+    crate::common::fixture::{Fixture, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::rpc_renft::{
+        rpc_initialize_admin_state, rpc_lend, rpc_rent, rpc_set_payable_account, rpc_simulate_rent,
+    },
+    crate::common::rpc_state::RpcState,
+    crate::common::state::{Currency, State},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::signature::Signer,
+};
+
+async fn setup() -> Result<State, TestError> {
+    Fixture::new(LISTING).build(Stage::Lent).await
+}
+
+#[tokio::test]
+async fn test_simulation_rent() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup().await?;
+    let pda_sol_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Sol);
+
+    tracing::info!("Simulating rent");
+    let simulation =
+        simulate_rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state).await?;
+    assert!(simulation.is_ok());
+    assert!(simulation.units_consumed > 0);
+    assert!(!simulation.logs.is_empty());
+    assert_eq!(
+        simulation
+            .token_account(&pda_sol_token_account_pubkey)?
+            .amount,
+        prepaid_rent
+    );
+
+    // Nothing was committed
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+    let pda_sol_token_account = test_state
+        .bench
+        .get_token_account(&pda_sol_token_account_pubkey)
+        .await?;
+    assert_eq!(pda_sol_token_account.amount, 0);
+
+    tracing::info!("Start renting");
+    LISTING.rent(Currency::Sol, &mut test_state).await?;
+    let pda_sol_token_account = test_state
+        .bench
+        .get_token_account(&pda_sol_token_account_pubkey)
+        .await?;
+    assert_eq!(pda_sol_token_account.amount, prepaid_rent);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(tarpaulin, ignore)]
+fn test_simulation_rpc_rent() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let test_state = RpcState::initialize(LISTING.max_renters, prepaid_rent)?;
+    let pda_sol_token_account_pubkey = test_state.pda_sol_token_account_keypair.pubkey();
+    let escrow_state_account_pubkey = test_state.escrow_state_account_keypair.pubkey();
+
+    tracing::info!("Initialize admin state");
+    rpc_initialize_admin_state(LISTING.fee, &test_state)?;
+    tracing::info!("Set payable account");
+    rpc_set_payable_account(
+        test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state,
+    )?;
+    tracing::info!("Start lending");
+    rpc_lend(
+        LISTING.daily_rent_price,
+        LISTING.max_renters,
+        LISTING.max_rent_duration,
+        &test_state,
+    )?;
+
+    tracing::info!("Simulating rent");
+    let simulation = rpc_simulate_rent(LISTING.rent_amount, LISTING.rent_duration, &test_state)?;
+    assert!(simulation.is_ok());
+    assert_eq!(
+        simulation
+            .token_account(&pda_sol_token_account_pubkey)?
+            .amount,
+        prepaid_rent
+    );
+
+    // Nothing was committed
+    assert!(test_state.find_valid_renting()? == Renting::NULL_RENTING);
+    assert_eq!(
+        test_state
+            .rpc_bench
+            .get_token_account(&pda_sol_token_account_pubkey)?
+            .amount,
+        0
+    );
+
+    tracing::info!("Start renting");
+    rpc_rent(LISTING.rent_amount, LISTING.rent_duration, &test_state)?;
+    let simulated_escrow_state_account = simulation
+        .account(&escrow_state_account_pubkey)
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    // The renting is timestamped when it lands, so the simulated one can differ in `rented_at`
+    let simulated_renting = find_valid_renting(&simulated_escrow_state_account.data)?;
+    let renting = test_state.find_valid_renting()?;
+    assert_eq!(simulated_renting.rent_amount, renting.rent_amount);
+    assert_eq!(simulated_renting.rent_duration, renting.rent_duration);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulation_preview_stop_rent_and_claim() -> Result<(), TestError> {
+    let days_charged: u8 = 1;
+    let mut test_state = setup().await?;
+    let renter_pubkey = test_state.renter_keypair.pubkey();
+    let renter_sol_token_account_pubkey = test_state.renter_token_account_pubkey(Currency::Sol);
+    let lender_sol_token_account_pubkey = test_state.lender_token_account_pubkey(Currency::Sol);
+    let admin_sol_token_account_pubkey = test_state.admin_token_account_pubkey(Currency::Sol);
+
+    tracing::info!("Start renting");
+    let renting = LISTING.rent(Currency::Sol, &mut test_state).await?;
+
+    tracing::info!("Simulating an early claim");
+    let simulation = simulate_claim(&renter_pubkey, renting.rented_at, &mut test_state).await?;
+    assert!(is_instruction_error(&simulation.result));
+    assert!(test_state.find_valid_renting().await? == renting);
+
+    test_state
+        .bench
+        .set_unix_timestamp(renting.rented_at + SECONDS_IN_DAY * (days_charged as i64))
+        .await?;
+
+    tracing::info!("Previewing the stop rent refund");
+    let expected = LISTING.partial_fee_split(days_charged)?;
+    let simulation = simulate_stop_rent(renting.rented_at, &mut test_state).await?;
+    assert!(simulation.is_ok());
+    let previewed = FeeSplit {
+        lender: simulation
+            .token_account(&lender_sol_token_account_pubkey)?
+            .amount,
+        admin: simulation
+            .token_account(&admin_sol_token_account_pubkey)?
+            .amount,
+        renter: simulation
+            .token_account(&renter_sol_token_account_pubkey)?
+            .amount,
+    };
+    assert_eq!(previewed, expected);
+    assert!(test_state.find_valid_renting().await? == renting);
+    assert_eq!(get_fee_split(&mut test_state).await?.total(), 0);
+
+    tracing::info!("Stop renting");
+    stop_rent(renting.rented_at, &mut test_state).await?;
+    assert_eq!(get_fee_split(&mut test_state).await?, expected);
+    let renter_sol_token_account = test_state
+        .bench
+        .get_token_account(&renter_sol_token_account_pubkey)
+        .await?;
+    assert_eq!(renter_sol_token_account.amount, expected.renter);
+
+    Ok(())
+}
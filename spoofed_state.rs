@@ -0,0 +1,706 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::{
+        test_error_to_instruction_error, test_error_to_program_error, TestError,
+    },
+    crate::common::fixture::{Fixture, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::spoof::{pack_admin_state, replace_pubkey, spoofed_account},
+    crate::common::state::{Currency, GenesisKeys, State},
+    solana_program::{
+        instruction::InstructionError, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    },
+    solana_program_test::tokio,
+    solana_renft_collateral_free::{admin::TOKEN_ACCOUNTS_LEN, error::EscrowError},
+    solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair},
+};
+
+// The attacker's keys exist before the fixture does, so that the accounts spoofed at genesis can
+// name them. Its token accounts are only created once the bank is running.
+struct Attacker {
+    keypair: Keypair,
+    sol_token_account_keypair: Keypair,
+    nft_account_keypair: Keypair,
+    program_id: Pubkey,
+}
+
+impl Attacker {
+    fn new() -> Self {
+        Self {
+            keypair: Keypair::new(),
+            sol_token_account_keypair: Keypair::new(),
+            nft_account_keypair: Keypair::new(),
+            program_id: Pubkey::new_unique(),
+        }
+    }
+
+    fn sol_token_account_pubkey(&self) -> Pubkey {
+        self.sol_token_account_keypair.pubkey()
+    }
+
+    fn nft_account_pubkey(&self) -> Pubkey {
+        self.nft_account_keypair.pubkey()
+    }
+}
+
+async fn setup<F>(attacker: &Attacker, stage: Stage, accounts: F) -> Result<State, TestError>
+where
+    F: FnOnce(&GenesisKeys) -> Vec<(Pubkey, Account)> + 'static,
+{
+    let mut test_state = Fixture::new(LISTING)
+        .genesis_accounts(accounts)
+        .build(stage)
+        .await?;
+
+    tracing::info!("Creating attacker accounts");
+    test_state
+        .bench
+        .airdrop(vec![attacker.keypair.pubkey()])
+        .await?;
+    test_state
+        .bench
+        .create_token_account(
+            test_state.sol_token_pubkey,
+            &attacker.keypair,
+            &attacker.sol_token_account_keypair,
+        )
+        .await?;
+    test_state
+        .bench
+        .create_token_account(
+            test_state.nft_keypair.pubkey(),
+            &attacker.keypair,
+            &attacker.nft_account_keypair,
+        )
+        .await?;
+    Ok(test_state)
+}
+
+// Long before any fixture's own renting, so that a forged escrow never holds a renting the
+// fixture could also look up
+const TEMPLATE_RENTED_AT: i64 = 1_600_000_000;
+
+// The bytes of a genuine escrow at `stage`, taken from a fixture of its own, with the keys of that
+// fixture and the time it was rented at, if it was.
+struct EscrowTemplate {
+    data: Vec<u8>,
+    keys: GenesisKeys,
+    rented_at: Option<i64>,
+}
+
+async fn escrow_template(stage: Stage) -> Result<EscrowTemplate, TestError> {
+    tracing::info!("Building a fixture to take escrow bytes from");
+    let mut test_state = Fixture::new(LISTING).build(stage.min(Stage::Lent)).await?;
+    let rented_at = if stage >= Stage::Rented {
+        test_state
+            .bench
+            .set_unix_timestamp(TEMPLATE_RENTED_AT)
+            .await?;
+        Some(
+            LISTING
+                .rent(Currency::Sol, &mut test_state)
+                .await?
+                .rented_at,
+        )
+    } else {
+        None
+    };
+    let data = test_state
+        .bench
+        .get_account(&test_state.escrow_state_account_keypair.pubkey())
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?
+        .data;
+    Ok(EscrowTemplate {
+        data,
+        keys: test_state.genesis_keys(),
+        rented_at,
+    })
+}
+
+impl EscrowTemplate {
+    // Escrow bytes that read as an initialized escrow of the fixture with `keys`, although that
+    // fixture never wrote them. With `take_over` the lender's role and payout account are the
+    // attacker's.
+    fn forge(&self, keys: &GenesisKeys, attacker: (Pubkey, Pubkey), take_over: bool) -> Vec<u8> {
+        let mut data = self.data.clone();
+        let (attacker_pubkey, attacker_sol_token_account_pubkey) = attacker;
+        let (lender, lender_sol_token_account) = if take_over {
+            (attacker_pubkey, attacker_sol_token_account_pubkey)
+        } else {
+            (keys.lender, keys.lender_sol_token_account)
+        };
+        assert_eq!(replace_pubkey(&mut data, &self.keys.lender, &lender), 1);
+        assert_eq!(
+            replace_pubkey(
+                &mut data,
+                &self.keys.lender_sol_token_account,
+                &lender_sol_token_account
+            ),
+            1
+        );
+        for (from, to) in [
+            (self.keys.renter, keys.renter),
+            (self.keys.pda_sol_token_account, keys.pda_sol_token_account),
+            (
+                self.keys.lender_temp_nft_account,
+                keys.lender_temp_nft_account,
+            ),
+        ]
+        .iter()
+        {
+            replace_pubkey(&mut data, from, to);
+        }
+        data
+    }
+}
+
+async fn get_token_amount(test_state: &mut State, address: &Pubkey) -> Result<u64, TestError> {
+    Ok(test_state.bench.get_token_account(address).await?.amount)
+}
+
+async fn attacker_claim(
+    test_state: &mut State,
+    attacker: &Attacker,
+    rented_at: i64,
+    escrow_state_account_pubkey: &Pubkey,
+    admin_state_account_pubkey: &Pubkey,
+) -> Result<(), TestError> {
+    claim_impl(
+        &test_state.renter_keypair.pubkey(),
+        rented_at,
+        &attacker.keypair,
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &attacker.sol_token_account_pubkey(),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        escrow_state_account_pubkey,
+        admin_state_account_pubkey,
+        test_state,
+    )
+    .await
+}
+
+// Sets the clock past the expiry of both the fixture's renting and the template's
+async fn expire(test_state: &mut State, rented_at: &[i64]) -> Result<(), TestError> {
+    let latest = rented_at.iter().copied().max().unwrap_or_default();
+    test_state
+        .bench
+        .set_unix_timestamp(LISTING.expires_at(latest))
+        .await
+}
+
+#[tokio::test]
+async fn test_spoofed_escrow_claim() -> Result<(), TestError> {
+    let template = escrow_template(Stage::Rented).await?;
+    let template_rented_at = template.rented_at.unwrap();
+    let attacker = Attacker::new();
+    let attacker_pubkeys = (
+        attacker.keypair.pubkey(),
+        attacker.sol_token_account_pubkey(),
+    );
+    let attacker_program_id = attacker.program_id;
+    let spoofed_escrow_state_account_pubkey = Pubkey::new_unique();
+    let mut test_state = setup(&attacker, Stage::Rented, move |keys| {
+        vec![(
+            spoofed_escrow_state_account_pubkey,
+            spoofed_account(
+                template.forge(keys, attacker_pubkeys, true),
+                &attacker_program_id,
+                &Rent::default(),
+            ),
+        )]
+    })
+    .await?;
+    let test_renting = test_state.find_valid_renting().await?;
+    expire(
+        &mut test_state,
+        &[test_renting.rented_at, template_rented_at],
+    )
+    .await?;
+
+    // Every check passes on the forged bytes, only the runtime stops the write to an escrow the
+    // program does not own
+    tracing::info!("Claim the forged renting of a foreign-owned escrow taken over by the attacker");
+    let admin_state_account_pubkey = test_state.admin_state_account_keypair.pubkey();
+    assert_eq!(
+        test_error_to_instruction_error(
+            attacker_claim(
+                &mut test_state,
+                &attacker,
+                template_rented_at,
+                &spoofed_escrow_state_account_pubkey,
+                &admin_state_account_pubkey,
+            )
+            .await
+        ),
+        InstructionError::ExternalAccountDataModified
+    );
+
+    tracing::info!("Claim the fixture's renting from the foreign-owned escrow");
+    assert_eq!(
+        test_error_to_program_error(
+            attacker_claim(
+                &mut test_state,
+                &attacker,
+                test_renting.rented_at,
+                &spoofed_escrow_state_account_pubkey,
+                &admin_state_account_pubkey,
+            )
+            .await
+        ),
+        EscrowError::IndexNotFound.into()
+    );
+
+    let pda_sol_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Sol);
+    assert_eq!(
+        get_token_amount(&mut test_state, &pda_sol_token_account_pubkey).await?,
+        LISTING.prepaid_rent()?
+    );
+    assert_eq!(
+        get_token_amount(&mut test_state, &attacker.sol_token_account_pubkey()).await?,
+        0
+    );
+
+    tracing::info!("Claim rent");
+    claim(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &mut test_state,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_spoofed_escrow_stop_lend() -> Result<(), TestError> {
+    let template = escrow_template(Stage::Lent).await?;
+    let attacker = Attacker::new();
+    let attacker_pubkeys = (
+        attacker.keypair.pubkey(),
+        attacker.sol_token_account_pubkey(),
+    );
+    let attacker_program_id = attacker.program_id;
+    let spoofed_escrow_state_account_pubkeys = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut test_state = setup(&attacker, Stage::Lent, move |keys| {
+        [false, true]
+            .iter()
+            .zip(spoofed_escrow_state_account_pubkeys.iter())
+            .map(|(take_over, address)| {
+                (
+                    *address,
+                    spoofed_account(
+                        template.forge(keys, attacker_pubkeys, *take_over),
+                        &attacker_program_id,
+                        &Rent::default(),
+                    ),
+                )
+            })
+            .collect()
+    })
+    .await?;
+
+    for (take_over, spoofed_escrow_state_account_pubkey) in [false, true]
+        .iter()
+        .zip(spoofed_escrow_state_account_pubkeys.iter())
+    {
+        tracing::info!(
+            "Stop lending with a foreign-owned escrow, taken over: {}",
+            take_over
+        );
+        let result = stop_lend_impl(
+            &attacker.keypair,
+            &test_state.lender_temp_nft_account_keypair.pubkey(),
+            &attacker.nft_account_pubkey(),
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            spoofed_escrow_state_account_pubkey,
+            &mut test_state,
+        )
+        .await;
+        if *take_over {
+            // The attacker is the escrow's lender, only the runtime stops the write
+            assert_eq!(
+                test_error_to_instruction_error(result),
+                InstructionError::ExternalAccountDataModified
+            );
+        } else {
+            // Rejected like any other signer that is not the lender
+            assert_eq!(
+                test_error_to_program_error(result),
+                ProgramError::InvalidAccountData
+            );
+        }
+    }
+
+    let lender_temp_nft_account_pubkey = test_state.lender_temp_nft_account_keypair.pubkey();
+    assert_eq!(
+        get_token_amount(&mut test_state, &lender_temp_nft_account_pubkey).await?,
+        LISTING.max_renters as u64
+    );
+    assert_eq!(
+        get_token_amount(&mut test_state, &attacker.nft_account_pubkey()).await?,
+        0
+    );
+
+    tracing::info!("Stop lending");
+    stop_lend(&mut test_state).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_spoofed_escrow_forged_initialized_rent() -> Result<(), TestError> {
+    let template = escrow_template(Stage::Lent).await?;
+    let attacker = Attacker::new();
+    let attacker_pubkeys = (
+        attacker.keypair.pubkey(),
+        attacker.sol_token_account_pubkey(),
+    );
+    let attacker_program_id = attacker.program_id;
+    let spoofed_escrow_state_account_pubkeys = [Pubkey::new_unique(), Pubkey::new_unique()];
+    // The fixture is never lent, so the escrows read as initialized only because they were forged
+    let mut test_state = setup(&attacker, Stage::AdminReady, move |keys| {
+        [false, true]
+            .iter()
+            .zip(spoofed_escrow_state_account_pubkeys.iter())
+            .map(|(take_over, address)| {
+                (
+                    *address,
+                    spoofed_account(
+                        template.forge(keys, attacker_pubkeys, *take_over),
+                        &attacker_program_id,
+                        &Rent::default(),
+                    ),
+                )
+            })
+            .collect()
+    })
+    .await?;
+
+    for (take_over, spoofed_escrow_state_account_pubkey) in [false, true]
+        .iter()
+        .zip(spoofed_escrow_state_account_pubkeys.iter())
+    {
+        tracing::info!(
+            "Renting from a forged initialized escrow, taken over: {}",
+            take_over
+        );
+        assert_eq!(
+            test_error_to_instruction_error(
+                rent_impl(
+                    LISTING.rent_amount,
+                    LISTING.rent_duration,
+                    &clone_keypair(&test_state.renter_keypair),
+                    &test_state.renter_temp_token_account_pubkey(Currency::Sol),
+                    &test_state.pda_token_account_pubkey(Currency::Sol),
+                    spoofed_escrow_state_account_pubkey,
+                    &mut test_state,
+                )
+                .await
+            ),
+            InstructionError::ExternalAccountDataModified
+        );
+    }
+
+    let renter_temp_sol_token_account_pubkey =
+        test_state.renter_temp_token_account_pubkey(Currency::Sol);
+    assert_eq!(
+        get_token_amount(&mut test_state, &renter_temp_sol_token_account_pubkey).await?,
+        LISTING.prepaid_rent()?
+    );
+    let pda_sol_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Sol);
+    assert_eq!(
+        get_token_amount(&mut test_state, &pda_sol_token_account_pubkey).await?,
+        0
+    );
+
+    tracing::info!("Start lending and renting");
+    LISTING.lend(Currency::Sol, &mut test_state).await?;
+    LISTING.rent(Currency::Sol, &mut test_state).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_spoofed_admin_state_foreign_owner() -> Result<(), TestError> {
+    let template = escrow_template(Stage::Rented).await?;
+    let template_rented_at = template.rented_at.unwrap();
+    let attacker = Attacker::new();
+    let attacker_pubkeys = (
+        attacker.keypair.pubkey(),
+        attacker.sol_token_account_pubkey(),
+    );
+    let attacker_program_id = attacker.program_id;
+    let spoofed_admin_state_account_pubkey = Pubkey::new_unique();
+    let spoofed_escrow_state_account_pubkey = Pubkey::new_unique();
+    let mut test_state = setup(&attacker, Stage::Rented, move |keys| {
+        let token_accounts = [0; TOKEN_ACCOUNTS_LEN];
+        vec![
+            (
+                spoofed_admin_state_account_pubkey,
+                spoofed_account(
+                    pack_admin_state(&token_accounts, 0, true),
+                    &attacker_program_id,
+                    &Rent::default(),
+                ),
+            ),
+            (
+                spoofed_escrow_state_account_pubkey,
+                spoofed_account(
+                    template.forge(keys, attacker_pubkeys, true),
+                    &attacker_program_id,
+                    &Rent::default(),
+                ),
+            ),
+        ]
+    })
+    .await?;
+    let test_renting = test_state.find_valid_renting().await?;
+    expire(
+        &mut test_state,
+        &[test_renting.rented_at, template_rented_at],
+    )
+    .await?;
+
+    // The admin token account passed is checked against the admin, not just against the admin
+    // state, so forging the admin state does not make the attacker's account payable
+    tracing::info!("Claim with a zero-fee admin state owned by another program");
+    assert_eq!(
+        test_error_to_program_error(
+            claim_impl(
+                &test_state.renter_keypair.pubkey(),
+                test_renting.rented_at,
+                &clone_keypair(&test_state.lender_keypair),
+                &test_state.pda_token_account_pubkey(Currency::Sol),
+                &test_state.lender_token_account_pubkey(Currency::Sol),
+                &attacker.sol_token_account_pubkey(),
+                &test_state.escrow_state_account_keypair.pubkey(),
+                &spoofed_admin_state_account_pubkey,
+                &mut test_state,
+            )
+            .await
+        ),
+        EscrowError::AddressUnauthorized.into()
+    );
+
+    tracing::info!("Claim with the attacker's escrow and admin state");
+    assert_eq!(
+        test_error_to_program_error(
+            attacker_claim(
+                &mut test_state,
+                &attacker,
+                template_rented_at,
+                &spoofed_escrow_state_account_pubkey,
+                &spoofed_admin_state_account_pubkey,
+            )
+            .await
+        ),
+        EscrowError::AddressUnauthorized.into()
+    );
+
+    let pda_sol_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Sol);
+    assert_eq!(
+        get_token_amount(&mut test_state, &pda_sol_token_account_pubkey).await?,
+        LISTING.prepaid_rent()?
+    );
+    assert_eq!(
+        get_token_amount(&mut test_state, &attacker.sol_token_account_pubkey()).await?,
+        0
+    );
+
+    tracing::info!("Claim rent");
+    claim(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &mut test_state,
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Payable accounts as stored in the admin state, with `payable_account` in the first slot
+fn token_accounts_paying(payable_account: &Pubkey) -> [u8; TOKEN_ACCOUNTS_LEN] {
+    let mut token_accounts = [0; TOKEN_ACCOUNTS_LEN];
+    token_accounts[..32].copy_from_slice(payable_account.as_ref());
+    token_accounts
+}
+
+#[tokio::test]
+async fn test_spoofed_admin_state_program_owned() -> Result<(), TestError> {
+    let attacker = Attacker::new();
+    let attacker_sol_token_account_pubkey = attacker.sol_token_account_pubkey();
+    let spoofed_admin_state_account_pubkey = Pubkey::new_unique();
+    // Owned by the escrow program but never written by it: `is_initialized`, the zero fee and the
+    // payable account are all forged
+    let mut test_state = setup(&attacker, Stage::Rented, move |keys| {
+        vec![(
+            spoofed_admin_state_account_pubkey,
+            spoofed_account(
+                pack_admin_state(
+                    &token_accounts_paying(&attacker_sol_token_account_pubkey),
+                    0,
+                    true,
+                ),
+                &keys.program_id,
+                &Rent::default(),
+            ),
+        )]
+    })
+    .await?;
+    let test_renting = test_state.find_valid_renting().await?;
+    expire(&mut test_state, &[test_renting.rented_at]).await?;
+
+    // Same check as for a foreign-owned admin state
+    tracing::info!("Claim with a forged admin state owned by the escrow program");
+    assert_eq!(
+        test_error_to_program_error(
+            claim_impl(
+                &test_state.renter_keypair.pubkey(),
+                test_renting.rented_at,
+                &clone_keypair(&test_state.lender_keypair),
+                &test_state.pda_token_account_pubkey(Currency::Sol),
+                &test_state.lender_token_account_pubkey(Currency::Sol),
+                &attacker.sol_token_account_pubkey(),
+                &test_state.escrow_state_account_keypair.pubkey(),
+                &spoofed_admin_state_account_pubkey,
+                &mut test_state,
+            )
+            .await
+        ),
+        EscrowError::AddressUnauthorized.into()
+    );
+
+    let pda_sol_token_account_pubkey = test_state.pda_token_account_pubkey(Currency::Sol);
+    assert_eq!(
+        get_token_amount(&mut test_state, &pda_sol_token_account_pubkey).await?,
+        LISTING.prepaid_rent()?
+    );
+    assert_eq!(
+        get_token_amount(&mut test_state, &attacker.sol_token_account_pubkey()).await?,
+        0
+    );
+
+    tracing::info!("Claim rent");
+    claim(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &mut test_state,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_spoofed_admin_state_fee_above_max() -> Result<(), TestError> {
+    let attacker = Attacker::new();
+    let spoofed_admin_state_account_pubkey = Pubkey::new_unique();
+    let mut test_state = setup(&attacker, Stage::AdminReady, move |keys| {
+        vec![(
+            spoofed_admin_state_account_pubkey,
+            spoofed_account(
+                pack_admin_state(
+                    &token_accounts_paying(&keys.admin_sol_token_account),
+                    20000,
+                    true,
+                ),
+                &keys.program_id,
+                &Rent::default(),
+            ),
+        )]
+    })
+    .await?;
+
+    // A fee above 10000 bps would leave the lender a negative share of the rent
+    tracing::info!("Start lending against an admin state with a fee of 20000 bps");
+    assert_eq!(
+        test_error_to_program_error(
+            lend_impl(
+                LISTING.daily_rent_price,
+                LISTING.max_renters,
+                LISTING.max_rent_duration,
+                &clone_keypair(&test_state.lender_keypair),
+                &test_state.lender_temp_nft_account_keypair.pubkey(),
+                &test_state.lender_token_account_pubkey(Currency::Sol),
+                &test_state.pda_token_account_pubkey(Currency::Sol),
+                &test_state.escrow_state_account_keypair.pubkey(),
+                &spoofed_admin_state_account_pubkey,
+                &mut test_state,
+            )
+            .await
+        ),
+        EscrowError::AmountOverflow.into()
+    );
+
+    tracing::info!("Start lending and renting");
+    LISTING.lend(Currency::Sol, &mut test_state).await?;
+    // Rent reads no admin state, so the renting goes ahead and the fee can only be taken when it
+    // is settled
+    let test_renting = LISTING.rent(Currency::Sol, &mut test_state).await?;
+
+    test_state
+        .bench
+        .set_unix_timestamp(test_renting.rented_at + SECONDS_IN_DAY + 1)
+        .await?;
+    tracing::info!("Stop renting with the 20000 bps admin state");
+    assert_eq!(
+        test_error_to_program_error(
+            stop_rent_impl(
+                test_renting.rented_at,
+                &clone_keypair(&test_state.renter_keypair),
+                &test_state.pda_token_account_pubkey(Currency::Sol),
+                &test_state.renter_token_account_pubkey(Currency::Sol),
+                &test_state.lender_token_account_pubkey(Currency::Sol),
+                &test_state.admin_token_account_pubkey(Currency::Sol),
+                &test_state.escrow_state_account_keypair.pubkey(),
+                &spoofed_admin_state_account_pubkey,
+                &mut test_state,
+            )
+            .await
+        ),
+        EscrowError::AmountOverflow.into()
+    );
+
+    expire(&mut test_state, &[test_renting.rented_at]).await?;
+    tracing::info!("Claim rent with the 20000 bps admin state");
+    assert_eq!(
+        test_error_to_program_error(
+            claim_impl(
+                &test_state.renter_keypair.pubkey(),
+                test_renting.rented_at,
+                &clone_keypair(&test_state.lender_keypair),
+                &test_state.pda_token_account_pubkey(Currency::Sol),
+                &test_state.lender_token_account_pubkey(Currency::Sol),
+                &test_state.admin_token_account_pubkey(Currency::Sol),
+                &test_state.escrow_state_account_keypair.pubkey(),
+                &spoofed_admin_state_account_pubkey,
+                &mut test_state,
+            )
+            .await
+        ),
+        EscrowError::AmountOverflow.into()
+    );
+
+    let pda_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.pda_token_account_pubkey(Currency::Sol))
+        .await?;
+    let admin_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.admin_token_account_pubkey(Currency::Sol))
+        .await?;
+    assert_eq!(pda_sol_token_account.amount, LISTING.prepaid_rent()?);
+    assert_eq!(admin_sol_token_account.amount, 0);
+
+    tracing::info!("Claim rent");
+    claim(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &mut test_state,
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,443 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    crate::common::substitution::{run_variants, Candidate, Scenario},
+    solana_program::{instruction::Instruction, pubkey::Pubkey, system_program},
+    solana_program_test::tokio,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+    std::future::Future,
+};
+
+struct OtherListing {
+    lender_keypair: Keypair,
+    pda_sol_token_account_pubkey: Pubkey,
+    escrow_state_account_pubkey: Pubkey,
+}
+
+async fn lend_as_other_lender(test_state: &mut State) -> Result<OtherListing, TestError> {
+    let lender_keypair = Keypair::new();
+    let lender_sol_token_account_keypair = Keypair::new();
+    let pda_sol_token_account_keypair = Keypair::new();
+    let lender_temp_nft_account_keypair = Keypair::new();
+
+    tracing::info!("Airdropping SOL to other lender");
+    test_state
+        .bench
+        .airdrop(vec![lender_keypair.pubkey()])
+        .await?;
+
+    tracing::info!("Creating and initializing mint account for other NFT");
+    let nft_keypair = test_state
+        .bench
+        .create_mint(
+            &test_state.minter_keypair.pubkey(),
+            true,
+            &test_state.minter_keypair,
+        )
+        .await?;
+
+    tracing::info!("Creating token accounts for other lender");
+    for (token, account) in [
+        (
+            test_state.sol_token_pubkey,
+            &lender_sol_token_account_keypair,
+        ),
+        (test_state.sol_token_pubkey, &pda_sol_token_account_keypair),
+        (nft_keypair.pubkey(), &lender_temp_nft_account_keypair),
+    ] {
+        test_state
+            .bench
+            .create_token_account(token, &lender_keypair, account)
+            .await?;
+    }
+    test_state
+        .bench
+        .mint_tokens(
+            lender_temp_nft_account_keypair.pubkey(),
+            &nft_keypair,
+            &test_state.minter_keypair,
+            LISTING.max_renters.into(),
+        )
+        .await?;
+
+    tracing::info!("Creating escrow state account for other lender");
+    let escrow_state_account_keypair = test_state
+        .bench
+        .create_escrow_state_account(LISTING.max_renters, &lender_keypair, test_state.program_id)
+        .await?;
+
+    tracing::info!("Other lender starts lending");
+    lend_impl(
+        LISTING.daily_rent_price,
+        LISTING.max_renters,
+        LISTING.max_rent_duration,
+        &lender_keypair,
+        &lender_temp_nft_account_keypair.pubkey(),
+        &lender_sol_token_account_keypair.pubkey(),
+        &pda_sol_token_account_keypair.pubkey(),
+        &escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        test_state,
+    )
+    .await?;
+
+    Ok(OtherListing {
+        lender_keypair,
+        pda_sol_token_account_pubkey: pda_sol_token_account_keypair.pubkey(),
+        escrow_state_account_pubkey: escrow_state_account_keypair.pubkey(),
+    })
+}
+
+fn candidates(test_state: &State, other_listing: Option<&OtherListing>) -> Vec<Candidate> {
+    let mut candidates = vec![
+        Candidate::new("random account", Pubkey::new_unique()),
+        Candidate::new("lender", test_state.lender_keypair.pubkey()),
+        Candidate::new("renter", test_state.renter_keypair.pubkey()),
+        Candidate::new("admin", test_state.admin_keypair.pubkey()),
+        Candidate::new("escrow PDA", test_state.pda_pubkey),
+        Candidate::new(
+            "PDA of another program",
+            Pubkey::find_program_address(&["escrow".as_bytes()], &spl_token::id()).0,
+        ),
+        Candidate::new(
+            "renter SOL token account",
+            test_state.renter_token_account_pubkey(Currency::Sol),
+        ),
+        Candidate::new(
+            "renter temporary SOL token account",
+            test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        ),
+        Candidate::new(
+            "lender USDC token account",
+            test_state.lender_token_account_pubkey(Currency::Usdc),
+        ),
+        Candidate::new(
+            "PDA USDC token account",
+            test_state.pda_token_account_pubkey(Currency::Usdc),
+        ),
+        Candidate::new(
+            "lender main NFT account",
+            test_state.lender_main_nft_account_pubkey(),
+        ),
+        Candidate::new(
+            "admin state account",
+            test_state.admin_state_account_keypair.pubkey(),
+        ),
+        Candidate::new(
+            "escrow state account",
+            test_state.escrow_state_account_keypair.pubkey(),
+        ),
+        Candidate::new("system program", system_program::id()),
+        Candidate::new("token program", spl_token::id()),
+    ];
+    if let Some(other_listing) = other_listing {
+        candidates.push(Candidate::new(
+            "other lender",
+            other_listing.lender_keypair.pubkey(),
+        ));
+        candidates.push(Candidate::new(
+            "other lender's escrow state account",
+            other_listing.escrow_state_account_pubkey,
+        ));
+        candidates.push(Candidate::new(
+            "other lender's PDA SOL token account",
+            other_listing.pda_sol_token_account_pubkey,
+        ));
+    }
+    candidates
+}
+
+fn scenario(
+    test_state: State,
+    instruction: Instruction,
+    free_accounts: &[usize],
+    equivalent_swaps: &[(usize, &str)],
+    other_listing: Option<OtherListing>,
+) -> Scenario {
+    let candidates = candidates(&test_state, other_listing.as_ref());
+    let mut keyring = vec![
+        clone_keypair(&test_state.lender_keypair),
+        clone_keypair(&test_state.renter_keypair),
+        clone_keypair(&test_state.admin_keypair),
+    ];
+    if let Some(other_listing) = other_listing {
+        keyring.push(other_listing.lender_keypair);
+    }
+    Scenario {
+        test_state,
+        instruction,
+        candidates,
+        free_accounts: free_accounts.to_vec(),
+        equivalent_swaps: equivalent_swaps
+            .iter()
+            .map(|(index, label)| (*index, label.to_string()))
+            .collect(),
+        keyring,
+    }
+}
+
+async fn assert_all_rejected<F, Fut>(build: F) -> Result<(), TestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Scenario, TestError>>,
+{
+    let report = run_variants(build).await?;
+
+    assert!(
+        report.accepted.is_empty(),
+        "{} substituted instructions were accepted:\n{}",
+        report.accepted.len(),
+        report.accepted.join("\n")
+    );
+    assert!(
+        report.not_executed.is_empty(),
+        "{} substituted instructions failed before the program ran:\n{}",
+        report.not_executed.len(),
+        report.not_executed.join("\n")
+    );
+    assert!(report.rejected > 0);
+    Ok(())
+}
+
+async fn setup_lending() -> Result<(State, OtherListing), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+    let other_listing = lend_as_other_lender(&mut test_state).await?;
+
+    tracing::info!("Start lending");
+    LISTING.lend(Currency::Sol, &mut test_state).await?;
+    Ok((test_state, other_listing))
+}
+
+async fn initialize_admin_state_scenario() -> Result<Scenario, TestError> {
+    let test_state = Fixture::new(LISTING).build(Stage::Created).await?;
+    let instruction = initialize_admin_state_instruction(
+        LISTING.fee,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    Ok(scenario(test_state, instruction, &[], &[], None))
+}
+
+#[tokio::test]
+async fn test_substitution_initialize_admin_state() -> Result<(), TestError> {
+    assert_all_rejected(initialize_admin_state_scenario).await
+}
+
+async fn set_fee_scenario() -> Result<Scenario, TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::Created).await?;
+    initialize_admin_state(LISTING.fee, &mut test_state).await?;
+    let instruction = set_fee_instruction(
+        LISTING.fee + 1,
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    Ok(scenario(test_state, instruction, &[], &[], None))
+}
+
+#[tokio::test]
+async fn test_substitution_set_fee() -> Result<(), TestError> {
+    assert_all_rejected(set_fee_scenario).await
+}
+
+async fn set_payable_account_scenario() -> Result<Scenario, TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::Created).await?;
+    initialize_admin_state(LISTING.fee, &mut test_state).await?;
+    let instruction = set_payable_account_instruction(
+        test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.admin_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.program_id,
+    )?;
+    // The payable token account is the admin's own choice
+    Ok(scenario(test_state, instruction, &[1], &[], None))
+}
+
+#[tokio::test]
+async fn test_substitution_set_payable_account() -> Result<(), TestError> {
+    assert_all_rejected(set_payable_account_scenario).await
+}
+
+async fn lend_scenario() -> Result<Scenario, TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+    let other_listing = lend_as_other_lender(&mut test_state).await?;
+    let instruction = lend_instruction(
+        LISTING.daily_rent_price,
+        LISTING.max_renters,
+        LISTING.max_rent_duration,
+        &test_state.lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    Ok(scenario(
+        test_state,
+        instruction,
+        &[],
+        &[],
+        Some(other_listing),
+    ))
+}
+
+#[tokio::test]
+async fn test_substitution_lend() -> Result<(), TestError> {
+    assert_all_rejected(lend_scenario).await
+}
+
+async fn edit_lend_scenario() -> Result<Scenario, TestError> {
+    let (test_state, other_listing) = setup_lending().await?;
+    let instruction = edit_lend_instruction(
+        LISTING.daily_rent_price + 1,
+        LISTING.max_rent_duration,
+        &test_state.lender_keypair.pubkey(),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    // The new PDA token account selects the currency, which the lender is free to change
+    Ok(scenario(
+        test_state,
+        instruction,
+        &[2],
+        &[],
+        Some(other_listing),
+    ))
+}
+
+#[tokio::test]
+async fn test_substitution_edit_lend() -> Result<(), TestError> {
+    assert_all_rejected(edit_lend_scenario).await
+}
+
+async fn stop_lend_scenario() -> Result<Scenario, TestError> {
+    let (test_state, other_listing) = setup_lending().await?;
+    let instruction = stop_lend_instruction(
+        &test_state.lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &test_state.lender_main_nft_account_pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    Ok(scenario(
+        test_state,
+        instruction,
+        &[],
+        &[],
+        Some(other_listing),
+    ))
+}
+
+#[tokio::test]
+async fn test_substitution_stop_lend() -> Result<(), TestError> {
+    assert_all_rejected(stop_lend_scenario).await
+}
+
+async fn rent_scenario() -> Result<Scenario, TestError> {
+    let (test_state, other_listing) = setup_lending().await?;
+    let instruction = rent_instruction(
+        LISTING.rent_amount,
+        LISTING.rent_duration,
+        &test_state.renter_keypair.pubkey(),
+        &test_state.renter_temp_token_account_pubkey(Currency::Sol),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    // The renter may pay from any of their SOL token accounts
+    Ok(scenario(
+        test_state,
+        instruction,
+        &[],
+        &[(0, "renter SOL token account")],
+        Some(other_listing),
+    ))
+}
+
+#[tokio::test]
+async fn test_substitution_rent() -> Result<(), TestError> {
+    assert_all_rejected(rent_scenario).await
+}
+
+async fn stop_rent_scenario() -> Result<Scenario, TestError> {
+    let (mut test_state, other_listing) = setup_lending().await?;
+    let test_renting = LISTING.rent(Currency::Sol, &mut test_state).await?;
+    let instruction = stop_rent_instruction(
+        test_renting.rented_at,
+        &test_state.renter_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.renter_token_account_pubkey(Currency::Sol),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    // The refund may go to any of the renter's SOL token accounts
+    Ok(scenario(
+        test_state,
+        instruction,
+        &[],
+        &[(1, "renter temporary SOL token account")],
+        Some(other_listing),
+    ))
+}
+
+#[tokio::test]
+async fn test_substitution_stop_rent() -> Result<(), TestError> {
+    assert_all_rejected(stop_rent_scenario).await
+}
+
+async fn claim_scenario() -> Result<Scenario, TestError> {
+    let (mut test_state, other_listing) = setup_lending().await?;
+    let test_renting = LISTING.rent(Currency::Sol, &mut test_state).await?;
+    test_state
+        .bench
+        .set_unix_timestamp(LISTING.expires_at(test_renting.rented_at))
+        .await?;
+    let instruction = claim_instruction(
+        &test_state.renter_keypair.pubkey(),
+        test_renting.rented_at,
+        &test_state.lender_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.lender_token_account_pubkey(Currency::Sol),
+        &test_state.admin_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &test_state.pda_pubkey,
+        &spl_token::id(),
+        &test_state.program_id,
+    )?;
+    Ok(scenario(
+        test_state,
+        instruction,
+        &[],
+        &[],
+        Some(other_listing),
+    ))
+}
+
+#[tokio::test]
+async fn test_substitution_claim() -> Result<(), TestError> {
+    assert_all_rejected(claim_scenario).await
+}
@@ -0,0 +1,74 @@
+mod common;
+use {
+    crate::common::error::TestError,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::telemetry::trace_file_path,
+    serde_json::Value,
+    solana_program_test::tokio,
+    std::fs,
+};
+
+fn read_trace(test_name: &str) -> Result<Vec<Value>, TestError> {
+    let trace = fs::read_to_string(trace_file_path(test_name))
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+    trace
+        .lines()
+        .map(|line| serde_json::from_str(line).map_err(|e| TestError::UnexpectedError(Box::new(e))))
+        .collect()
+}
+
+fn find_event<'a>(trace: &'a [Value], instruction: &str, message: &str) -> Option<&'a Value> {
+    trace.iter().find(|line| {
+        line["span"]["name"] == "transaction"
+            && line["span"]["instruction"] == instruction
+            && line["fields"]["message"] == message
+    })
+}
+
+#[tokio::test]
+async fn test_telemetry_rent() -> Result<(), TestError> {
+    // The fixture traces to a file named after the test
+    let test_name = "test_telemetry_rent";
+
+    let mut test_state = Fixture::new(LISTING).build(Stage::Rented).await?;
+    assert!(rent(
+        LISTING.rent_amount,
+        LISTING.max_rent_duration + 1,
+        &mut test_state
+    )
+    .await
+    .is_err());
+    drop(test_state);
+
+    let trace = read_trace(test_name)?;
+
+    let confirmed = find_event(&trace, "Rent", "confirmed").ok_or(TestError::TestError(
+        "No confirmed Rent transaction traced".to_string(),
+    ))?;
+    assert_eq!(confirmed["span"]["actor"], "renter");
+    assert!(confirmed["span"]["signature"].is_string());
+    assert!(confirmed["span"]["slot"].is_u64());
+    assert!(confirmed["span"]["elapsed_ms"].is_u64());
+    assert_eq!(confirmed["spans"][0]["name"], "operation");
+    assert_eq!(confirmed["spans"][0]["test"], test_name);
+
+    let failed = find_event(&trace, "Rent", "failed").ok_or(TestError::TestError(
+        "No failed Rent transaction traced".to_string(),
+    ))?;
+    assert!(failed["fields"]["error"].is_string());
+    assert_ne!(failed["span"]["signature"], confirmed["span"]["signature"]);
+
+    for instruction in ["InitializeAdminState", "SetPayableAccount", "Lend"].iter() {
+        assert!(find_event(&trace, instruction, "confirmed").is_some());
+    }
+
+    // The steps of the fixture are traced in the same span
+    assert!(trace.iter().any(|line| {
+        line["fields"]["message"] == "Start lending in Sol"
+            && line["span"]["name"] == "operation"
+            && line["span"]["test"] == test_name
+    }));
+
+    Ok(())
+}
@@ -0,0 +1,341 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::fee::get_pda_balance,
+    crate::common::fixture::{Fixture, Stage, LISTING},
+    crate::common::renft::*,
+    crate::common::rpc_state::RpcState,
+    crate::common::state::{Currency, State},
+    crate::common::token_2022::{get_mint_extension_types, MintExtension},
+    solana_program::{instruction::Instruction, pubkey::Pubkey},
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+    spl_token_2022::extension::ExtensionType,
+};
+
+const DECIMALS: u8 = 6;
+const TRANSFER_FEE: MintExtension = MintExtension::TransferFee {
+    transfer_fee_basis_points: 100,
+    maximum_fee: u64::MAX,
+};
+
+// Creates a Token-2022 mint with `extensions` and one funded account per owner.
+async fn create_funded_accounts(
+    test_state: &mut State,
+    decimals: u8,
+    extensions: &[MintExtension],
+    owners: &[&Keypair],
+    amount: u64,
+) -> Result<(Pubkey, Vec<Pubkey>), TestError> {
+    let minter_keypair = clone_keypair(&test_state.minter_keypair);
+    let mint_keypair = test_state
+        .bench
+        .create_mint_2022(&minter_keypair, true, decimals, extensions)
+        .await?;
+
+    let mut token_accounts = vec![];
+    for owner in owners {
+        let token_account_pubkey = test_state
+            .bench
+            .create_token_account_2022(mint_keypair.pubkey(), owner, &Keypair::new())
+            .await?;
+        if amount > 0 {
+            test_state
+                .bench
+                .mint_tokens_2022(
+                    token_account_pubkey,
+                    &mint_keypair.pubkey(),
+                    &minter_keypair,
+                    amount,
+                    decimals,
+                )
+                .await?;
+        }
+        token_accounts.push(token_account_pubkey);
+    }
+    Ok((mint_keypair.pubkey(), token_accounts))
+}
+
+// Escrow instructions in this file name the Token-2022 program, which the `_impl` helpers never do
+async fn process_instruction(
+    instruction: Instruction,
+    signer: &Keypair,
+    test_state: &mut State,
+) -> Result<(), TestError> {
+    test_state
+        .process_transaction(&[instruction], Some(&signer.pubkey()), &[signer])
+        .await
+}
+
+#[tokio::test]
+async fn test_token_2022_bench() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::Created).await?;
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+
+    tracing::info!("Creating a transfer fee mint");
+    let (mint_pubkey, token_accounts) = create_funded_accounts(
+        &mut test_state,
+        DECIMALS,
+        &[TRANSFER_FEE],
+        &[&lender_keypair, &renter_keypair],
+        10000,
+    )
+    .await?;
+    let mint = test_state
+        .bench
+        .get_account(&mint_pubkey)
+        .await
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    assert_eq!(mint.owner, spl_token_2022::id());
+    assert_eq!(
+        get_mint_extension_types(&mint.data)?,
+        vec![ExtensionType::TransferFeeConfig]
+    );
+
+    test_state
+        .bench
+        .transfer_tokens_2022(
+            &lender_keypair,
+            &token_accounts[0],
+            &mint_pubkey,
+            &token_accounts[1],
+            10000,
+            DECIMALS,
+        )
+        .await?;
+    let lender_token_account = test_state
+        .bench
+        .get_token_account_2022(&token_accounts[0])
+        .await?;
+    let renter_token_account = test_state
+        .bench
+        .get_token_account_2022(&token_accounts[1])
+        .await?;
+    assert_eq!(lender_token_account.amount, 0);
+    // 1% is withheld in the destination account
+    assert_eq!(renter_token_account.amount, 19900);
+
+    tracing::info!("Creating a non-transferable mint");
+    let (mint_pubkey, token_accounts) = create_funded_accounts(
+        &mut test_state,
+        0,
+        &[MintExtension::NonTransferable],
+        &[&lender_keypair, &renter_keypair],
+        1,
+    )
+    .await?;
+    assert!(test_state
+        .bench
+        .transfer_tokens_2022(
+            &lender_keypair,
+            &token_accounts[0],
+            &mint_pubkey,
+            &token_accounts[1],
+            1,
+            0,
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(tarpaulin, ignore)]
+fn test_token_2022_rpc_bench() -> Result<(), TestError> {
+    let test_state = RpcState::initialize(LISTING.max_renters, 0)?;
+    let minter_keypair = &test_state.minter_keypair;
+
+    tracing::info!("Creating a transfer fee mint");
+    let mint_keypair =
+        test_state
+            .rpc_bench
+            .create_mint_2022(minter_keypair, true, DECIMALS, &[TRANSFER_FEE])?;
+    let mint = test_state
+        .rpc_bench
+        .get_account(&mint_keypair.pubkey())
+        .ok_or(TestError::TestError("Account not found".to_string()))?;
+    assert_eq!(mint.owner, spl_token_2022::id());
+    assert_eq!(
+        get_mint_extension_types(&mint.data)?,
+        vec![ExtensionType::TransferFeeConfig]
+    );
+
+    let token_account_pubkey = test_state.rpc_bench.create_token_account_2022(
+        mint_keypair.pubkey(),
+        &test_state.lender_keypair,
+        &Keypair::new(),
+    )?;
+    test_state.rpc_bench.mint_tokens_2022(
+        token_account_pubkey,
+        &mint_keypair.pubkey(),
+        minter_keypair,
+        10000,
+        DECIMALS,
+    )?;
+
+    let token_account = test_state
+        .rpc_bench
+        .get_token_account_2022(&token_account_pubkey)?;
+    assert_eq!(token_account.mint, mint_keypair.pubkey());
+    assert_eq!(token_account.owner, test_state.lender_keypair.pubkey());
+    assert_eq!(token_account.amount, 10000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_token_2022_nft_lend() -> Result<(), TestError> {
+    let extension_sets: [&[MintExtension]; 2] = [&[], &[MintExtension::NonTransferable]];
+
+    for extensions in extension_sets.iter() {
+        let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+        let lender_keypair = clone_keypair(&test_state.lender_keypair);
+
+        tracing::info!("Creating a Token-2022 NFT with extensions {:?}", extensions);
+        let (_, nft_accounts) = create_funded_accounts(
+            &mut test_state,
+            0,
+            extensions,
+            &[&lender_keypair],
+            LISTING.max_renters as u64,
+        )
+        .await?;
+
+        // The escrow only drives the original token program, so the NFT never leaves the lender
+        tracing::info!("Start lending a Token-2022 NFT");
+        let instruction = lend_instruction(
+            LISTING.daily_rent_price,
+            LISTING.max_renters,
+            LISTING.max_rent_duration,
+            &lender_keypair.pubkey(),
+            &nft_accounts[0],
+            &test_state.lender_token_account_pubkey(Currency::Sol),
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.admin_state_account_keypair.pubkey(),
+            &spl_token_2022::id(),
+            &test_state.program_id,
+        )?;
+        assert!(is_instruction_error(
+            &process_instruction(instruction, &lender_keypair, &mut test_state).await
+        ));
+
+        let nft_account = test_state
+            .bench
+            .get_token_account_2022(&nft_accounts[0])
+            .await?;
+        assert_eq!(nft_account.owner, lender_keypair.pubkey());
+        assert_eq!(nft_account.amount, LISTING.max_renters as u64);
+
+        tracing::info!("Start lending the original NFT");
+        LISTING.lend(Currency::Sol, &mut test_state).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_token_2022_currency_lend() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+    let lender_keypair = clone_keypair(&test_state.lender_keypair);
+
+    tracing::info!("Creating Token-2022 currency accounts for lender and PDA");
+    let (_, token_accounts) = create_funded_accounts(
+        &mut test_state,
+        DECIMALS,
+        &[TRANSFER_FEE],
+        &[&lender_keypair, &lender_keypair],
+        0,
+    )
+    .await?;
+
+    tracing::info!("Start lending priced in a Token-2022 currency");
+    let instruction = lend_instruction(
+        LISTING.daily_rent_price,
+        LISTING.max_renters,
+        LISTING.max_rent_duration,
+        &lender_keypair.pubkey(),
+        &test_state.lender_temp_nft_account_keypair.pubkey(),
+        &token_accounts[0],
+        &token_accounts[1],
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &test_state.admin_state_account_keypair.pubkey(),
+        &spl_token_2022::id(),
+        &test_state.program_id,
+    )?;
+    assert!(is_instruction_error(
+        &process_instruction(instruction, &lender_keypair, &mut test_state).await
+    ));
+
+    let pda_token_account = test_state
+        .bench
+        .get_token_account_2022(&token_accounts[1])
+        .await?;
+    assert_eq!(pda_token_account.owner, lender_keypair.pubkey());
+    let lender_temp_nft_account = test_state
+        .bench
+        .get_token_account(&test_state.lender_temp_nft_account_keypair.pubkey())
+        .await?;
+    assert_eq!(lender_temp_nft_account.owner, lender_keypair.pubkey());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_token_2022_currency_rent() -> Result<(), TestError> {
+    let mut test_state = Fixture::new(LISTING).build(Stage::AdminReady).await?;
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let prepaid_rent = LISTING.prepaid_rent()?;
+
+    tracing::info!("Start lending");
+    LISTING.lend(Currency::Sol, &mut test_state).await?;
+
+    let extension_sets: [&[MintExtension]; 2] = [&[], &[TRANSFER_FEE]];
+    for extensions in extension_sets.iter() {
+        tracing::info!(
+            "Renting with a Token-2022 currency with extensions {:?}",
+            extensions
+        );
+        let (_, token_accounts) = create_funded_accounts(
+            &mut test_state,
+            DECIMALS,
+            extensions,
+            &[&renter_keypair],
+            prepaid_rent,
+        )
+        .await?;
+
+        let instruction = rent_instruction(
+            LISTING.rent_amount,
+            LISTING.rent_duration,
+            &renter_keypair.pubkey(),
+            &token_accounts[0],
+            &test_state.pda_token_account_pubkey(Currency::Sol),
+            &test_state.escrow_state_account_keypair.pubkey(),
+            &test_state.pda_pubkey,
+            &spl_token_2022::id(),
+            &test_state.program_id,
+        )?;
+        assert!(is_instruction_error(
+            &process_instruction(instruction, &renter_keypair, &mut test_state).await
+        ));
+
+        let renter_token_account = test_state
+            .bench
+            .get_token_account_2022(&token_accounts[0])
+            .await?;
+        assert_eq!(renter_token_account.amount, prepaid_rent);
+    }
+
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+    assert_eq!(get_pda_balance(&mut test_state).await?, 0);
+
+    tracing::info!("Start renting");
+    LISTING.rent(Currency::Sol, &mut test_state).await?;
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+mod common;
+use {
+    crate::common::error::TestError,
+    crate::common::rpc_renft::{
+        rpc_claim_instruction, rpc_claim_versioned, rpc_create_escrow_lookup_table,
+        rpc_initialize_admin_state, rpc_lend, rpc_rent, rpc_set_payable_account,
+        rpc_stop_rent_instruction, rpc_stop_rent_versioned,
+    },
+    crate::common::rpc_state::RpcState,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::{hash::Hash, message::v0, signature::Signer},
+};
+
+const DAILY_RENT_PRICE: u64 = 2000;
+const MAX_RENTERS: u32 = 1;
+const MAX_RENT_DURATION: u8 = 3;
+const RENT_AMOUNT: u16 = 1;
+const RENT_DURATION: u8 = 1;
+const FEE: u32 = 1000;
+
+#[test]
+#[cfg_attr(tarpaulin, ignore)]
+fn test_versioned_stop_rent_and_claim() -> Result<(), TestError> {
+    let prepaid_rent = DAILY_RENT_PRICE * (RENT_AMOUNT as u64) * (RENT_DURATION as u64);
+    let test_state = RpcState::initialize(MAX_RENTERS, prepaid_rent)?;
+
+    tracing::info!("Initialize admin state");
+    rpc_initialize_admin_state(FEE, &test_state)?;
+    tracing::info!("Set payable account");
+    rpc_set_payable_account(
+        test_state.admin_sol_token_account_keypair.pubkey(),
+        &test_state,
+    )?;
+    tracing::info!("Start lending");
+    rpc_lend(
+        DAILY_RENT_PRICE,
+        MAX_RENTERS,
+        MAX_RENT_DURATION,
+        &test_state,
+    )?;
+    tracing::info!("Start renting");
+    rpc_rent(RENT_AMOUNT, RENT_DURATION, &test_state)?;
+    let renting = test_state.find_valid_renting()?;
+
+    tracing::info!("Creating an address lookup table for the escrow accounts");
+    let lookup_table = rpc_create_escrow_lookup_table(&test_state)?;
+    assert_eq!(lookup_table.addresses.len(), 8);
+
+    // Only the signer and the program are left in the static account keys
+    let renter_pubkey = test_state.renter_keypair.pubkey();
+    let lender_pubkey = test_state.lender_keypair.pubkey();
+    for (payer, instruction) in [
+        (
+            renter_pubkey,
+            rpc_stop_rent_instruction(renting.rented_at, &test_state)?,
+        ),
+        (
+            lender_pubkey,
+            rpc_claim_instruction(&renter_pubkey, renting.rented_at, &test_state)?,
+        ),
+    ]
+    .iter()
+    {
+        let message = v0::Message::try_compile(
+            payer,
+            &[instruction.clone()],
+            &[lookup_table.clone()],
+            Hash::default(),
+        )
+        .map_err(|e| TestError::UnexpectedError(Box::new(e)))?;
+        assert_eq!(message.account_keys, vec![*payer, test_state.program_id]);
+    }
+
+    tracing::info!("Claiming an unexpired rent in a v0 transaction");
+    assert!(rpc_claim_versioned(
+        &renter_pubkey,
+        renting.rented_at,
+        &[lookup_table.clone()],
+        &test_state
+    )
+    .is_err());
+    assert!(test_state.find_valid_renting()? == renting);
+
+    tracing::info!("Stop renting in a v0 transaction");
+    rpc_stop_rent_versioned(renting.rented_at, &[lookup_table], &test_state)?;
+    assert!(test_state.find_valid_renting()? == Renting::NULL_RENTING);
+    let renter_sol_token_account = test_state
+        .rpc_bench
+        .get_token_account(&test_state.renter_sol_token_account_keypair.pubkey())?;
+    assert_eq!(renter_sol_token_account.amount, prepaid_rent);
+
+    Ok(())
+}
@@ -0,0 +1,235 @@
+mod common;
+use {
+    crate::common::clone_keypair,
+    crate::common::error::{is_instruction_error, TestError},
+    crate::common::fee::get_fee_split,
+    crate::common::fixture::{Fixture, Stage, LISTING, SECONDS_IN_DAY},
+    crate::common::renft::*,
+    crate::common::state::{Currency, State},
+    solana_program::program_pack::Pack,
+    solana_program_test::tokio,
+    solana_renft_collateral_free::renting::Renting,
+    solana_sdk::{signature::Signer, signer::keypair::Keypair},
+};
+
+async fn setup(renter_sol_token_amount: u64) -> Result<State, TestError> {
+    Fixture::new(LISTING)
+        .renter_sol_token_amount(renter_sol_token_amount)
+        .build(Stage::Lent)
+        .await
+}
+
+fn token_account_rent(test_state: &State) -> u64 {
+    test_state
+        .bench
+        .rent
+        .minimum_balance(spl_token::state::Account::get_packed_len())
+}
+
+#[tokio::test]
+async fn test_wrapped_sol_stop_rent_unwrap() -> Result<(), TestError> {
+    let days_charged: u8 = 1;
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup(prepaid_rent).await?;
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let reserve = token_account_rent(&test_state);
+
+    tracing::info!("Start renting");
+    let rented_at = LISTING
+        .rent(Currency::Sol, &mut test_state)
+        .await?
+        .rented_at;
+    test_state
+        .bench
+        .set_unix_timestamp(rented_at + SECONDS_IN_DAY * (days_charged as i64))
+        .await?;
+
+    tracing::info!("Stop renting");
+    stop_rent(rented_at, &mut test_state).await?;
+    let fee_split = get_fee_split(&mut test_state).await?;
+    assert_eq!(fee_split, LISTING.partial_fee_split(days_charged)?);
+    assert!(fee_split.renter > 0);
+
+    let renter_sol_token_account_pubkey = test_state.renter_token_account_pubkey(Currency::Sol);
+    test_state
+        .bench
+        .assert_lamports(&renter_sol_token_account_pubkey, reserve + fee_split.renter)
+        .await;
+
+    tracing::info!("Unwrapping the refund");
+    let renter_lamports = test_state
+        .bench
+        .get_lamports(&renter_keypair.pubkey())
+        .await;
+    let unwrapped = test_state
+        .bench
+        .unwrap_sol(&renter_keypair, &renter_sol_token_account_pubkey)
+        .await?;
+    assert_eq!(unwrapped, reserve + fee_split.renter);
+    test_state
+        .bench
+        .assert_lamports(
+            &renter_keypair.pubkey(),
+            renter_lamports + reserve + fee_split.renter,
+        )
+        .await;
+    assert!(test_state
+        .bench
+        .get_account(&renter_sol_token_account_pubkey)
+        .await
+        .is_none());
+
+    tracing::info!("Closing the drained temporary SOL token account");
+    let renter_temp_sol_token_account_pubkey =
+        test_state.renter_temp_token_account_pubkey(Currency::Sol);
+    assert_eq!(
+        test_state
+            .bench
+            .get_token_account(&renter_temp_sol_token_account_pubkey)
+            .await?
+            .amount,
+        0
+    );
+    let renter_lamports = test_state
+        .bench
+        .get_lamports(&renter_keypair.pubkey())
+        .await;
+    test_state
+        .bench
+        .close_token_account(
+            &renter_keypair,
+            &renter_temp_sol_token_account_pubkey,
+            &renter_keypair.pubkey(),
+        )
+        .await?;
+    test_state
+        .bench
+        .assert_lamports(&renter_keypair.pubkey(), renter_lamports + reserve)
+        .await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrapped_sol_top_up() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup(0).await?;
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let renter_temp_sol_token_account_pubkey =
+        test_state.renter_temp_token_account_pubkey(Currency::Sol);
+
+    tracing::info!("Renting from an empty temporary SOL token account");
+    assert!(is_instruction_error(
+        &rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state).await
+    ));
+
+    tracing::info!("Topping up the temporary SOL token account");
+    test_state
+        .bench
+        .top_up_wrapped_sol(
+            &renter_keypair,
+            &renter_temp_sol_token_account_pubkey,
+            prepaid_rent - 1,
+        )
+        .await?;
+    assert_eq!(
+        test_state
+            .bench
+            .get_token_account(&renter_temp_sol_token_account_pubkey)
+            .await?
+            .amount,
+        prepaid_rent - 1
+    );
+    test_state.bench.next_slot().await?;
+    assert!(is_instruction_error(
+        &rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state).await
+    ));
+
+    test_state
+        .bench
+        .top_up_wrapped_sol(&renter_keypair, &renter_temp_sol_token_account_pubkey, 1)
+        .await?;
+    assert_eq!(
+        test_state
+            .bench
+            .get_token_account(&renter_temp_sol_token_account_pubkey)
+            .await?
+            .amount,
+        prepaid_rent
+    );
+
+    tracing::info!("Start renting");
+    test_state.bench.next_slot().await?;
+    rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state).await?;
+    test_state
+        .bench
+        .assert_lamports(
+            &renter_temp_sol_token_account_pubkey,
+            token_account_rent(&test_state),
+        )
+        .await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrapped_sol_closed_temp_account() -> Result<(), TestError> {
+    let prepaid_rent = LISTING.prepaid_rent()?;
+    let mut test_state = setup(prepaid_rent).await?;
+    let renter_keypair = clone_keypair(&test_state.renter_keypair);
+    let renter_temp_sol_token_account_pubkey =
+        test_state.renter_temp_token_account_pubkey(Currency::Sol);
+
+    tracing::info!("Unwrapping the temporary SOL token account");
+    let renter_lamports = test_state
+        .bench
+        .get_lamports(&renter_keypair.pubkey())
+        .await;
+    let unwrapped = test_state
+        .bench
+        .unwrap_sol(&renter_keypair, &renter_temp_sol_token_account_pubkey)
+        .await?;
+    assert_eq!(unwrapped, token_account_rent(&test_state) + prepaid_rent);
+    test_state
+        .bench
+        .assert_lamports(&renter_keypair.pubkey(), renter_lamports + unwrapped)
+        .await;
+
+    tracing::info!("Renting from the closed temporary SOL token account");
+    assert!(
+        rent(LISTING.rent_amount, LISTING.rent_duration, &mut test_state)
+            .await
+            .is_err()
+    );
+    assert!(test_state.find_valid_renting().await? == Renting::NULL_RENTING);
+    let pda_sol_token_account = test_state
+        .bench
+        .get_token_account(&test_state.pda_token_account_pubkey(Currency::Sol))
+        .await?;
+    assert_eq!(pda_sol_token_account.amount, 0);
+
+    tracing::info!("Wrapping SOL into a new temporary SOL token account");
+    let renter_temp_sol_token_account_keypair = Keypair::new();
+    test_state
+        .bench
+        .wrap_sol(
+            &renter_keypair,
+            &renter_temp_sol_token_account_keypair,
+            prepaid_rent,
+        )
+        .await?;
+
+    tracing::info!("Start renting");
+    rent_impl(
+        LISTING.rent_amount,
+        LISTING.rent_duration,
+        &renter_keypair,
+        &renter_temp_sol_token_account_keypair.pubkey(),
+        &test_state.pda_token_account_pubkey(Currency::Sol),
+        &test_state.escrow_state_account_keypair.pubkey(),
+        &mut test_state,
+    )
+    .await?;
+
+    Ok(())
+}